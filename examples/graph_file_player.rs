@@ -0,0 +1,149 @@
+//! Plays an audio file through a reverb, with the file player, reverb and output units all
+//! connected within an **AuGraph**. Halfway through, the reverb is swapped for a delay without
+//! stopping playback.
+//!
+//! Usage: `cargo run --example graph_file_player -- path/to/file.wav`
+
+extern crate core_foundation_sys;
+extern crate coreaudio;
+
+use core_foundation_sys::base::{kCFAllocatorDefault, CFRelease};
+use core_foundation_sys::url::CFURLCreateFromFileSystemRepresentation;
+use coreaudio::audio_unit::graph::AuGraph;
+use coreaudio::audio_unit::{EffectType, Element, GeneratorType, IOType, Scope, Type};
+use coreaudio::sys;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::ptr;
+use std::time::Duration;
+
+/// The description of the Apple component of the given type.
+fn description<T: Into<Type>>(ty: T) -> sys::AudioComponentDescription {
+    let ty = ty.into();
+    sys::AudioComponentDescription {
+        componentType: ty.as_u32(),
+        componentSubType: ty.as_subtype_u32().unwrap(),
+        componentManufacturer: sys::kAudioUnitManufacturer_Apple,
+        componentFlags: 0,
+        componentFlagsMask: 0,
+    }
+}
+
+/// Open the audio file at the given path for reading.
+fn open_file(path: &PathBuf) -> Result<sys::AudioFileID, coreaudio::Error> {
+    let bytes = path.as_os_str().as_bytes();
+    let mut file: sys::AudioFileID = ptr::null_mut();
+    unsafe {
+        let url = CFURLCreateFromFileSystemRepresentation(
+            kCFAllocatorDefault,
+            bytes.as_ptr(),
+            bytes.len() as _,
+            false as _,
+        );
+        let status = sys::AudioFileOpenURL(
+            url as sys::CFURLRef,
+            sys::kAudioFileReadPermission,
+            0,
+            &mut file as *mut _,
+        );
+        CFRelease(url as *const _);
+        coreaudio::Error::from_os_status(status)?;
+    }
+    Ok(file)
+}
+
+/// The length of the audio file in frames, along with its sample rate.
+fn file_length(file: sys::AudioFileID) -> Result<(u32, f64), coreaudio::Error> {
+    unsafe {
+        let mut format: sys::AudioStreamBasicDescription = mem::zeroed();
+        let mut size = mem::size_of::<sys::AudioStreamBasicDescription>() as u32;
+        coreaudio::Error::from_os_status(sys::AudioFileGetProperty(
+            file,
+            sys::kAudioFilePropertyDataFormat,
+            &mut size as *mut _,
+            &mut format as *mut _ as *mut _,
+        ))?;
+        let mut packets = 0u64;
+        let mut size = mem::size_of::<u64>() as u32;
+        coreaudio::Error::from_os_status(sys::AudioFileGetProperty(
+            file,
+            sys::kAudioFilePropertyAudioDataPacketCount,
+            &mut size as *mut _,
+            &mut packets as *mut _ as *mut _,
+        ))?;
+        let frames = packets * format.mFramesPerPacket as u64;
+        Ok((frames as u32, format.mSampleRate))
+    }
+}
+
+fn main() -> Result<(), coreaudio::Error> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .expect("usage: graph_file_player <path>");
+    let file = open_file(&path)?;
+    let (frames, sample_rate) = file_length(file)?;
+
+    let mut graph = AuGraph::new()?;
+    let player = graph.add_node(description(GeneratorType::AudioFilePlayer))?;
+    let reverb = graph.add_node(description(EffectType::MatrixReverb))?;
+    let output = graph.add_node(description(IOType::DefaultOutput))?;
+    graph.open()?;
+    graph.connect(player, 0, reverb, 0)?;
+    graph.connect(reverb, 0, output, 0)?;
+    graph.initialize()?;
+
+    // The graph owns the file player's unit, so it is only borrowed while scheduling the file.
+    {
+        let mut player_unit = graph.node_audio_unit(player)?;
+        let id = sys::kAudioUnitProperty_ScheduledFileIDs;
+        player_unit.set_property(id, Scope::Global, Element::Output, Some(&file))?;
+        let mut time_stamp: sys::AudioTimeStamp = unsafe { mem::zeroed() };
+        time_stamp.mFlags = sys::kAudioTimeStampSampleTimeValid;
+        let region = sys::ScheduledAudioFileRegion {
+            mTimeStamp: time_stamp,
+            mCompletionProc: None,
+            mCompletionProcUserData: ptr::null_mut(),
+            mAudioFile: file,
+            mLoopCount: 0,
+            mStartFrame: 0,
+            mFramesToPlay: frames,
+        };
+        let id = sys::kAudioUnitProperty_ScheduledFileRegion;
+        player_unit.set_property(id, Scope::Global, Element::Output, Some(&region))?;
+        let id = sys::kAudioUnitProperty_ScheduledFilePrime;
+        player_unit.set_property(id, Scope::Global, Element::Output, Some(&0u32))?;
+        // A sample time of -1 starts playback on the next render cycle.
+        time_stamp.mSampleTime = -1.0;
+        let id = sys::kAudioUnitProperty_ScheduleStartTimeStamp;
+        player_unit.set_property(id, Scope::Global, Element::Output, Some(&time_stamp))?;
+    }
+
+    graph.start()?;
+    println!("running: {}", graph.is_running()?);
+    let half = Duration::from_secs_f64(frames as f64 / sample_rate / 2.0);
+    std::thread::sleep(half);
+
+    // Re-patch the running graph. The changes are pending until the graph is updated.
+    let delay = graph.add_node(description(EffectType::Delay))?;
+    graph.disconnect_input(output, 0)?;
+    graph.disconnect_input(reverb, 0)?;
+    graph.remove_node(reverb)?;
+    graph.connect(player, 0, delay, 0)?;
+    graph.connect(delay, 0, output, 0)?;
+    while !graph.update()? {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    println!(
+        "swapped the reverb for a delay, running: {}",
+        graph.is_running()?
+    );
+
+    std::thread::sleep(half + Duration::from_secs(2));
+    graph.stop()?;
+
+    drop(graph);
+    unsafe { sys::AudioFileClose(file) };
+    Ok(())
+}
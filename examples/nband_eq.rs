@@ -0,0 +1,64 @@
+//! A 10 band graphic equalizer example, shaping white noise with an NBandEQ unit and playing the
+//! result through the default output device.
+
+extern crate coreaudio;
+
+use coreaudio::audio_unit::effect::{NBandEQ, NBandEQFilterType};
+use coreaudio::audio_unit::render_callback::{self, data};
+
+/// The center frequencies of the bands, one octave apart.
+const FREQUENCIES: [f32; 10] = [
+    31.25, 62.5, 125., 250., 500., 1000., 2000., 4000., 8000., 16000.,
+];
+
+/// The gain of each band in dB, forming a "smiley face" curve.
+const GAINS: [f32; 10] = [9., 6., 3., 0., -3., -3., 0., 3., 6., 9.];
+
+/// A cheap pseudo-random white noise generator.
+struct WhiteNoise {
+    state: u32,
+}
+
+impl Iterator for WhiteNoise {
+    type Item = f32;
+    fn next(&mut self) -> Option<f32> {
+        self.state = self
+            .state
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        Some((self.state as f32 / u32::MAX as f32 * 2. - 1.) * 0.05)
+    }
+}
+
+fn main() -> Result<(), coreaudio::Error> {
+    let mut eq = NBandEQ::new(FREQUENCIES.len() as u32)?;
+    for (band, (&frequency, &gain)) in FREQUENCIES.iter().zip(GAINS.iter()).enumerate() {
+        let band = band as u32;
+        eq.set_band_filter_type(band, NBandEQFilterType::Parametric)?;
+        eq.set_band_frequency_hz(band, frequency)?;
+        eq.set_band_bandwidth(band, 1.0)?;
+        eq.set_band_gain_db(band, gain)?;
+        eq.set_band_bypass(band, false)?;
+    }
+
+    let mut noise = WhiteNoise { state: 1 };
+    type Args = render_callback::Args<data::NonInterleaved<f32>>;
+    eq.set_render_callback(move |args: Args| {
+        let Args {
+            num_frames,
+            mut data,
+            ..
+        } = args;
+        for i in 0..num_frames {
+            let sample = noise.next().unwrap();
+            for channel in data.channels_mut() {
+                channel[i] = sample;
+            }
+        }
+        Ok(())
+    })?;
+
+    let _playing = eq.into_audio_unit().play_through_default_output()?;
+    std::thread::sleep(std::time::Duration::from_millis(3000));
+    Ok(())
+}
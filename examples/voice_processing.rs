@@ -0,0 +1,99 @@
+//! A voice processing example, using the VoiceProcessingIO unit to capture the microphone with
+//! echo cancellation while playing a tone through the output.
+//!
+//! The tone stands in for the far-end audio of a call. The level of the echo cancelled
+//! microphone signal is printed while toggling the AGC and muting the output.
+
+extern crate coreaudio;
+
+use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+use coreaudio::audio_unit::render_callback::{self, data};
+use coreaudio::audio_unit::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const SAMPLE_RATE: f64 = 44100.0;
+
+fn main() -> Result<(), coreaudio::Error> {
+    let mut audio_unit = AudioUnit::voice_processing_io()?;
+
+    // Voice processing works on mono audio.
+    let stream_format = StreamFormat {
+        sample_rate: SAMPLE_RATE,
+        sample_format: SampleFormat::F32,
+        flags: LinearPcmFlags::IS_FLOAT
+            | LinearPcmFlags::IS_PACKED
+            | LinearPcmFlags::IS_NON_INTERLEAVED,
+        channels: 1,
+    };
+    let id = coreaudio::sys::kAudioUnitProperty_StreamFormat;
+    let asbd = stream_format.to_asbd();
+    audio_unit.uninitialize()?;
+    audio_unit.set_property(id, Scope::Input, Element::Output, Some(&asbd))?;
+    audio_unit.set_property(id, Scope::Output, Element::Input, Some(&asbd))?;
+    audio_unit.initialize()?;
+
+    type Args = render_callback::Args<data::NonInterleaved<f32>>;
+
+    // Play a quiet tone as the far-end audio.
+    let mut phase = 0.0f64;
+    audio_unit.set_render_callback(move |mut args: Args| {
+        for i in 0..args.num_frames {
+            let sample = ((phase * PI * 2.0).sin() * 0.1) as f32;
+            phase = (phase + 440.0 / SAMPLE_RATE) % 1.0;
+            for channel in args.data.channels_mut() {
+                channel[i] = sample;
+            }
+        }
+        Ok(())
+    })?;
+
+    // Track the peak level of the echo cancelled microphone signal.
+    let peak = Arc::new(AtomicU32::new(0));
+    let input_peak = peak.clone();
+    audio_unit.set_input_callback(move |args: Args| {
+        let block_peak = args
+            .data
+            .channels()
+            .flat_map(|channel| channel.iter())
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        let previous = f32::from_bits(input_peak.load(Ordering::Relaxed));
+        input_peak.store(previous.max(block_peak).to_bits(), Ordering::Relaxed);
+        Ok(())
+    })?;
+
+    audio_unit.start()?;
+
+    let report = |label: &str, audio_unit: &AudioUnit| -> Result<(), coreaudio::Error> {
+        for _ in 0..4 {
+            thread::sleep(Duration::from_millis(500));
+            let level = f32::from_bits(peak.swap(0, Ordering::Relaxed));
+            println!(
+                "{} (agc: {}, muted: {}): peak {:.3}",
+                label,
+                audio_unit.is_agc_enabled()?,
+                audio_unit.is_muted()?,
+                level,
+            );
+        }
+        Ok(())
+    };
+
+    report("voice processing", &audio_unit)?;
+
+    audio_unit.set_agc_enabled(false)?;
+    report("without AGC", &audio_unit)?;
+
+    audio_unit.set_agc_enabled(true)?;
+    audio_unit.set_muted(true)?;
+    report("output muted", &audio_unit)?;
+
+    audio_unit.set_muted(false)?;
+    audio_unit.set_voice_processing_bypassed(true)?;
+    report("bypassed", &audio_unit)?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! A wrapper around Apple's **AudioFilePlayer** generator unit, which plays regions of audio
+//! files without a render callback.
+//!
+//! Playing a file involves the following steps, in order:
+//!
+//! 1. [**set_file**](./struct.AudioFilePlayer.html#method.set_file) with an open `AudioFileID`.
+//! 2. [**schedule_region**](./struct.AudioFilePlayer.html#method.schedule_region) for each
+//!    region of the file to play.
+//! 3. [**prime**](./struct.AudioFilePlayer.html#method.prime) to read ahead the start of the
+//!    file, avoiding a glitch on the first render.
+//! 4. [**set_start_time**](./struct.AudioFilePlayer.html#method.set_start_time) to start
+//!    playback, once the unit is connected and its output is running.
+
+use super::{AudioUnit, Element, GeneratorType, Scope};
+use crate::error::{AudioError, Error};
+use std::mem;
+use std::ptr;
+use sys;
+
+/// A region of the file to play, as scheduled via
+/// [**AudioFilePlayer::schedule_region**](./struct.AudioFilePlayer.html#method.schedule_region).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ScheduledFileRegion {
+    /// The first frame of the file to play.
+    pub start_frame: i64,
+    /// The number of frames of the file to play.
+    pub frames: u32,
+    /// The number of times to repeat the region after playing it once.
+    pub loop_count: u32,
+    /// The sample time at which to play the region, relative to the start time.
+    pub start_time: f64,
+}
+
+/// An **AudioFilePlayer** unit, playing scheduled regions of an audio file.
+///
+/// **Available** in OS X v10.4 and later.
+pub struct AudioFilePlayer {
+    audio_unit: AudioUnit,
+    file: sys::AudioFileID,
+}
+
+impl_audio_unit_wrapper!(AudioFilePlayer);
+
+impl AudioFilePlayer {
+    /// Create and initialize a new **AudioFilePlayer** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(GeneratorType::AudioFilePlayer)?;
+        Ok(AudioFilePlayer {
+            audio_unit,
+            file: ptr::null_mut(),
+        })
+    }
+
+    /// Set the file to play via `kAudioUnitProperty_ScheduledFileIDs`.
+    ///
+    /// Regions previously scheduled for another file should be cleared first by resetting the
+    /// unit.
+    ///
+    /// # Safety
+    ///
+    /// `file` must be a valid `AudioFileID` opened for reading, e.g. via `AudioFileOpenURL`, and
+    /// must stay open until the unit is done playing it, i.e. until the unit is reset or dropped.
+    pub unsafe fn set_file(&mut self, file: sys::AudioFileID) -> Result<(), Error> {
+        let status = sys::AudioUnitSetProperty(
+            self.audio_unit.instance,
+            sys::kAudioUnitProperty_ScheduledFileIDs,
+            Scope::Global as u32,
+            Element::Output as u32,
+            &file as *const sys::AudioFileID as *const _,
+            mem::size_of::<sys::AudioFileID>() as u32,
+        );
+        Error::from_os_status(status)?;
+        self.file = file;
+        Ok(())
+    }
+
+    /// Schedule a region of the file for playback via `kAudioUnitProperty_ScheduledFileRegion`.
+    ///
+    /// Returns `AudioError::FileNotFound` if no file has been set via
+    /// [**set_file**](./struct.AudioFilePlayer.html#method.set_file).
+    pub fn schedule_region(&mut self, region: ScheduledFileRegion) -> Result<(), Error> {
+        if self.file.is_null() {
+            return Err(Error::Audio(AudioError::FileNotFound));
+        }
+        let mut time_stamp: sys::AudioTimeStamp = unsafe { mem::zeroed() };
+        time_stamp.mSampleTime = region.start_time;
+        time_stamp.mFlags = sys::kAudioTimeStampSampleTimeValid;
+        let region = sys::ScheduledAudioFileRegion {
+            mTimeStamp: time_stamp,
+            mCompletionProc: None,
+            mCompletionProcUserData: ptr::null_mut(),
+            mAudioFile: self.file,
+            mLoopCount: region.loop_count,
+            mStartFrame: region.start_frame,
+            mFramesToPlay: region.frames,
+        };
+        let id = sys::kAudioUnitProperty_ScheduledFileRegion;
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&region))
+    }
+
+    /// Read ahead the given number of frames of the scheduled regions via
+    /// `kAudioUnitProperty_ScheduledFilePrime`, so that playback can begin without waiting on
+    /// disk.
+    ///
+    /// A value of `0` primes the unit's default number of frames.
+    pub fn prime(&mut self, frames: u32) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_ScheduledFilePrime;
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&frames))
+    }
+
+    /// Start playback of the scheduled regions at the given time via
+    /// `kAudioUnitProperty_ScheduleStartTimeStamp`.
+    ///
+    /// A time stamp with a sample time of `-1.0` starts playback on the next render cycle.
+    pub fn set_start_time(&mut self, time_stamp: &sys::AudioTimeStamp) -> Result<(), Error> {
+        self.audio_unit.set_schedule_start_time(time_stamp)
+    }
+}
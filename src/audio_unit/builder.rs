@@ -0,0 +1,284 @@
+//! A builder for configuring an **AudioUnit** with its settings applied in the correct order.
+//!
+//! Configuring an **AudioUnit** by hand involves several steps that must happen in a particular
+//! order, e.g. the stream format must be set while the unit is uninitialized and callbacks
+//! should be in place before the unit is started. The **AudioUnitBuilder** collects all of the
+//! settings up front and applies them in the order required by Core Audio on `build`.
+
+use super::render_callback::{Args, Data};
+use super::{AudioUnit, Element, IOType, Scope, StreamFormat, Type};
+use crate::error::Error;
+use std::fmt;
+use sys;
+
+/// A deferred call that installs a callback on the **AudioUnit**.
+type InstallCallbackFn = dyn FnOnce(&mut AudioUnit) -> Result<(), Error>;
+
+/// The steps taken by **AudioUnitBuilder::build**, in the order in which they are applied.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildStep {
+    /// Creating the **AudioUnit** instance.
+    CreateUnit,
+    /// Uninitializing the unit so that it may be configured.
+    Uninitialize,
+    /// Enabling or disabling input or output on an I/O unit.
+    EnableIo,
+    /// Selecting the device of an I/O unit.
+    SetDevice,
+    /// Setting the stream format of the audio provided by the render callback.
+    SetRenderCallbackFormat,
+    /// Setting the stream format of the audio delivered to the input callback.
+    SetInputCallbackFormat,
+    /// Initializing the configured unit.
+    Initialize,
+    /// Installing the render callback.
+    SetRenderCallback,
+    /// Installing the input callback.
+    SetInputCallback,
+    /// Starting the unit.
+    Start,
+}
+
+/// The error returned when **AudioUnitBuilder::build** fails, describing the step that failed.
+#[derive(Copy, Clone, Debug)]
+pub struct BuildError {
+    /// The step during which the error occurred.
+    pub step: BuildStep,
+    /// The underlying error.
+    pub error: Error,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} failed: {}", self.step, self.error)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(err: BuildError) -> Self {
+        err.error
+    }
+}
+
+/// Collects the configuration of an **AudioUnit** and applies it in the correct order.
+///
+/// ```no_run
+/// # use coreaudio::audio_unit::builder::AudioUnitBuilder;
+/// # use coreaudio::audio_unit::render_callback::{self, data};
+/// # use coreaudio::audio_unit::IOType;
+/// # fn main() -> Result<(), coreaudio::Error> {
+/// type Args = render_callback::Args<data::NonInterleaved<f32>>;
+/// let _audio_unit = AudioUnitBuilder::new(IOType::DefaultOutput)
+///     .render_callback(|_args: Args| Ok(()))
+///     .start(true)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AudioUnitBuilder {
+    ty: Type,
+    enable_input: Option<bool>,
+    enable_output: Option<bool>,
+    #[cfg(target_os = "macos")]
+    device_id: Option<sys::AudioDeviceID>,
+    render_callback_format: Option<StreamFormat>,
+    input_callback_format: Option<StreamFormat>,
+    render_callback: Option<Box<InstallCallbackFn>>,
+    input_callback: Option<Box<InstallCallbackFn>>,
+    start: bool,
+}
+
+impl AudioUnitBuilder {
+    /// Begin building an **AudioUnit** of the given type.
+    pub fn new<T>(ty: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        AudioUnitBuilder {
+            ty: ty.into(),
+            enable_input: None,
+            enable_output: None,
+            #[cfg(target_os = "macos")]
+            device_id: None,
+            render_callback_format: None,
+            input_callback_format: None,
+            render_callback: None,
+            input_callback: None,
+            start: false,
+        }
+    }
+
+    /// Enable or disable input on an I/O unit via `kAudioOutputUnitProperty_EnableIO`.
+    ///
+    /// Input is disabled by default on most I/O units, with the exception of
+    /// `IOType::VoiceProcessingIO` which enables it by default.
+    pub fn enable_input(mut self, enable: bool) -> Self {
+        self.enable_input = Some(enable);
+        self
+    }
+
+    /// Enable or disable output on an I/O unit via `kAudioOutputUnitProperty_EnableIO`.
+    ///
+    /// Output is enabled by default on most I/O units.
+    pub fn enable_output(mut self, enable: bool) -> Self {
+        self.enable_output = Some(enable);
+        self
+    }
+
+    /// Select the device used by an I/O unit, e.g. one created with `IOType::HalOutput`.
+    #[cfg(target_os = "macos")]
+    pub fn device(mut self, device_id: sys::AudioDeviceID) -> Self {
+        self.device_id = Some(device_id);
+        self
+    }
+
+    /// The format of the audio that the render callback provides to the unit.
+    ///
+    /// This is the stream format of the input scope of the output element. As a
+    /// `IOType::VoiceProcessingIO` unit requires the same format on both sides, it is also used
+    /// as the input callback format of such a unit with input enabled, unless one is given.
+    pub fn render_callback_format(mut self, stream_format: StreamFormat) -> Self {
+        self.render_callback_format = Some(stream_format);
+        self
+    }
+
+    /// The format of the audio that the unit delivers to the input callback.
+    ///
+    /// This is the stream format of the output scope of the input element. It is also used as
+    /// the render callback format of a `IOType::VoiceProcessingIO` unit, unless one is given.
+    pub fn input_callback_format(mut self, stream_format: StreamFormat) -> Self {
+        self.input_callback_format = Some(stream_format);
+        self
+    }
+
+    /// The render callback to install once the unit is initialized.
+    ///
+    /// See [**AudioUnit::set_render_callback**](../struct.AudioUnit.html#method.set_render_callback).
+    pub fn render_callback<F, D>(mut self, f: F) -> Self
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.render_callback = Some(Box::new(move |au: &mut AudioUnit| {
+            au.set_render_callback(f)
+        }));
+        self
+    }
+
+    /// The input callback to install once the unit is initialized.
+    ///
+    /// See [**AudioUnit::set_input_callback**](../struct.AudioUnit.html#method.set_input_callback).
+    pub fn input_callback<F, D>(mut self, f: F) -> Self
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.input_callback = Some(Box::new(move |au: &mut AudioUnit| au.set_input_callback(f)));
+        self
+    }
+
+    /// Whether or not to start the unit once it is fully configured. Defaults to `false`.
+    pub fn start(mut self, start: bool) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Create the **AudioUnit** and apply the collected settings.
+    ///
+    /// The unit is configured while uninitialized, then initialized, then its callbacks are
+    /// installed and finally it is started if requested. If any step fails, the returned
+    /// **BuildError** describes which.
+    pub fn build(self) -> Result<AudioUnit, BuildError> {
+        fn step<T>(step: BuildStep, result: Result<T, Error>) -> Result<T, BuildError> {
+            result.map_err(|error| BuildError { step, error })
+        }
+
+        let mut audio_unit = step(BuildStep::CreateUnit, AudioUnit::new(self.ty))?;
+        step(BuildStep::Uninitialize, audio_unit.uninitialize())?;
+
+        // Unlike the other I/O units, the voice processing unit enables input by default and
+        // requires the formats on the application side of its input and output to match.
+        let voice_processing = matches!(self.ty, Type::IO(IOType::VoiceProcessingIO));
+        let input_enabled = self.enable_input.unwrap_or(voice_processing);
+        let (render_callback_format, input_callback_format) = if voice_processing && input_enabled {
+            (
+                self.render_callback_format.or(self.input_callback_format),
+                self.input_callback_format.or(self.render_callback_format),
+            )
+        } else {
+            (self.render_callback_format, self.input_callback_format)
+        };
+
+        let enable_io = sys::kAudioOutputUnitProperty_EnableIO;
+        if let Some(enable) = self.enable_input {
+            let enable = enable as u32;
+            step(
+                BuildStep::EnableIo,
+                audio_unit.set_property(enable_io, Scope::Input, Element::Input, Some(&enable)),
+            )?;
+        }
+        if let Some(enable) = self.enable_output {
+            let enable = enable as u32;
+            step(
+                BuildStep::EnableIo,
+                audio_unit.set_property(enable_io, Scope::Output, Element::Output, Some(&enable)),
+            )?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            if let Some(device_id) = self.device_id {
+                step(
+                    BuildStep::SetDevice,
+                    audio_unit.set_property(
+                        sys::kAudioOutputUnitProperty_CurrentDevice,
+                        Scope::Global,
+                        Element::Output,
+                        Some(&device_id),
+                    ),
+                )?;
+            }
+        }
+
+        let stream_format_id = sys::kAudioUnitProperty_StreamFormat;
+        if let Some(stream_format) = render_callback_format {
+            let asbd = stream_format.to_asbd();
+            step(
+                BuildStep::SetRenderCallbackFormat,
+                audio_unit.set_property(
+                    stream_format_id,
+                    Scope::Input,
+                    Element::Output,
+                    Some(&asbd),
+                ),
+            )?;
+        }
+        if let Some(stream_format) = input_callback_format {
+            let asbd = stream_format.to_asbd();
+            step(
+                BuildStep::SetInputCallbackFormat,
+                audio_unit.set_property(
+                    stream_format_id,
+                    Scope::Output,
+                    Element::Input,
+                    Some(&asbd),
+                ),
+            )?;
+        }
+
+        step(BuildStep::Initialize, audio_unit.initialize())?;
+
+        if let Some(install) = self.render_callback {
+            step(BuildStep::SetRenderCallback, install(&mut audio_unit))?;
+        }
+        if let Some(install) = self.input_callback {
+            step(BuildStep::SetInputCallback, install(&mut audio_unit))?;
+        }
+
+        if self.start {
+            step(BuildStep::Start, audio_unit.start())?;
+        }
+
+        Ok(audio_unit)
+    }
+}
@@ -0,0 +1,151 @@
+//! Querying the channel layouts supported by an **AudioUnit**.
+//!
+//! Channel layouts are identified by an `AudioChannelLayoutTag`, whose lower 16 bits hold the
+//! number of channels in the layout. The common tags are mapped to the **ChannelLayoutTag** enum.
+
+use super::{AudioUnit, Scope};
+use crate::error::Error;
+use sys;
+
+/// The commonly used `AudioChannelLayoutTag`s.
+///
+/// See the `CoreAudioTypes.h` header for the speaker order of each layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChannelLayoutTag {
+    /// The layout is described by an array of channel descriptions.
+    UseChannelDescriptions,
+    /// The layout is described by a channel bitmap.
+    UseChannelBitmap,
+    /// A single channel.
+    Mono,
+    /// Left and right.
+    Stereo,
+    /// Left and right, for playback over headphones.
+    StereoHeadphones,
+    /// Left and right, matrix encoded (e.g. Dolby Surround).
+    MatrixStereo,
+    /// Mid and side.
+    MidSide,
+    /// Left and right, recorded binaurally.
+    Binaural,
+    /// W, X, Y and Z ambisonic channels.
+    AmbisonicBFormat,
+    /// Front left, front right, back left and back right.
+    Quadraphonic,
+    /// Left, right, rear left, rear right and center.
+    Pentagonal,
+    /// Left, right, rear left, rear right, center and rear center.
+    Hexagonal,
+    /// Eight channels arranged in a circle.
+    Octagonal,
+    /// Eight channels at the corners of a cube.
+    Cube,
+    /// Left, right and center.
+    Mpeg3_0A,
+    /// Left, right, center and center surround.
+    Mpeg4_0A,
+    /// Left, right, center, left surround and right surround.
+    Mpeg5_0A,
+    /// Left, right, center, LFE, left surround and right surround.
+    Mpeg5_1A,
+    /// Left, right, center, LFE, left surround, right surround and center surround.
+    Mpeg6_1A,
+    /// Left, right, center, LFE, left surround, right surround, left center and right center.
+    Mpeg7_1A,
+    /// The given number of channels, in order and without any particular meaning.
+    DiscreteInOrder(u16),
+    /// The layout is unknown, with the given number of channels.
+    Unknown(u16),
+    /// A tag not listed above.
+    Custom(u32),
+}
+
+impl ChannelLayoutTag {
+    /// Convert the `ChannelLayoutTag` to its associated `u32` tag.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            ChannelLayoutTag::UseChannelDescriptions => 0,
+            ChannelLayoutTag::UseChannelBitmap => 1 << 16,
+            ChannelLayoutTag::Mono => (100 << 16) | 1,
+            ChannelLayoutTag::Stereo => (101 << 16) | 2,
+            ChannelLayoutTag::StereoHeadphones => (102 << 16) | 2,
+            ChannelLayoutTag::MatrixStereo => (103 << 16) | 2,
+            ChannelLayoutTag::MidSide => (104 << 16) | 2,
+            ChannelLayoutTag::Binaural => (106 << 16) | 2,
+            ChannelLayoutTag::AmbisonicBFormat => (107 << 16) | 4,
+            ChannelLayoutTag::Quadraphonic => (108 << 16) | 4,
+            ChannelLayoutTag::Pentagonal => (109 << 16) | 5,
+            ChannelLayoutTag::Hexagonal => (110 << 16) | 6,
+            ChannelLayoutTag::Octagonal => (111 << 16) | 8,
+            ChannelLayoutTag::Cube => (112 << 16) | 8,
+            ChannelLayoutTag::Mpeg3_0A => (113 << 16) | 3,
+            ChannelLayoutTag::Mpeg4_0A => (115 << 16) | 4,
+            ChannelLayoutTag::Mpeg5_0A => (117 << 16) | 5,
+            ChannelLayoutTag::Mpeg5_1A => (121 << 16) | 6,
+            ChannelLayoutTag::Mpeg6_1A => (125 << 16) | 7,
+            ChannelLayoutTag::Mpeg7_1A => (126 << 16) | 8,
+            ChannelLayoutTag::DiscreteInOrder(channels) => (147 << 16) | channels as u32,
+            ChannelLayoutTag::Unknown(channels) => 0xFFFF_0000 | channels as u32,
+            ChannelLayoutTag::Custom(tag) => tag,
+        }
+    }
+
+    /// Convert a `u32` tag into the `ChannelLayoutTag`, or `Custom` if it is not listed.
+    pub fn from_u32(tag: u32) -> Self {
+        let channels = (tag & 0xFFFF) as u16;
+        match tag >> 16 {
+            147 => ChannelLayoutTag::DiscreteInOrder(channels),
+            0xFFFF => ChannelLayoutTag::Unknown(channels),
+            _ => match tag {
+                0 => ChannelLayoutTag::UseChannelDescriptions,
+                65_536 => ChannelLayoutTag::UseChannelBitmap,
+                6_553_601 => ChannelLayoutTag::Mono,
+                6_619_138 => ChannelLayoutTag::Stereo,
+                6_684_674 => ChannelLayoutTag::StereoHeadphones,
+                6_750_210 => ChannelLayoutTag::MatrixStereo,
+                6_815_746 => ChannelLayoutTag::MidSide,
+                6_946_818 => ChannelLayoutTag::Binaural,
+                7_012_356 => ChannelLayoutTag::AmbisonicBFormat,
+                7_077_892 => ChannelLayoutTag::Quadraphonic,
+                7_143_429 => ChannelLayoutTag::Pentagonal,
+                7_208_966 => ChannelLayoutTag::Hexagonal,
+                7_274_504 => ChannelLayoutTag::Octagonal,
+                7_340_040 => ChannelLayoutTag::Cube,
+                7_405_571 => ChannelLayoutTag::Mpeg3_0A,
+                7_536_644 => ChannelLayoutTag::Mpeg4_0A,
+                7_667_717 => ChannelLayoutTag::Mpeg5_0A,
+                7_929_862 => ChannelLayoutTag::Mpeg5_1A,
+                8_192_007 => ChannelLayoutTag::Mpeg6_1A,
+                8_257_544 => ChannelLayoutTag::Mpeg7_1A,
+                tag => ChannelLayoutTag::Custom(tag),
+            },
+        }
+    }
+
+    /// The number of channels in the layout, as encoded in the lower 16 bits of the tag.
+    ///
+    /// This is `0` for `UseChannelDescriptions` and `UseChannelBitmap`, whose number of channels
+    /// is given by the description or bitmap instead.
+    pub fn channels(&self) -> u32 {
+        self.as_u32() & 0xFFFF
+    }
+}
+
+impl AudioUnit {
+    /// The channel layouts supported by the given scope and element via
+    /// `kAudioUnitProperty_SupportedChannelLayoutTags`, e.g. to choose a surround layout that the
+    /// unit accepts before setting one.
+    ///
+    /// Convert the tags via [**ChannelLayoutTag::from_u32**](./channel_layout/enum.ChannelLayoutTag.html#method.from_u32)
+    /// for a readable representation. Units that don't support channel layouts return an error.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn supported_channel_layout_tags(
+        &self,
+        scope: Scope,
+        element: impl Into<u32>,
+    ) -> Result<Vec<u32>, Error> {
+        let id = sys::kAudioUnitProperty_SupportedChannelLayoutTags;
+        super::get_property_array(self.instance, id, scope, element)
+    }
+}
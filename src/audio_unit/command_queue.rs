@@ -0,0 +1,208 @@
+//! A wait-free single-producer, single-consumer queue for sending commands to the render thread.
+//!
+//! The render callback must not block, so state shared with the rest of the application can't
+//! be guarded by a lock. Instead, the state is owned by the callback and updated by commands sent
+//! through a **CommandQueue**, which
+//! [**set_render_callback_with_commands**](../struct.AudioUnit.html#method.set_render_callback_with_commands)
+//! drains at the start of each render cycle.
+
+use super::render_callback::{Args, Data};
+use super::AudioUnit;
+use crate::error::Error;
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A bounded ring buffer of commands of type `C`, shared by a **CommandSender** and a
+/// **CommandReceiver**.
+pub struct CommandQueue<C> {
+    // One slot more than the capacity, so that a full queue can be told apart from an empty one.
+    slots: Box<[UnsafeCell<MaybeUninit<C>>]>,
+    // The index of the next slot to read, only written by the receiver.
+    head: AtomicUsize,
+    // The index of the next slot to write, only written by the sender.
+    tail: AtomicUsize,
+}
+
+// Commands are only ever accessed by one side at a time, as handed over via `head` and `tail`.
+unsafe impl<C: Send> Send for CommandQueue<C> {}
+unsafe impl<C: Send> Sync for CommandQueue<C> {}
+
+/// The sending half of a **CommandQueue**, e.g. held by the UI thread.
+pub struct CommandSender<C> {
+    queue: Arc<CommandQueue<C>>,
+}
+
+/// The receiving half of a **CommandQueue**, usually owned by the render callback.
+pub struct CommandReceiver<C> {
+    queue: Arc<CommandQueue<C>>,
+}
+
+impl<C> CommandQueue<C> {
+    /// Create a queue holding up to `capacity` pending commands, returning its two halves.
+    ///
+    /// The queue never allocates after creation, so the capacity should cover the commands that
+    /// may be sent within a single render cycle.
+    pub fn with_capacity(capacity: usize) -> (CommandSender<C>, CommandReceiver<C>) {
+        let slots = (0..capacity + 1)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        let queue = Arc::new(CommandQueue {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        });
+        let sender = CommandSender {
+            queue: queue.clone(),
+        };
+        (sender, CommandReceiver { queue })
+    }
+
+    /// The maximum number of pending commands.
+    pub fn capacity(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn next_index(&self, index: usize) -> usize {
+        (index + 1) % self.slots.len()
+    }
+}
+
+impl<C> Drop for CommandQueue<C> {
+    fn drop(&mut self) {
+        // Both halves are gone, so drop the commands that were never received.
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { (*self.slots[head].get()).as_mut_ptr().drop_in_place() };
+            head = self.next_index(head);
+        }
+    }
+}
+
+impl<C> CommandSender<C> {
+    /// Send a command without blocking.
+    ///
+    /// Returns the command as `Err` if the queue is full.
+    pub fn send(&mut self, command: C) -> Result<(), C> {
+        let queue = &*self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        let next = queue.next_index(tail);
+        if next == queue.head.load(Ordering::Acquire) {
+            return Err(command);
+        }
+        unsafe { (*queue.slots[tail].get()).as_mut_ptr().write(command) };
+        queue.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// The maximum number of pending commands.
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+
+    /// The number of commands that can currently be sent without the queue being full.
+    ///
+    /// More may become available as the receiver catches up.
+    pub fn available(&self) -> usize {
+        let queue = &*self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        let head = queue.head.load(Ordering::Acquire);
+        let pending = (tail + queue.slots.len() - head) % queue.slots.len();
+        queue.capacity() - pending
+    }
+}
+
+impl<C> CommandReceiver<C> {
+    /// Receive the oldest pending command without blocking, if any.
+    pub fn try_recv(&mut self) -> Option<C> {
+        let queue = &*self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        if head == queue.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let command = unsafe { (*queue.slots[head].get()).as_ptr().read() };
+        queue.head.store(queue.next_index(head), Ordering::Release);
+        Some(command)
+    }
+
+    /// The number of commands that have been sent but not yet received.
+    ///
+    /// More may become pending as the sender sends further commands.
+    pub fn pending(&self) -> usize {
+        let queue = &*self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        let tail = queue.tail.load(Ordering::Acquire);
+        (tail + queue.slots.len() - head) % queue.slots.len()
+    }
+
+    /// An iterator receiving the pending commands, oldest first.
+    ///
+    /// Commands sent while iterating may also be yielded.
+    pub fn drain(&mut self) -> impl Iterator<Item = C> + '_ {
+        std::iter::from_fn(move || self.try_recv())
+    }
+}
+
+impl AudioUnit {
+    /// Pass a render callback that owns some `state`, updated by commands received from another
+    /// thread via a **CommandQueue**.
+    ///
+    /// At the start of each render cycle, all pending commands are received and passed to
+    /// `apply` along with the state, before `f` renders the audio. Neither locks nor allocates,
+    /// as long as `apply` and `f` don't. Note that commands are dropped on the render thread, so
+    /// those owning heap allocations should be sent back to be freed elsewhere.
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::command_queue::CommandQueue;
+    /// # use coreaudio::audio_unit::render_callback::{self, data};
+    /// # use coreaudio::audio_unit::{AudioUnit, IOType};
+    /// # fn main() -> Result<(), coreaudio::Error> {
+    /// enum Command {
+    ///     SetGain(f32),
+    /// }
+    /// type Args = render_callback::Args<data::NonInterleaved<f32>>;
+    ///
+    /// let mut audio_unit = AudioUnit::new(IOType::DefaultOutput)?;
+    /// let (mut sender, receiver) = CommandQueue::with_capacity(64);
+    /// audio_unit.set_render_callback_with_commands(
+    ///     1.0f32,
+    ///     receiver,
+    ///     |gain: &mut f32, Command::SetGain(new_gain)| *gain = new_gain,
+    ///     |gain: &mut f32, mut args: Args| {
+    ///         for channel in args.data.channels_mut() {
+    ///             for sample in channel.iter_mut() {
+    ///                 *sample *= *gain;
+    ///             }
+    ///         }
+    ///         Ok(())
+    ///     },
+    /// )?;
+    /// audio_unit.start()?;
+    /// sender.send(Command::SetGain(0.5)).ok();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_render_callback_with_commands<S, C, A, F, D>(
+        &mut self,
+        mut state: S,
+        mut commands: CommandReceiver<C>,
+        mut apply: A,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        S: 'static,
+        C: 'static,
+        A: FnMut(&mut S, C) + 'static,
+        F: FnMut(&mut S, Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.set_render_callback(move |args: Args<D>| {
+            for command in commands.drain() {
+                apply(&mut state, command);
+            }
+            f(&mut state, args)
+        })
+    }
+}
@@ -0,0 +1,309 @@
+//! Discovery of the audio components, e.g. audio units, installed on the system.
+//!
+//! Find the original Audio Component Services reference [here](https://developer.apple.com/documentation/audiotoolbox/audio_component_services).
+
+use super::{AudioUnit, FourCC, Type};
+use crate::error::Error;
+use core_foundation_sys::base::CFRelease;
+use core_foundation_sys::string::CFStringRef;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+use sys;
+
+bitflags! {
+    /// Flags describing an audio component, as found in the `flags` of its description.
+    ///
+    /// Original documentation [here](https://developer.apple.com/documentation/audiotoolbox/audiocomponentflags).
+    pub struct ComponentFlags: u32 {
+        /// The component is not found by searches unless it is explicitly asked for, e.g. via
+        /// `AudioComponentFindNext` with this flag set in the mask.
+        ///
+        /// **Available** in OS X v10.7 and later.
+        const UNSEARCHABLE = sys::kAudioComponentFlag_Unsearchable;
+        /// The component can be loaded into a sandboxed host process.
+        ///
+        /// **Available** in OS X v10.8 and later.
+        const SANDBOX_SAFE = sys::kAudioComponentFlag_SandboxSafe;
+        /// The component is a version 3 audio unit, i.e. an app extension.
+        ///
+        /// **Available** in OS X v10.11 and later.
+        const IS_V3_AUDIO_UNIT = sys::kAudioComponentFlag_IsV3AudioUnit;
+        /// The component must be instantiated asynchronously, e.g. via
+        /// [**AudioUnit::new_async**](../struct.AudioUnit.html#method.new_async).
+        ///
+        /// **Available** in OS X v10.11 and later.
+        const REQUIRES_ASYNC_INSTANTIATION = sys::kAudioComponentFlag_RequiresAsyncInstantiation;
+        /// The version 3 audio unit may be loaded into the host's process.
+        ///
+        /// **Available** in OS X v10.11 and later.
+        const CAN_LOAD_IN_PROCESS = sys::kAudioComponentFlag_CanLoadInProcess;
+    }
+}
+
+/// A description of the components to search for.
+///
+/// A value of `0` for any of the type, sub type or manufacturer matches any value, so the
+/// `Default` description matches all components.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchDescription {
+    /// The type of the component, e.g. `Type::Effect(_).as_u32()` for all effects.
+    pub component_type: u32,
+    /// The sub type of the component.
+    pub component_sub_type: u32,
+    /// The manufacturer of the component, e.g. `sys::kAudioUnitManufacturer_Apple`.
+    pub component_manufacturer: u32,
+    /// The flags that must be set on matching components, for the bits set in `flags_mask`.
+    pub flags: u32,
+    /// The bits of `flags` that are considered when matching.
+    pub flags_mask: u32,
+}
+
+impl SearchDescription {
+    /// A description matching all components of the given manufacturer, e.g. to find all units
+    /// within a vendor's bundle.
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::components::{Components, SearchDescription};
+    /// # fn main() -> Result<(), coreaudio::Error> {
+    /// let apple = SearchDescription::manufacturer("appl".parse()?);
+    /// for component in Components::find(&apple) {
+    ///     println!("{:?}", component.name());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn manufacturer(manufacturer: FourCC) -> Self {
+        SearchDescription {
+            component_manufacturer: manufacturer.as_u32(),
+            ..Default::default()
+        }
+    }
+
+    fn to_sys(self) -> sys::AudioComponentDescription {
+        sys::AudioComponentDescription {
+            componentType: self.component_type,
+            componentSubType: self.component_sub_type,
+            componentManufacturer: self.component_manufacturer,
+            componentFlags: self.flags,
+            componentFlagsMask: self.flags_mask,
+        }
+    }
+}
+
+/// Entry point for searching the components installed on the system.
+pub struct Components;
+
+impl Components {
+    /// Find all components matching the given description, in the system-defined order.
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::components::{Components, SearchDescription};
+    /// # use coreaudio::audio_unit::{EffectType, Type};
+    /// let effects = SearchDescription {
+    ///     component_type: Type::Effect(EffectType::Delay).as_u32(),
+    ///     ..Default::default()
+    /// };
+    /// for component in Components::find(&effects) {
+    ///     println!("{:?}", component.name());
+    /// }
+    /// ```
+    pub fn find(desc: &SearchDescription) -> impl Iterator<Item = Component> {
+        ComponentIter {
+            desc: desc.to_sys(),
+            current: ptr::null_mut(),
+        }
+    }
+
+    /// The number of components matching the given description.
+    pub fn count(desc: &SearchDescription) -> u32 {
+        unsafe { sys::AudioComponentCount(&desc.to_sys() as *const _) }
+    }
+
+    /// Describe every component installed on the system, e.g. for listing plugins in a host.
+    ///
+    /// Components whose description cannot be retrieved are skipped. Unavailable names and
+    /// versions are left empty and zeroed respectively.
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::components::Components;
+    /// for info in Components::scan_all() {
+    ///     println!("{} - {} ({:?})", info.manufacturer_name, info.name, info.ty);
+    /// }
+    /// ```
+    pub fn scan_all() -> Vec<ComponentInfo> {
+        Components::find(&SearchDescription::default())
+            .filter_map(|component| component.info().ok())
+            .collect()
+    }
+}
+
+/// A summary of a **Component**, as produced by
+/// [**Components::scan_all**](./struct.Components.html#method.scan_all).
+#[derive(Clone, Debug)]
+pub struct ComponentInfo {
+    /// The component itself, e.g. for instantiating it.
+    pub component: Component,
+    /// The raw description of the component.
+    pub description: SearchDescription,
+    /// The typed representation of the component's type and sub type, if known.
+    pub ty: Option<Type>,
+    /// The manufacturer part of the component's name, e.g. `"Apple"`.
+    pub manufacturer_name: String,
+    /// The model part of the component's name, e.g. `"AUDelay"`.
+    pub name: String,
+    /// The version of the component as `(major, minor, bug fix)`.
+    pub version: (u16, u8, u8),
+    /// The flags of the component.
+    pub flags: ComponentFlags,
+}
+
+/// An iterator yielding each **Component** matching a description.
+struct ComponentIter {
+    desc: sys::AudioComponentDescription,
+    current: sys::AudioComponent,
+}
+
+impl Iterator for ComponentIter {
+    type Item = Component;
+    fn next(&mut self) -> Option<Component> {
+        // Passing the previously found component continues the search after it.
+        self.current = unsafe { sys::AudioComponentFindNext(self.current, &self.desc as *const _) };
+        if self.current.is_null() {
+            None
+        } else {
+            Some(Component {
+                component: self.current,
+            })
+        }
+    }
+}
+
+/// An audio component installed on the system, from which **AudioUnit**s may be instantiated.
+#[derive(Copy, Clone, Debug)]
+pub struct Component {
+    component: sys::AudioComponent,
+}
+
+unsafe impl Send for Component {}
+unsafe impl Sync for Component {}
+
+impl Component {
+    /// The full description of the component, including its type, sub type and manufacturer.
+    pub fn description(&self) -> Result<SearchDescription, Error> {
+        let mut desc = mem::MaybeUninit::<sys::AudioComponentDescription>::uninit();
+        let desc = unsafe {
+            Error::from_os_status(sys::AudioComponentGetDescription(
+                self.component,
+                desc.as_mut_ptr(),
+            ))?;
+            desc.assume_init()
+        };
+        Ok(SearchDescription {
+            component_type: desc.componentType,
+            component_sub_type: desc.componentSubType,
+            component_manufacturer: desc.componentManufacturer,
+            flags: desc.componentFlags,
+            flags_mask: desc.componentFlagsMask,
+        })
+    }
+
+    /// The flags of the component, e.g. whether it is sandbox safe or must be instantiated
+    /// asynchronously.
+    pub fn flags(&self) -> Result<ComponentFlags, Error> {
+        let desc = self.description()?;
+        Ok(ComponentFlags::from_bits_truncate(desc.flags))
+    }
+
+    /// Whether the component can be loaded into a sandboxed host process.
+    ///
+    /// Hosts may use this to decide between in-process and out-of-process instantiation via
+    /// [**AudioUnit::new_async**](../struct.AudioUnit.html#method.new_async).
+    pub fn is_sandbox_safe(&self) -> Result<bool, Error> {
+        Ok(self.flags()?.contains(ComponentFlags::SANDBOX_SAFE))
+    }
+
+    /// The name of the component, usually of the form "Manufacturer: Name".
+    pub fn name(&self) -> Result<String, Error> {
+        let mut name: sys::CFStringRef = ptr::null();
+        unsafe {
+            Error::from_os_status(sys::AudioComponentCopyName(
+                self.component,
+                &mut name as *mut _,
+            ))?;
+            let name = name as CFStringRef;
+            if name.is_null() {
+                return Ok(String::new());
+            }
+            let string = super::cf_string_to_string(name);
+            CFRelease(name as *const c_void);
+            string.ok_or(Error::Unspecified)
+        }
+    }
+
+    /// The version of the component as `(major, minor, bug fix)`.
+    ///
+    /// Components pack their version as `0xMMMMmmbb`, e.g. `0x00010600` for version 1.6.0.
+    pub fn version(&self) -> Result<(u16, u8, u8), Error> {
+        let mut version = 0u32;
+        unsafe {
+            Error::from_os_status(sys::AudioComponentGetVersion(
+                self.component,
+                &mut version as *mut _,
+            ))?;
+        }
+        let major = (version >> 16) as u16;
+        let minor = (version >> 8) as u8;
+        let bug_fix = version as u8;
+        Ok((major, minor, bug_fix))
+    }
+
+    /// Gather the description, name, version and flags of the component into a
+    /// **ComponentInfo**.
+    ///
+    /// The name is split at the first `": "` into the manufacturer and model names. Names without
+    /// a manufacturer part leave the `manufacturer_name` empty.
+    pub fn info(&self) -> Result<ComponentInfo, Error> {
+        let description = self.description()?;
+        let full_name = self.name().unwrap_or_default();
+        let (manufacturer_name, name) = match full_name.find(": ") {
+            Some(i) => (full_name[..i].to_string(), full_name[i + 2..].to_string()),
+            None => (String::new(), full_name),
+        };
+        Ok(ComponentInfo {
+            component: *self,
+            description,
+            ty: Type::from_u32(description.component_type, description.component_sub_type),
+            manufacturer_name,
+            name,
+            version: self.version().unwrap_or((0, 0, 0)),
+            flags: ComponentFlags::from_bits_truncate(description.flags),
+        })
+    }
+
+    /// Create and initialize an **AudioUnit** from the component.
+    ///
+    /// Version 3 and sandboxed audio units may need to be instantiated asynchronously via
+    /// [**AudioUnit::new_async**](../struct.AudioUnit.html#method.new_async) instead.
+    pub fn instantiate(&self) -> Result<AudioUnit, Error> {
+        let mut instance: sys::AudioUnit = ptr::null_mut();
+        unsafe {
+            Error::from_os_status(sys::AudioComponentInstanceNew(
+                self.component,
+                &mut instance as *mut _,
+            ))?;
+            let mut audio_unit = AudioUnit::from_raw(instance, true);
+            audio_unit.initialize()?;
+            Ok(audio_unit)
+        }
+    }
+}
+
+impl AudioUnit {
+    /// The component from which the **AudioUnit** was instantiated, e.g. to retrieve its name or
+    /// version.
+    pub fn component(&self) -> Component {
+        let component = unsafe { sys::AudioComponentInstanceGetComponent(self.instance) };
+        Component { component }
+    }
+}
@@ -0,0 +1,96 @@
+//! A wrapper around Apple's **AUConverter** unit, converting between two linear PCM formats.
+//!
+//! The converter handles changes to the sample rate, the sample format and the interleaving of
+//! the audio, e.g. bridging a 44.1kHz file into a 48kHz device graph.
+
+use super::{AudioUnit, Element, FormatConverterType, Scope, StreamFormat};
+use crate::error::Error;
+use sys;
+
+/// The complexity of the algorithm used to convert between sample rates, trading CPU usage for
+/// quality.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SampleRateConverterComplexity {
+    /// Linear interpolation, the cheapest and lowest quality.
+    Linear,
+    /// The default algorithm.
+    Normal,
+    /// The highest quality algorithm, intended for offline rendering.
+    ///
+    /// **Available** in OS X v10.5 and later.
+    Mastering,
+}
+
+impl SampleRateConverterComplexity {
+    /// Convert the complexity to its associated `u32` for compatibility with the original API.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            SampleRateConverterComplexity::Linear => {
+                sys::kAudioUnitSampleRateConverterComplexity_Linear
+            }
+            SampleRateConverterComplexity::Normal => {
+                sys::kAudioUnitSampleRateConverterComplexity_Normal
+            }
+            SampleRateConverterComplexity::Mastering => {
+                sys::kAudioUnitSampleRateConverterComplexity_Mastering
+            }
+        }
+    }
+}
+
+/// An **AUConverter** unit converting audio from an input **StreamFormat** to an output
+/// **StreamFormat**.
+///
+/// The audio to convert is pulled from a render callback or a connected unit, and the converted
+/// audio is rendered by the unit's output, e.g. when it is connected to an output unit.
+pub struct Converter {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Converter, render_callback);
+
+impl Converter {
+    /// Create a converter from the `input` format to the `output` format.
+    ///
+    /// Returns `Error::UnsupportedStreamFormat` if the formats differ in their number of
+    /// channels, as the converter does not know how to map them, or if either sample rate is not
+    /// positive.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn new(input: StreamFormat, output: StreamFormat) -> Result<Self, Error> {
+        let valid_rates = input.sample_rate > 0.0 && output.sample_rate > 0.0;
+        if input.channels != output.channels || !valid_rates {
+            return Err(Error::UnsupportedStreamFormat);
+        }
+
+        let mut audio_unit = AudioUnit::new(FormatConverterType::AUConverter)?;
+        audio_unit.uninitialize()?;
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd = input.to_asbd();
+        audio_unit.set_property(id, Scope::Input, Element::Output, Some(&asbd))?;
+        let asbd = output.to_asbd();
+        audio_unit.set_property(id, Scope::Output, Element::Output, Some(&asbd))?;
+        audio_unit.initialize()?;
+        Ok(Converter { audio_unit })
+    }
+
+    /// Set the complexity of the sample rate conversion.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn set_sample_rate_converter_complexity(
+        &mut self,
+        complexity: SampleRateConverterComplexity,
+    ) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_SampleRateConverterComplexity;
+        let complexity = complexity.as_u32();
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&complexity))
+    }
+
+    /// Set the quality of the conversion from `0` (minimum) to `127` (maximum).
+    ///
+    /// See [**AudioUnit::set_render_quality**](../struct.AudioUnit.html#method.set_render_quality).
+    pub fn set_quality(&mut self, quality: u8) -> Result<(), Error> {
+        self.audio_unit.set_render_quality(quality)
+    }
+}
@@ -0,0 +1,76 @@
+//! A wrapper around Apple's **DLSMusicDevice** unit, a General MIDI synthesizer.
+//!
+//! The synth plays Apple's built-in General MIDI sound bank unless another DLS or SoundFont bank
+//! is loaded, so it can be played via the MIDI methods of its **AudioUnit**, e.g.
+//! [**note_on**](../struct.AudioUnit.html#method.note_on), right away.
+
+use super::{AudioUnit, Element, MusicDeviceType, Scope};
+use crate::error::{AudioError, Error};
+use core_foundation_sys::base::CFRelease;
+use std::os::raw::c_void;
+use std::path::Path;
+use sys;
+
+/// The path of the General MIDI sound bank that ships with macOS.
+pub const SYSTEM_SOUND_BANK_PATH: &str =
+    "/System/Library/Components/CoreAudio.component/Contents/Resources/gs_instruments.dls";
+
+/// A **DLSMusicDevice** unit, synthesizing MIDI using a DLS or SoundFont sound bank.
+///
+/// **Available** in OS X v10.0 and later.
+pub struct DLSSynth {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(DLSSynth);
+
+impl DLSSynth {
+    /// Create and initialize a new **DLSSynth** unit using the system's sound bank.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MusicDeviceType::DLSSynth)?;
+        Ok(DLSSynth { audio_unit })
+    }
+
+    /// Load the DLS or SoundFont (`.sf2`) sound bank at the given path via
+    /// `kMusicDeviceProperty_SoundBankURL`.
+    ///
+    /// Returns `AudioError::FileNotFound` if there is no file at the given path.
+    pub fn set_sound_bank(&mut self, path: &Path) -> Result<(), Error> {
+        if !path.is_file() {
+            return Err(Error::Audio(AudioError::FileNotFound));
+        }
+        let url = super::cf_url_from_path(path)?;
+        let id = sys::kMusicDeviceProperty_SoundBankURL;
+        let result = self
+            .audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&url));
+        unsafe { CFRelease(url as *const c_void) };
+        result
+    }
+
+    /// Load the system's General MIDI sound bank, e.g. to switch back after loading another.
+    ///
+    /// See [**SYSTEM_SOUND_BANK_PATH**](./constant.SYSTEM_SOUND_BANK_PATH.html).
+    pub fn set_system_sound_bank(&mut self) -> Result<(), Error> {
+        self.set_sound_bank(Path::new(SYSTEM_SOUND_BANK_PATH))
+    }
+
+    /// Load a sound bank from its contents in memory via `kMusicDeviceProperty_SoundBankData`.
+    ///
+    /// This property is deprecated and not supported by all versions of the synth, in which case
+    /// an error is returned and [**set_sound_bank**](./struct.DLSSynth.html#method.set_sound_bank)
+    /// should be used instead.
+    pub fn set_sound_bank_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        let status = unsafe {
+            sys::AudioUnitSetProperty(
+                self.audio_unit.instance,
+                sys::kMusicDeviceProperty_SoundBankData,
+                Scope::Global as u32,
+                Element::Output as u32,
+                data.as_ptr() as *const c_void,
+                data.len() as u32,
+            )
+        };
+        Error::from_os_status(status)
+    }
+}
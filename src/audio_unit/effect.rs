@@ -0,0 +1,722 @@
+//! Typed wrappers around Apple's effect units, built on the generic parameter API.
+//!
+//! Each wrapper exposes the parameters of its unit as methods, in the units expected by Core
+//! Audio, e.g. percent or seconds.
+
+use super::{AudioUnit, EffectType, Element, Scope};
+use crate::error::Error;
+use std::ptr;
+use sys;
+
+/// Set a global parameter of the unit.
+fn set_global_parameter(audio_unit: &mut AudioUnit, id: u32, value: f32) -> Result<(), Error> {
+    audio_unit.set_parameter(id, Scope::Global, Element::Output, value, 0)
+}
+
+/// An **AUDelay** unit, mixing a delayed and filtered copy of the audio back into it.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct Delay {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Delay, render_callback);
+
+impl Delay {
+    /// Create and initialize a new **Delay** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::Delay)?;
+        Ok(Delay { audio_unit })
+    }
+
+    /// Set the proportion of the delayed signal in the output, from `0.0` (dry) to `100.0`
+    /// (wet) percent. Defaults to `50.0`.
+    pub fn set_wet_dry_mix(&mut self, percent: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kDelayParam_WetDryMix, percent)
+    }
+
+    /// Set the delay time in seconds, from `0.0` to `2.0`. Defaults to `1.0`.
+    pub fn set_delay_time_seconds(&mut self, seconds: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kDelayParam_DelayTime, seconds)
+    }
+
+    /// Set the amount of the delayed signal fed back into the delay, from `-100.0` to `100.0`
+    /// percent. Defaults to `50.0`.
+    pub fn set_feedback_percent(&mut self, percent: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kDelayParam_Feedback, percent)
+    }
+
+    /// Set the cutoff frequency of the low-pass filter applied to the delayed signal, from
+    /// `10.0` Hz up to the Nyquist frequency. Defaults to `15000.0`.
+    pub fn set_lowpass_cutoff_hz(&mut self, hz: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kDelayParam_LopassCutoff, hz)
+    }
+}
+
+/// The factory presets of the **MatrixReverb** unit, from the smallest to the largest space.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatrixReverbPreset {
+    SmallRoom,
+    MediumRoom,
+    LargeRoom,
+    MediumHall,
+    LargeHall,
+    Plate,
+    MediumChamber,
+    LargeChamber,
+    Cathedral,
+    LargeRoom2,
+    MediumHall2,
+    MediumHall3,
+    LargeHall2,
+}
+
+impl MatrixReverbPreset {
+    /// Convert the preset to its number within the unit's factory presets.
+    pub fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// A **MatrixReverb** unit, simulating a small and a large space that may be mixed together.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct MatrixReverb {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(MatrixReverb, render_callback);
+
+impl MatrixReverb {
+    /// Create and initialize a new **MatrixReverb** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::MatrixReverb)?;
+        Ok(MatrixReverb { audio_unit })
+    }
+
+    /// Load one of the unit's factory presets via `kAudioUnitProperty_PresentPreset`, replacing
+    /// the values of all of its parameters.
+    pub fn set_preset(&mut self, preset: MatrixReverbPreset) -> Result<(), Error> {
+        let preset = sys::AUPreset {
+            presetNumber: preset.as_u32() as i32,
+            presetName: ptr::null(),
+        };
+        let id = sys::kAudioUnitProperty_PresentPreset;
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&preset))
+    }
+
+    /// Set the proportion of the reverberated signal in the output, from `0.0` (dry) to `100.0`
+    /// (wet) percent.
+    pub fn set_dry_wet_mix(&mut self, percent: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kReverbParam_DryWetMix, percent)
+    }
+
+    /// Set the balance between the small and the large space, from `0.0` (small) to `100.0`
+    /// (large) percent.
+    pub fn set_small_large_mix(&mut self, percent: f32) -> Result<(), Error> {
+        set_global_parameter(
+            &mut self.audio_unit,
+            sys::kReverbParam_SmallLargeMix,
+            percent,
+        )
+    }
+
+    /// Set the size of the small space in seconds, from `0.005` to `0.020`.
+    pub fn set_small_size(&mut self, seconds: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kReverbParam_SmallSize, seconds)
+    }
+
+    /// Set the size of the large space in seconds, from `0.4` to `10.0`. Larger spaces decay
+    /// more slowly.
+    pub fn set_large_size(&mut self, seconds: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kReverbParam_LargeSize, seconds)
+    }
+
+    /// Set the delay before the first reflections in seconds, from `0.001` to `0.03`.
+    pub fn set_pre_delay(&mut self, seconds: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kReverbParam_PreDelay, seconds)
+    }
+
+    /// Set the gain of the filter applied to the reverberated signal in decibels, from `-18.0`
+    /// to `18.0`.
+    pub fn set_gain_db(&mut self, db: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kReverbParam_FilterGain, db)
+    }
+}
+
+/// An **AULowpass** unit, a resonant filter attenuating frequencies above its cutoff.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct LowPassFilter {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(LowPassFilter, render_callback);
+
+impl LowPassFilter {
+    /// Create and initialize a new **LowPassFilter** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::LowPassFilter)?;
+        Ok(LowPassFilter { audio_unit })
+    }
+
+    /// Set the cutoff frequency in Hz, from `10.0` up to the Nyquist frequency. Defaults to
+    /// `6900.0`.
+    pub fn set_cutoff_hz(&mut self, hz: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kLowPassParam_CutoffFrequency, hz)
+    }
+
+    /// Set the resonance at the cutoff frequency in decibels, from `-20.0` to `40.0`. Defaults
+    /// to `0.0`.
+    pub fn set_resonance_db(&mut self, db: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kLowPassParam_Resonance, db)
+    }
+}
+
+/// An **AUHipass** unit, a resonant filter attenuating frequencies below its cutoff.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct HighPassFilter {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(HighPassFilter, render_callback);
+
+impl HighPassFilter {
+    /// Create and initialize a new **HighPassFilter** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::HighPassFilter)?;
+        Ok(HighPassFilter { audio_unit })
+    }
+
+    /// Set the cutoff frequency in Hz, from `10.0` up to the Nyquist frequency. Defaults to
+    /// `6900.0`.
+    pub fn set_cutoff_hz(&mut self, hz: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kHipassParam_CutoffFrequency, hz)
+    }
+
+    /// Set the resonance at the cutoff frequency in decibels, from `-20.0` to `40.0`. Defaults
+    /// to `0.0`.
+    pub fn set_resonance_db(&mut self, db: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kHipassParam_Resonance, db)
+    }
+}
+
+/// An **AUBandpass** unit, attenuating frequencies outside of a band around its center
+/// frequency.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct BandPassFilter {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(BandPassFilter, render_callback);
+
+impl BandPassFilter {
+    /// Create and initialize a new **BandPassFilter** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::BandPassFilter)?;
+        Ok(BandPassFilter { audio_unit })
+    }
+
+    /// Set the center frequency of the band in Hz, from `20.0` up to the Nyquist frequency.
+    /// Defaults to `5000.0`.
+    pub fn set_center_hz(&mut self, hz: f32) -> Result<(), Error> {
+        set_global_parameter(
+            &mut self.audio_unit,
+            sys::kBandpassParam_CenterFrequency,
+            hz,
+        )
+    }
+
+    /// Set the width of the band in cents, from `100.0` to `12000.0`. Defaults to `600.0`.
+    pub fn set_bandwidth(&mut self, cents: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kBandpassParam_Bandwidth, cents)
+    }
+}
+
+/// An **AUParametricEQ** unit, a single band equalizer boosting or cutting the frequencies
+/// around its center frequency, e.g. for a tone control.
+///
+/// The values of the parameters are checked against the ranges reported by the unit, returning
+/// `Error::ParameterValueOutOfRange` if they lie outside of them. The settings may be saved and
+/// restored along with the rest of the unit's state via
+/// [**save_state**](./struct.ParametricEQ.html#method.save_state).
+///
+/// **Available** in OS X v10.2 and later.
+pub struct ParametricEQ {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(ParametricEQ, render_callback);
+
+impl ParametricEQ {
+    /// Create and initialize a new **ParametricEQ** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::ParametricEQ)?;
+        Ok(ParametricEQ { audio_unit })
+    }
+
+    /// Set the center frequency of the band in Hz, from `20.0` up to the Nyquist frequency.
+    /// Defaults to `2000.0`.
+    pub fn set_center_frequency_hz(&mut self, hz: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kParametricEQParam_CenterFreq, hz)
+    }
+
+    /// Set the Q of the band, from `0.1` (wide) to `20.0` (narrow). Defaults to `1.0`.
+    pub fn set_q(&mut self, q: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kParametricEQParam_Q, q)
+    }
+
+    /// Set the gain applied to the band in decibels, from `-20.0` to `20.0`. Defaults to `0.0`.
+    pub fn set_gain_db(&mut self, db: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kParametricEQParam_Gain, db)
+    }
+
+    /// Save the settings of the equalizer.
+    ///
+    /// See [**AudioUnit::save_state**](../struct.AudioUnit.html#method.save_state).
+    pub fn save_state(&self) -> Result<Vec<u8>, Error> {
+        self.audio_unit.save_state()
+    }
+
+    /// Restore settings previously saved via
+    /// [**save_state**](./struct.ParametricEQ.html#method.save_state).
+    ///
+    /// See [**AudioUnit::restore_state**](../struct.AudioUnit.html#method.restore_state).
+    pub fn restore_state(&mut self, plist: &[u8]) -> Result<(), Error> {
+        self.audio_unit.restore_state(plist)
+    }
+}
+
+/// The type of filter applied by a band of an **NBandEQ**.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NBandEQFilterType {
+    /// Boosts or cuts the frequencies around the band's frequency.
+    Parametric,
+    /// A 2nd order Butterworth low-pass filter.
+    ButterworthLowPass,
+    /// A 2nd order Butterworth high-pass filter.
+    ButterworthHighPass,
+    /// A low-pass filter with a resonant peak at its cutoff.
+    ResonantLowPass,
+    /// A high-pass filter with a resonant peak at its cutoff.
+    ResonantHighPass,
+    /// Passes the frequencies around the band's frequency.
+    BandPass,
+    /// Rejects the frequencies around the band's frequency.
+    BandStop,
+    /// Boosts or cuts the frequencies below the band's frequency.
+    LowShelf,
+    /// Boosts or cuts the frequencies above the band's frequency.
+    HighShelf,
+    /// A low shelf with a resonant peak at the band's frequency.
+    ResonantLowShelf,
+    /// A high shelf with a resonant peak at the band's frequency.
+    ResonantHighShelf,
+}
+
+impl NBandEQFilterType {
+    /// Convert the filter type to its associated `u32` for compatibility with the original API.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            NBandEQFilterType::Parametric => sys::kAUNBandEQFilterType_Parametric,
+            NBandEQFilterType::ButterworthLowPass => {
+                sys::kAUNBandEQFilterType_2ndOrderButterworthLowPass
+            }
+            NBandEQFilterType::ButterworthHighPass => {
+                sys::kAUNBandEQFilterType_2ndOrderButterworthHighPass
+            }
+            NBandEQFilterType::ResonantLowPass => sys::kAUNBandEQFilterType_ResonantLowPass,
+            NBandEQFilterType::ResonantHighPass => sys::kAUNBandEQFilterType_ResonantHighPass,
+            NBandEQFilterType::BandPass => sys::kAUNBandEQFilterType_BandPass,
+            NBandEQFilterType::BandStop => sys::kAUNBandEQFilterType_BandStop,
+            NBandEQFilterType::LowShelf => sys::kAUNBandEQFilterType_LowShelf,
+            NBandEQFilterType::HighShelf => sys::kAUNBandEQFilterType_HighShelf,
+            NBandEQFilterType::ResonantLowShelf => sys::kAUNBandEQFilterType_ResonantLowShelf,
+            NBandEQFilterType::ResonantHighShelf => sys::kAUNBandEQFilterType_ResonantHighShelf,
+        }
+    }
+}
+
+/// An **AUNBandEQ** unit, an equalizer with a configurable number of bands, each with its own
+/// filter type.
+///
+/// The parameters of each band are addressed by adding the index of the band to the identifier
+/// of the parameter, which the methods of this wrapper take care of.
+///
+/// **Available** in OS X v10.9 and later.
+pub struct NBandEQ {
+    audio_unit: AudioUnit,
+    bands: u32,
+}
+
+impl_audio_unit_wrapper!(NBandEQ, render_callback);
+
+impl NBandEQ {
+    /// Create and initialize a new **NBandEQ** unit with the given number of bands.
+    ///
+    /// The number of bands can only be set while the unit is uninitialized, so it is set before
+    /// the unit is initialized again. Each band is bypassed until it is enabled via
+    /// [**set_band_bypass**](./struct.NBandEQ.html#method.set_band_bypass).
+    pub fn new(bands: u32) -> Result<Self, Error> {
+        let mut audio_unit = AudioUnit::new(EffectType::NBandEQ)?;
+        audio_unit.uninitialize()?;
+        let id = sys::kAUNBandEQProperty_NumberOfBands;
+        audio_unit.set_property(id, Scope::Global, Element::Output, Some(&bands))?;
+        audio_unit.initialize()?;
+        Ok(NBandEQ { audio_unit, bands })
+    }
+
+    /// The number of bands of the equalizer.
+    pub fn bands(&self) -> u32 {
+        self.bands
+    }
+
+    /// Set the gain applied to the whole signal in decibels, from `-96.0` to `24.0`.
+    pub fn set_global_gain_db(&mut self, db: f32) -> Result<(), Error> {
+        set_global_parameter(&mut self.audio_unit, sys::kAUNBandEQParam_GlobalGain, db)
+    }
+
+    /// Set the type of filter applied by the given band.
+    pub fn set_band_filter_type(
+        &mut self,
+        band: u32,
+        filter_type: NBandEQFilterType,
+    ) -> Result<(), Error> {
+        let value = filter_type.as_u32() as f32;
+        self.set_band_parameter(sys::kAUNBandEQParam_FilterType, band, value)
+    }
+
+    /// Set the frequency of the given band in Hz, from `20.0` up to the Nyquist frequency.
+    pub fn set_band_frequency_hz(&mut self, band: u32, hz: f32) -> Result<(), Error> {
+        self.set_band_parameter(sys::kAUNBandEQParam_Frequency, band, hz)
+    }
+
+    /// Set the gain of the given band in decibels, from `-96.0` to `24.0`.
+    ///
+    /// Only applies to the parametric and shelf filter types.
+    pub fn set_band_gain_db(&mut self, band: u32, db: f32) -> Result<(), Error> {
+        self.set_band_parameter(sys::kAUNBandEQParam_Gain, band, db)
+    }
+
+    /// Set the bandwidth of the given band in octaves, from `0.05` to `5.0`.
+    ///
+    /// Only applies to the parametric, band-pass and band-stop filter types.
+    pub fn set_band_bandwidth(&mut self, band: u32, octaves: f32) -> Result<(), Error> {
+        self.set_band_parameter(sys::kAUNBandEQParam_Bandwidth, band, octaves)
+    }
+
+    /// Bypass or enable the given band.
+    pub fn set_band_bypass(&mut self, band: u32, bypass: bool) -> Result<(), Error> {
+        let value = if bypass { 1.0 } else { 0.0 };
+        self.set_band_parameter(sys::kAUNBandEQParam_BypassBand, band, value)
+    }
+
+    /// Set the parameter of the given band, whose identifier is the sum of the parameter's base
+    /// identifier and the index of the band.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the band does not exist.
+    fn set_band_parameter(&mut self, base_id: u32, band: u32, value: f32) -> Result<(), Error> {
+        if band >= self.bands {
+            return Err(Error::ParameterValueOutOfRange);
+        }
+        set_global_parameter(&mut self.audio_unit, base_id + band, value)
+    }
+}
+
+/// The number of bands of a **GraphicEQ**.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicEQBands {
+    /// Ten bands spaced an octave apart, from 32 Hz to 16 kHz.
+    Ten,
+    /// Thirty-one bands spaced a third of an octave apart, from 20 Hz to 20 kHz.
+    ThirtyOne,
+}
+
+impl GraphicEQBands {
+    /// The number of bands.
+    pub fn count(&self) -> u32 {
+        match *self {
+            GraphicEQBands::Ten => 10,
+            GraphicEQBands::ThirtyOne => 31,
+        }
+    }
+}
+
+/// An **AUGraphicEQ** unit, an equalizer with a fixed set of 10 or 31 bands.
+///
+/// The gain of each band is the parameter whose identifier is the index of the band.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct GraphicEQ {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(GraphicEQ, render_callback);
+
+impl GraphicEQ {
+    /// Create and initialize a new **GraphicEQ** unit, which starts out with ten bands.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::GraphicEQ)?;
+        Ok(GraphicEQ { audio_unit })
+    }
+
+    /// Switch between the 10 and 31 band modes via `kGraphicEQParam_NumberOfBands`.
+    pub fn set_bands(&mut self, bands: GraphicEQBands) -> Result<(), Error> {
+        let value = match bands {
+            GraphicEQBands::Ten => 0.0,
+            GraphicEQBands::ThirtyOne => 1.0,
+        };
+        set_global_parameter(
+            &mut self.audio_unit,
+            sys::kGraphicEQParam_NumberOfBands,
+            value,
+        )
+    }
+
+    /// The current band mode of the equalizer.
+    pub fn bands(&self) -> Result<GraphicEQBands, Error> {
+        let id = sys::kGraphicEQParam_NumberOfBands;
+        let value = self
+            .audio_unit
+            .parameter(id, Scope::Global, Element::Output)?;
+        if value == 0.0 {
+            Ok(GraphicEQBands::Ten)
+        } else {
+            Ok(GraphicEQBands::ThirtyOne)
+        }
+    }
+
+    /// Set the gain of the given band in decibels, from `-20.0` to `20.0`.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the band does not exist in the current band
+    /// mode, or if the gain is outside of the range of the parameter.
+    pub fn set_band_gain(&mut self, band: u32, db: f32) -> Result<(), Error> {
+        if band >= self.bands()?.count() {
+            return Err(Error::ParameterValueOutOfRange);
+        }
+        self.audio_unit.set_global_parameter_checked(band, db)
+    }
+
+    /// The gain of each band in decibels, ordered from the lowest to the highest band.
+    pub fn band_gains(&self) -> Result<Vec<f32>, Error> {
+        (0..self.bands()?.count())
+            .map(|band| {
+                self.audio_unit
+                    .parameter(band, Scope::Global, Element::Output)
+            })
+            .collect()
+    }
+}
+
+/// An **AUDynamicsProcessor** unit, a compressor and expander, e.g. for evening out the level of
+/// speech.
+///
+/// Besides its settings, the unit reports the gain reduction it currently applies and the levels
+/// of its input and output, which may be polled for metering.
+///
+/// **Available** in OS X v10.2 and later.
+pub struct DynamicsProcessor {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(DynamicsProcessor, render_callback);
+
+impl DynamicsProcessor {
+    /// Create and initialize a new **DynamicsProcessor** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(EffectType::DynamicsProcessor)?;
+        Ok(DynamicsProcessor { audio_unit })
+    }
+
+    /// Set the level above which the signal is compressed in decibels, from `-40.0` to `20.0`.
+    /// Defaults to `-20.0`.
+    pub fn set_threshold_db(&mut self, db: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_Threshold;
+        set_global_parameter(&mut self.audio_unit, id, db)
+    }
+
+    /// Set the headroom above the threshold in decibels, from `0.1` to `40.0`, which determines
+    /// how strongly the signal is compressed. Defaults to `5.0`.
+    pub fn set_headroom_db(&mut self, db: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_HeadRoom;
+        set_global_parameter(&mut self.audio_unit, id, db)
+    }
+
+    /// Set the ratio by which the signal below the expansion threshold is expanded, from `1.0`
+    /// to `50.0`. Defaults to `2.0`.
+    pub fn set_expansion_ratio(&mut self, ratio: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_ExpansionRatio;
+        set_global_parameter(&mut self.audio_unit, id, ratio)
+    }
+
+    /// Set the level below which the signal is expanded in decibels.
+    pub fn set_expansion_threshold_db(&mut self, db: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_ExpansionThreshold;
+        set_global_parameter(&mut self.audio_unit, id, db)
+    }
+
+    /// Set the time the compressor takes to react to a rise in level in seconds, from `0.0001`
+    /// to `0.2`. Defaults to `0.001`.
+    pub fn set_attack_time(&mut self, seconds: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_AttackTime;
+        set_global_parameter(&mut self.audio_unit, id, seconds)
+    }
+
+    /// Set the time the compressor takes to recover after a fall in level in seconds, from
+    /// `0.01` to `3.0`. Defaults to `0.05`.
+    pub fn set_release_time(&mut self, seconds: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_ReleaseTime;
+        set_global_parameter(&mut self.audio_unit, id, seconds)
+    }
+
+    /// Set the gain applied to the output in decibels, from `-40.0` to `40.0`. Defaults to
+    /// `0.0`.
+    pub fn set_master_gain_db(&mut self, db: f32) -> Result<(), Error> {
+        let id = sys::kDynamicsProcessorParam_MasterGain;
+        set_global_parameter(&mut self.audio_unit, id, db)
+    }
+
+    /// The gain reduction currently applied by the compressor in decibels, for a gain reduction
+    /// meter.
+    pub fn compression_amount_db(&self) -> Result<f32, Error> {
+        let id = sys::kDynamicsProcessorParam_CompressionAmount;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// The current level of the input in decibels.
+    pub fn input_amplitude_db(&self) -> Result<f32, Error> {
+        let id = sys::kDynamicsProcessorParam_InputAmplitude;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// The current level of the output in decibels.
+    pub fn output_amplitude_db(&self) -> Result<f32, Error> {
+        let id = sys::kDynamicsProcessorParam_OutputAmplitude;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::super::render_callback::{data, Args};
+    use super::super::test_util::{output_sample_rate, render_offline};
+    use super::super::IOType;
+    use super::*;
+
+    /// A deterministic source of white noise in `-0.5..0.5`.
+    struct Noise(u32);
+
+    impl Iterator for Noise {
+        type Item = f32;
+        fn next(&mut self) -> Option<f32> {
+            // xorshift32
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            Some(self.0 as f32 / u32::MAX as f32 - 0.5)
+        }
+    }
+
+    /// The energy of the first difference of the samples, dominated by their high frequencies.
+    fn high_frequency_energy(samples: &[f32]) -> f32 {
+        samples.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum()
+    }
+
+    #[test]
+    fn delay_echoes_an_impulse_after_the_delay_time() {
+        const DELAY_SECONDS: f32 = 0.01;
+
+        let mut delay = Delay::new().unwrap();
+        delay.set_wet_dry_mix(100.0).unwrap();
+        delay.set_delay_time_seconds(DELAY_SECONDS).unwrap();
+        delay.set_feedback_percent(0.0).unwrap();
+        delay.set_lowpass_cutoff_hz(20000.0).unwrap();
+        // Clear the delay line and any smoothing of the delay time towards its new value.
+        let instance = delay.audio_unit().as_raw();
+        unsafe { Error::from_os_status(sys::AudioUnitReset(instance, Scope::Global as u32, 0)) }
+            .unwrap();
+
+        let mut position = 0;
+        delay
+            .set_render_callback(move |mut args: Args<data::NonInterleaved<f32>>| {
+                for channel in args.data.channels_mut() {
+                    for (frame, sample) in channel.iter_mut().enumerate() {
+                        *sample = if position + frame == 0 { 1.0 } else { 0.0 };
+                    }
+                }
+                position += args.num_frames;
+                Ok(())
+            })
+            .unwrap();
+
+        let sample_rate = output_sample_rate(delay.audio_unit()).unwrap();
+        let expected = (DELAY_SECONDS as f64 * sample_rate).round() as usize;
+        let rendered = render_offline(delay.audio_unit(), expected * 4, 512).unwrap();
+        let (peak, &amplitude) = rendered
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap())
+            .unwrap();
+        assert!(amplitude.abs() > 0.1, "no echo in the output");
+        // The delay's low-pass filter may smear the echo by a few frames.
+        assert!(
+            (peak as i64 - expected as i64).abs() <= 4,
+            "echo at frame {}, expected frame {}",
+            peak,
+            expected
+        );
+        // Fully wet, so the impulse itself must not pass through.
+        assert!(rendered[..expected / 2].iter().all(|s| s.abs() < 0.01));
+    }
+
+    #[test]
+    fn low_pass_attenuates_high_frequencies_of_white_noise() {
+        const SEED: u32 = 0x9e37_79b9;
+        const FRAMES: usize = 44100;
+
+        let mut filter = LowPassFilter::new().unwrap();
+        filter.set_cutoff_hz(1000.0).unwrap();
+        let mut noise = Noise(SEED);
+        let mut block = vec![0.0; 4096];
+        filter
+            .set_render_callback(move |mut args: Args<data::NonInterleaved<f32>>| {
+                let block = &mut block[..args.num_frames];
+                block
+                    .iter_mut()
+                    .zip(noise.by_ref())
+                    .for_each(|(s, n)| *s = n);
+                for channel in args.data.channels_mut() {
+                    channel.copy_from_slice(block);
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        // Pull the filtered noise through a GenericOutput unit, as when bouncing offline.
+        let mut output = AudioUnit::new(IOType::GenericOutput).unwrap();
+        unsafe { output.connect_input(0, filter.audio_unit(), 0).unwrap() };
+        output.start().unwrap();
+        let rendered = render_offline(&output, FRAMES, 512).unwrap();
+        output.stop().unwrap();
+        output.disconnect_input(0).unwrap();
+
+        let input: Vec<f32> = Noise(SEED).take(FRAMES).collect();
+        let input_energy = high_frequency_energy(&input);
+        let output_energy = high_frequency_energy(&rendered);
+        assert!(
+            output_energy < input_energy * 0.01,
+            "high frequency energy only reduced from {} to {}",
+            input_energy,
+            output_energy
+        );
+    }
+}
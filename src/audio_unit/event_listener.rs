@@ -0,0 +1,207 @@
+//! Notifications of changes to **AudioUnit** parameters using the `AUEventListener` API.
+//!
+//! Find the original Audio Unit Event reference [here](https://developer.apple.com/documentation/audiotoolbox/audio_unit_v2_c_api/au_event_listener).
+
+use super::{AudioUnit, Scope};
+use crate::error::Error;
+use core_foundation_sys::runloop::CFRunLoopRef;
+use std::os::raw::c_void;
+use std::ptr;
+use std::time::Duration;
+use sys;
+
+/// The kind of change described by a **ParameterEvent**.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParameterEventKind {
+    /// The value of the parameter changed.
+    ValueChange,
+    /// A gesture (e.g. dragging a slider) that will change the parameter has begun.
+    BeginGesture,
+    /// A gesture that changed the parameter has ended.
+    EndGesture,
+}
+
+/// A notification that a parameter of an **AudioUnit** has changed.
+#[derive(Copy, Clone, Debug)]
+pub struct ParameterEvent {
+    /// The kind of change.
+    pub kind: ParameterEventKind,
+    /// The identifier of the parameter.
+    pub id: u32,
+    /// The raw audio unit scope of the parameter.
+    pub scope: u32,
+    /// The audio unit element of the parameter.
+    pub element: u32,
+    /// The new value of the parameter. Only meaningful for `ParameterEventKind::ValueChange`.
+    pub value: f32,
+    /// The host time at which the event occurred.
+    pub host_time: u64,
+}
+
+/// The type of closure called by the listener for each **ParameterEvent**.
+type ParameterListenerFn = dyn FnMut(ParameterEvent) + Send;
+
+/// This type allows us to safely wrap a boxed listener closure to use within the listener proc.
+struct ParameterListenerFnWrapper {
+    callback: Box<ParameterListenerFn>,
+}
+
+/// A handle to a registered parameter listener.
+///
+/// The listener is disposed of and its closure freed when the handle is dropped. The handle is
+/// not tied to the lifetime of the **AudioUnit** whose parameters it listens to, so it should be
+/// dropped before the **AudioUnit** is, as the listener would otherwise keep referring to the
+/// disposed unit.
+pub struct EventListenerHandle {
+    listener: sys::AUEventListenerRef,
+    callback: *mut ParameterListenerFnWrapper,
+}
+
+unsafe impl Send for EventListenerHandle {}
+
+impl Drop for EventListenerHandle {
+    fn drop(&mut self) {
+        unsafe {
+            // We don't want to panic in `drop`, so we'll ignore returned errors.
+            Error::from_os_status(sys::AUListenerDispose(self.listener)).ok();
+            let _: Box<ParameterListenerFnWrapper> = Box::from_raw(self.callback);
+        }
+    }
+}
+
+impl AudioUnit {
+    /// Listen for changes to the given parameters, each identified by its id, scope and element,
+    /// e.g. `(sys::kMultiChannelMixerParam_Volume, Scope::Input, 1)` for the volume of the
+    /// second input bus of a mixer.
+    ///
+    /// The closure is called whenever a parameter's value changes (e.g. via the unit's own UI or
+    /// automation) and when a change gesture begins or ends. Value changes closer together than
+    /// `granularity` are coalesced.
+    ///
+    /// Events are delivered on a thread internal to Core Audio. Use
+    /// [**add_parameter_listener_with_run_loop**](./struct.AudioUnit.html#method.add_parameter_listener_with_run_loop)
+    /// to have them delivered on a specific run loop instead.
+    ///
+    /// The listener remains active until the returned **EventListenerHandle** is dropped, which
+    /// should happen before this **AudioUnit** is dropped.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn add_parameter_listener<F>(
+        &mut self,
+        parameters: &[(u32, Scope, u32)],
+        granularity: Duration,
+        f: F,
+    ) -> Result<EventListenerHandle, Error>
+    where
+        F: FnMut(ParameterEvent) + Send + 'static,
+    {
+        self.add_parameter_listener_with_run_loop(parameters, granularity, ptr::null_mut(), f)
+    }
+
+    /// The same as
+    /// [**add_parameter_listener**](./struct.AudioUnit.html#method.add_parameter_listener) but
+    /// events are delivered on the given run loop in its default mode, e.g. the run loop returned
+    /// by `CFRunLoopGetMain`.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn add_parameter_listener_with_run_loop<F>(
+        &mut self,
+        parameters: &[(u32, Scope, u32)],
+        granularity: Duration,
+        run_loop: CFRunLoopRef,
+        f: F,
+    ) -> Result<EventListenerHandle, Error>
+    where
+        F: FnMut(ParameterEvent) + Send + 'static,
+    {
+        let wrapper = Box::new(ParameterListenerFnWrapper {
+            callback: Box::new(f),
+        });
+        let wrapper_ptr = Box::into_raw(wrapper);
+
+        let interval = granularity.as_secs_f32();
+        let mut listener: sys::AUEventListenerRef = ptr::null_mut();
+        let status = unsafe {
+            sys::AUEventListenerCreate(
+                Some(event_listener_proc),
+                wrapper_ptr as *mut c_void,
+                run_loop as _,
+                ptr::null(),
+                interval,
+                interval,
+                &mut listener as *mut _,
+            )
+        };
+        if let Err(err) = Error::from_os_status(status) {
+            unsafe {
+                let _: Box<ParameterListenerFnWrapper> = Box::from_raw(wrapper_ptr);
+            }
+            return Err(err);
+        }
+
+        // From here on the handle is responsible for cleaning up, even if registration fails.
+        let handle = EventListenerHandle {
+            listener,
+            callback: wrapper_ptr,
+        };
+
+        let event_types = [
+            sys::kAudioUnitEvent_ParameterValueChange,
+            sys::kAudioUnitEvent_BeginParameterChangeGesture,
+            sys::kAudioUnitEvent_EndParameterChangeGesture,
+        ];
+        for &(id, scope, element) in parameters {
+            for &event_type in event_types.iter() {
+                let event = sys::AudioUnitEvent {
+                    mEventType: event_type,
+                    mArgument: sys::AudioUnitEvent__bindgen_ty_1 {
+                        mParameter: sys::AudioUnitParameter {
+                            mAudioUnit: self.instance,
+                            mParameterID: id,
+                            mScope: scope as u32,
+                            mElement: element,
+                        },
+                    },
+                };
+                unsafe {
+                    Error::from_os_status(sys::AUEventListenerAddEventType(
+                        handle.listener,
+                        ptr::null_mut(),
+                        &event as *const _,
+                    ))?;
+                }
+            }
+        }
+
+        Ok(handle)
+    }
+}
+
+/// Callback procedure that will be called for each event delivered to a parameter listener.
+extern "C" fn event_listener_proc(
+    in_callback_ref_con: *mut c_void,
+    _in_object: *mut c_void,
+    in_event: *const sys::AudioUnitEvent,
+    in_event_host_time: u64,
+    in_parameter_value: sys::AudioUnitParameterValue,
+) {
+    let wrapper = in_callback_ref_con as *mut ParameterListenerFnWrapper;
+    unsafe {
+        let kind = match (*in_event).mEventType {
+            sys::kAudioUnitEvent_ParameterValueChange => ParameterEventKind::ValueChange,
+            sys::kAudioUnitEvent_BeginParameterChangeGesture => ParameterEventKind::BeginGesture,
+            sys::kAudioUnitEvent_EndParameterChangeGesture => ParameterEventKind::EndGesture,
+            _ => return,
+        };
+        let parameter = (*in_event).mArgument.mParameter;
+        let event = ParameterEvent {
+            kind,
+            id: parameter.mParameterID,
+            scope: parameter.mScope,
+            element: parameter.mElement,
+            value: in_parameter_value,
+            host_time: in_event_host_time,
+        };
+        (*(*wrapper).callback)(event);
+    }
+}
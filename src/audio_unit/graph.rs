@@ -0,0 +1,612 @@
+//! A wrapper around **AUGraph**, for assembling several **AudioUnit**s into a processing graph.
+//!
+//! While deprecated in favour of `AVAudioEngine`, the **AUGraph** API remains the simplest way of
+//! connecting units from plain C on the macOS versions supported by this crate.
+//!
+//! Find the original AUGraph reference [here](https://developer.apple.com/documentation/audiotoolbox/audio_unit_processing_graph_services).
+
+use super::render_callback::{action_flags, input_proc, Args, Data, InputProcFnWrapper};
+use super::{AudioUnit, Element, IOType, Scope, StreamFormat, Type};
+use crate::error::{AUGraphError, Error};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::ptr;
+use sys;
+
+/// Identifies a node within an **AuGraph**, as returned by
+/// [**AuGraph::add_node**](./struct.AuGraph.html#method.add_node).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(sys::AUNode);
+
+impl NodeId {
+    /// The raw `AUNode`.
+    pub fn as_raw(&self) -> sys::AUNode {
+        self.0
+    }
+}
+
+/// The **AudioUnit** of a node, borrowed from its **AuGraph** via
+/// [**AuGraph::node_audio_unit**](./struct.AuGraph.html#method.node_audio_unit).
+///
+/// The unit is only readable through `Deref`, apart from its properties and parameters, so that
+/// no callbacks can be set on it that would be freed while the graph still calls them.
+pub struct NodeUnit<'a> {
+    audio_unit: AudioUnit,
+    graph: PhantomData<&'a AuGraph>,
+}
+
+impl<'a> NodeUnit<'a> {
+    /// Sets the value of a property of the node's unit.
+    ///
+    /// See [**AudioUnit::set_property**](../struct.AudioUnit.html#method.set_property).
+    pub fn set_property<T>(
+        &mut self,
+        id: u32,
+        scope: Scope,
+        elem: impl Into<u32>,
+        maybe_data: Option<&T>,
+    ) -> Result<(), Error> {
+        self.audio_unit.set_property(id, scope, elem, maybe_data)
+    }
+
+    /// Sets the value of a parameter of the node's unit.
+    ///
+    /// See [**AudioUnit::set_parameter**](../struct.AudioUnit.html#method.set_parameter).
+    pub fn set_parameter<P, E>(
+        &mut self,
+        id: P,
+        scope: Scope,
+        elem: E,
+        value: f32,
+        buffer_offset_frames: u32,
+    ) -> Result<(), Error>
+    where
+        P: Into<u32>,
+        E: Into<u32>,
+    {
+        self.audio_unit
+            .set_parameter(id, scope, elem, value, buffer_offset_frames)
+    }
+}
+
+impl<'a> Deref for NodeUnit<'a> {
+    type Target = AudioUnit;
+    fn deref(&self) -> &AudioUnit {
+        &self.audio_unit
+    }
+}
+
+/// A graph of audio units, owning the units of its nodes.
+///
+/// Nodes may be added before the graph is opened, but their units are only instantiated once it
+/// is opened via [**open**](./struct.AuGraph.html#method.open). The graph is stopped and disposed,
+/// along with the units of its nodes, when dropped.
+///
+/// Once the graph is initialized, changes to its connections and nodes are not applied
+/// immediately but kept pending until [**update**](./struct.AuGraph.html#method.update) is
+/// called. This allows re-patching a running graph, e.g. swapping an effect, without stopping
+/// the audio.
+pub struct AuGraph {
+    graph: sys::AUGraph,
+    render_notify_callbacks: Vec<*mut InputProcFnWrapper>,
+    // The input callbacks of nodes, along with the node and input that they feed.
+    node_input_callbacks: Vec<(NodeId, u32, *mut InputProcFnWrapper)>,
+    // Input callbacks that were replaced or disconnected, but that an initialized graph may still
+    // call until it is updated.
+    retired_input_callbacks: Vec<*mut InputProcFnWrapper>,
+}
+
+impl AuGraph {
+    /// Create a new, empty graph.
+    pub fn new() -> Result<Self, Error> {
+        let mut graph: sys::AUGraph = ptr::null_mut();
+        unsafe { Error::from_os_status(sys::NewAUGraph(&mut graph as *mut _))? };
+        Ok(AuGraph {
+            graph,
+            render_notify_callbacks: Vec::new(),
+            node_input_callbacks: Vec::new(),
+            retired_input_callbacks: Vec::new(),
+        })
+    }
+
+    /// Add a node for the first component matching the given description.
+    pub fn add_node(&mut self, desc: sys::AudioComponentDescription) -> Result<NodeId, Error> {
+        let mut node: sys::AUNode = 0;
+        unsafe {
+            Error::from_os_status(sys::AUGraphAddNode(
+                self.graph,
+                &desc as *const _,
+                &mut node as *mut _,
+            ))?;
+        }
+        Ok(NodeId(node))
+    }
+
+    /// Remove the given node from the graph, disposing of its unit.
+    ///
+    /// On an initialized graph the node is removed by the next
+    /// [**update**](./struct.AuGraph.html#method.update).
+    pub fn remove_node(&mut self, node: NodeId) -> Result<(), Error> {
+        unsafe { Error::from_os_status(sys::AUGraphRemoveNode(self.graph, node.0))? };
+        self.retire_input_callbacks(|(n, _)| n == node);
+        Ok(())
+    }
+
+    /// Open the graph, instantiating the units of its nodes.
+    ///
+    /// Units of nodes added after the graph is opened are instantiated immediately.
+    pub fn open(&mut self) -> Result<(), Error> {
+        unsafe { Error::from_os_status(sys::AUGraphOpen(self.graph)) }
+    }
+
+    /// The number of nodes in the graph.
+    pub fn node_count(&self) -> Result<u32, Error> {
+        let mut count = 0u32;
+        unsafe {
+            Error::from_os_status(sys::AUGraphGetNodeCount(self.graph, &mut count as *mut _))?;
+        }
+        Ok(count)
+    }
+
+    /// The **AudioUnit** of the given node, e.g. for setting its properties and parameters.
+    ///
+    /// The graph must be opened first. The unit remains owned by the graph, so the returned
+    /// **NodeUnit** borrows the graph and only allows reading the unit and setting its properties
+    /// and parameters. Callbacks of nodes are set via the graph, e.g. via
+    /// [**set_node_input_callback**](./struct.AuGraph.html#method.set_node_input_callback).
+    pub fn node_audio_unit(&self, node: NodeId) -> Result<NodeUnit<'_>, Error> {
+        let mut instance: sys::AudioUnit = ptr::null_mut();
+        unsafe {
+            Error::from_os_status(sys::AUGraphNodeInfo(
+                self.graph,
+                node.0,
+                ptr::null_mut(),
+                &mut instance as *mut _,
+            ))?;
+            // No callbacks can be set through the **NodeUnit**, so the wrapper owns nothing that
+            // could be freed while the graph still uses it.
+            Ok(NodeUnit {
+                audio_unit: AudioUnit::from_raw(instance, false),
+                graph: PhantomData,
+            })
+        }
+    }
+
+    /// Connect the given output bus of the `source` node to the given input bus of the
+    /// `destination` node.
+    ///
+    /// Returns `AUGraphError::InvalidConnection` if the destination input is already connected.
+    /// On an initialized graph the connection is made by the next
+    /// [**update**](./struct.AuGraph.html#method.update).
+    pub fn connect(
+        &mut self,
+        source: NodeId,
+        source_output: u32,
+        destination: NodeId,
+        destination_input: u32,
+    ) -> Result<(), Error> {
+        unsafe {
+            Error::from_os_status(sys::AUGraphConnectNodeInput(
+                self.graph,
+                source.0,
+                source_output,
+                destination.0,
+                destination_input,
+            ))
+        }
+    }
+
+    /// Remove the connection or input callback feeding the given input bus of the `destination`
+    /// node.
+    ///
+    /// On an initialized graph the connection is removed by the next
+    /// [**update**](./struct.AuGraph.html#method.update).
+    pub fn disconnect_input(
+        &mut self,
+        destination: NodeId,
+        destination_input: u32,
+    ) -> Result<(), Error> {
+        unsafe {
+            Error::from_os_status(sys::AUGraphDisconnectNodeInput(
+                self.graph,
+                destination.0,
+                destination_input,
+            ))?;
+        }
+        self.retire_input_callbacks(|key| key == (destination, destination_input));
+        Ok(())
+    }
+
+    /// Pass a render callback providing the audio for the given input bus of a node, e.g. one of
+    /// the inputs of a mixer node.
+    ///
+    /// The graph must be opened first, and `D` must match the stream format of the input. The
+    /// callback is kept alive until it is replaced, its input is disconnected via
+    /// [**disconnect_input**](./struct.AuGraph.html#method.disconnect_input), its node is removed
+    /// or the graph is dropped. On an initialized graph the callback is installed by the next
+    /// [**update**](./struct.AuGraph.html#method.update).
+    pub fn set_node_input_callback<F, D>(
+        &mut self,
+        node: NodeId,
+        input_number: u32,
+        f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        let stream_format = {
+            let audio_unit = self.node_audio_unit(node)?;
+            let id = sys::kAudioUnitProperty_StreamFormat;
+            let asbd = audio_unit.get_property(id, Scope::Input, input_number)?;
+            StreamFormat::from_asbd(asbd)?
+        };
+        if !D::does_stream_format_match(&stream_format) {
+            return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+        }
+
+        let wrapper_ptr = Box::into_raw(InputProcFnWrapper::new_render(f, &stream_format));
+        let render_callback = sys::AURenderCallbackStruct {
+            inputProc: Some(input_proc),
+            inputProcRefCon: wrapper_ptr as *mut c_void,
+        };
+        unsafe {
+            let status = sys::AUGraphSetNodeInputCallback(
+                self.graph,
+                node.0,
+                input_number,
+                &render_callback as *const _,
+            );
+            if let Err(err) = Error::from_os_status(status) {
+                let _: Box<InputProcFnWrapper> = Box::from_raw(wrapper_ptr);
+                return Err(err);
+            }
+        }
+        self.retire_input_callbacks(|key| key == (node, input_number));
+        self.node_input_callbacks
+            .push((node, input_number, wrapper_ptr));
+        Ok(())
+    }
+
+    /// Stop tracking the input callbacks matching `predicate` as installed.
+    ///
+    /// An initialized graph may still call them until its next update, so they are kept alive
+    /// until then. Otherwise they are freed right away.
+    fn retire_input_callbacks<P>(&mut self, predicate: P)
+    where
+        P: Fn((NodeId, u32)) -> bool,
+    {
+        let retired = &mut self.retired_input_callbacks;
+        self.node_input_callbacks
+            .retain(|&(node, input_number, callback)| {
+                if predicate((node, input_number)) {
+                    retired.push(callback);
+                    return false;
+                }
+                true
+            });
+        // If the state can't be queried, play it safe and wait for an update.
+        if let Ok(false) = self.is_initialized() {
+            self.free_retired_input_callbacks();
+        }
+    }
+
+    /// Free the input callbacks that the graph no longer calls.
+    fn free_retired_input_callbacks(&mut self) {
+        for callback in self.retired_input_callbacks.drain(..) {
+            let _: Box<InputProcFnWrapper> = unsafe { Box::from_raw(callback) };
+        }
+    }
+
+    /// Initialize the graph, making its connections and initializing the units of its nodes.
+    ///
+    /// The graph must be opened first.
+    pub fn initialize(&mut self) -> Result<(), Error> {
+        unsafe { Error::from_os_status(sys::AUGraphInitialize(self.graph)) }
+    }
+
+    /// Start rendering by starting the graph's output node.
+    ///
+    /// The graph must be initialized first and contain exactly one output node.
+    pub fn start(&mut self) -> Result<(), Error> {
+        unsafe { Error::from_os_status(sys::AUGraphStart(self.graph)) }
+    }
+
+    /// Stop rendering.
+    pub fn stop(&mut self) -> Result<(), Error> {
+        unsafe { Error::from_os_status(sys::AUGraphStop(self.graph)) }
+    }
+
+    /// Apply the pending changes to the connections and nodes of an initialized graph, e.g. while
+    /// it is running.
+    ///
+    /// The changes are applied asynchronously, between render cycles, so this doesn't block.
+    /// Returns whether the changes have been applied. If not, e.g. because the render thread is
+    /// busy, the changes remain pending and `update` should be called again later.
+    pub fn update(&mut self) -> Result<bool, Error> {
+        let mut is_updated: sys::Boolean = 0;
+        unsafe {
+            Error::from_os_status(sys::AUGraphUpdate(self.graph, &mut is_updated as *mut _))?;
+        }
+        if is_updated != 0 {
+            // The graph no longer calls the input callbacks that were replaced or disconnected.
+            self.free_retired_input_callbacks();
+        }
+        Ok(is_updated != 0)
+    }
+
+    /// Whether the graph has been initialized via
+    /// [**initialize**](./struct.AuGraph.html#method.initialize).
+    pub fn is_initialized(&self) -> Result<bool, Error> {
+        let mut is_initialized: sys::Boolean = 0;
+        unsafe {
+            let status = sys::AUGraphIsInitialized(self.graph, &mut is_initialized as *mut _);
+            Error::from_os_status(status)?;
+        }
+        Ok(is_initialized != 0)
+    }
+
+    /// Whether the graph is currently rendering.
+    pub fn is_running(&self) -> Result<bool, Error> {
+        let mut is_running: sys::Boolean = 0;
+        unsafe {
+            Error::from_os_status(sys::AUGraphIsRunning(self.graph, &mut is_running as *mut _))?;
+        }
+        Ok(is_running != 0)
+    }
+
+    /// Register a closure that is called with the audio rendered by the graph's output node after
+    /// each render cycle, e.g. for metering the final mix.
+    ///
+    /// The graph must be opened first and `D` must match the stream format of the output node's
+    /// input, otherwise `Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat` is
+    /// returned. Render cycles whose buffers don't match the layout of `D` are skipped. The
+    /// closure is called on the render thread, so it must not block or allocate. It remains
+    /// registered until the graph is dropped.
+    pub fn add_render_notify<F, D>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) + 'static,
+        D: Data,
+    {
+        let stream_format = {
+            let output = self.output_node()?;
+            let audio_unit = self.node_audio_unit(output)?;
+            let id = sys::kAudioUnitProperty_StreamFormat;
+            let asbd = audio_unit.get_property(id, Scope::Input, Element::Output)?;
+            StreamFormat::from_asbd(asbd)?
+        };
+        if !D::does_stream_format_match(&stream_format) {
+            return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+        }
+
+        let notify_fn = move |io_action_flags: *mut sys::AudioUnitRenderActionFlags,
+                              in_time_stamp: *const sys::AudioTimeStamp,
+                              in_bus_number: sys::UInt32,
+                              in_number_frames: sys::UInt32,
+                              io_data: *mut sys::AudioBufferList|
+              -> sys::OSStatus {
+            if io_action_flags.is_null() || in_time_stamp.is_null() || io_data.is_null() {
+                return 0;
+            }
+            if unsafe { *io_action_flags } & sys::kAudioUnitRenderAction_PostRender == 0 {
+                return 0;
+            }
+            let data = match unsafe { D::try_from_input_proc_args(in_number_frames, io_data) } {
+                Ok(data) => data,
+                Err(_) => return 0,
+            };
+            let args = unsafe {
+                Args {
+                    data,
+                    time_stamp: *in_time_stamp,
+                    flags: action_flags::Handle::from_ptr(io_action_flags),
+                    bus_number: in_bus_number,
+                    num_frames: in_number_frames as usize,
+                }
+            };
+            f(args);
+            0
+        };
+        let wrapper_ptr = Box::into_raw(InputProcFnWrapper::new_raw(notify_fn));
+        unsafe {
+            let status = sys::AUGraphAddRenderNotify(
+                self.graph,
+                Some(input_proc),
+                wrapper_ptr as *mut c_void,
+            );
+            if let Err(err) = Error::from_os_status(status) {
+                let _: Box<InputProcFnWrapper> = Box::from_raw(wrapper_ptr);
+                return Err(err);
+            }
+        }
+        self.render_notify_callbacks.push(wrapper_ptr);
+        Ok(())
+    }
+
+    /// The node of the graph's output unit, which drives the rendering of the graph.
+    ///
+    /// Returns `AUGraphError::OutputNodeError` if the graph has no output node.
+    fn output_node(&self) -> Result<NodeId, Error> {
+        let output_type = Type::IO(IOType::DefaultOutput).as_u32();
+        for index in 0..self.node_count()? {
+            let mut node: sys::AUNode = 0;
+            let mut desc: sys::AudioComponentDescription = unsafe { mem::zeroed() };
+            unsafe {
+                let status = sys::AUGraphGetIndNode(self.graph, index, &mut node as *mut _);
+                Error::from_os_status(status)?;
+                Error::from_os_status(sys::AUGraphNodeInfo(
+                    self.graph,
+                    node,
+                    &mut desc as *mut _,
+                    ptr::null_mut(),
+                ))?;
+            }
+            if desc.componentType == output_type {
+                return Ok(NodeId(node));
+            }
+        }
+        Err(Error::AUGraph(AUGraphError::OutputNodeError))
+    }
+
+    /// The average CPU load of the graph's render thread as a fraction from `0.0` to `1.0`.
+    pub fn cpu_load(&self) -> Result<f32, Error> {
+        let mut load = 0.0;
+        unsafe { Error::from_os_status(sys::AUGraphGetCPULoad(self.graph, &mut load as *mut _))? };
+        Ok(load)
+    }
+
+    /// The maximum CPU load of the graph's render thread as a fraction from `0.0` to `1.0`,
+    /// since this was last called.
+    pub fn max_cpu_load(&self) -> Result<f32, Error> {
+        let mut load = 0.0;
+        unsafe {
+            Error::from_os_status(sys::AUGraphGetMaxCPULoad(self.graph, &mut load as *mut _))?
+        };
+        Ok(load)
+    }
+
+    /// The underlying `AUGraph`, for calling Core Audio functions that are not yet wrapped by
+    /// this crate.
+    ///
+    /// The graph remains owned by the **AuGraph** and must not be disposed of.
+    pub fn as_raw(&self) -> sys::AUGraph {
+        self.graph
+    }
+}
+
+impl Drop for AuGraph {
+    fn drop(&mut self) {
+        unsafe {
+            // We can't do anything useful about failures here.
+            sys::AUGraphStop(self.graph);
+            for wrapper_ptr in self.render_notify_callbacks.drain(..) {
+                sys::AUGraphRemoveRenderNotify(
+                    self.graph,
+                    Some(input_proc),
+                    wrapper_ptr as *mut c_void,
+                );
+                let _: Box<InputProcFnWrapper> = Box::from_raw(wrapper_ptr);
+            }
+            sys::AUGraphUninitialize(self.graph);
+            sys::DisposeAUGraph(self.graph);
+            let callbacks = self.node_input_callbacks.drain(..).map(|(_, _, c)| c);
+            for callback in callbacks.chain(self.retired_input_callbacks.drain(..)) {
+                let _: Box<InputProcFnWrapper> = Box::from_raw(callback);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::super::render_callback::data;
+    use super::super::test_util::render_offline;
+    use super::super::EffectType;
+    use super::*;
+    use std::sync::Arc;
+
+    /// The description of the Apple component of the given type.
+    fn description<T: Into<Type>>(ty: T) -> sys::AudioComponentDescription {
+        let ty = ty.into();
+        sys::AudioComponentDescription {
+            componentType: ty.as_u32(),
+            componentSubType: ty.as_subtype_u32().unwrap(),
+            componentManufacturer: sys::kAudioUnitManufacturer_Apple,
+            componentFlags: 0,
+            componentFlagsMask: 0,
+        }
+    }
+
+    /// Fill every channel with a constant, which passes through a low-pass filter unchanged.
+    fn dc(mut args: Args<data::NonInterleaved<f32>>) -> Result<(), ()> {
+        for channel in args.data.channels_mut() {
+            channel.iter_mut().for_each(|s| *s = 0.5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_notify_must_match_the_output_format() {
+        let mut graph = AuGraph::new().unwrap();
+        graph.add_node(description(IOType::GenericOutput)).unwrap();
+        graph.open().unwrap();
+
+        let result = graph.add_render_notify(|_: Args<data::NonInterleaved<i16>>| ());
+        assert!(matches!(
+            result,
+            Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat)
+        ));
+        graph
+            .add_render_notify(|_: Args<data::NonInterleaved<f32>>| ())
+            .unwrap();
+    }
+
+    #[test]
+    fn replaced_input_callbacks_of_an_uninitialized_graph_are_freed() {
+        let mut graph = AuGraph::new().unwrap();
+        let filter = graph
+            .add_node(description(EffectType::LowPassFilter))
+            .unwrap();
+        graph.open().unwrap();
+
+        let captured = Arc::new(());
+        let held = captured.clone();
+        graph
+            .set_node_input_callback(filter, 0, move |args| {
+                let _ = &held;
+                dc(args)
+            })
+            .unwrap();
+        assert_eq!(Arc::strong_count(&captured), 2);
+        graph.set_node_input_callback(filter, 0, dc).unwrap();
+        assert_eq!(Arc::strong_count(&captured), 1);
+    }
+
+    #[test]
+    fn swapping_an_effect_node_while_playing() {
+        let mut graph = AuGraph::new().unwrap();
+        let delay = graph.add_node(description(EffectType::Delay)).unwrap();
+        let output = graph.add_node(description(IOType::GenericOutput)).unwrap();
+        graph.open().unwrap();
+        // Fully wet with the default delay of one second, so the first slices are silent.
+        graph
+            .node_audio_unit(delay)
+            .unwrap()
+            .set_parameter(sys::kDelayParam_WetDryMix, Scope::Global, 0u32, 100.0, 0)
+            .unwrap();
+        graph.set_node_input_callback(delay, 0, dc).unwrap();
+        graph.connect(delay, 0, output, 0).unwrap();
+        graph.initialize().unwrap();
+        graph.start().unwrap();
+
+        let rendered = render_offline(&graph.node_audio_unit(output).unwrap(), 4096, 512).unwrap();
+        assert!(rendered.iter().all(|s| s.abs() < 0.01));
+
+        // Replace the delay by a low-pass filter, fed by the same callback.
+        let filter = graph
+            .add_node(description(EffectType::LowPassFilter))
+            .unwrap();
+        graph.disconnect_input(output, 0).unwrap();
+        graph.disconnect_input(delay, 0).unwrap();
+        graph.remove_node(delay).unwrap();
+        graph.set_node_input_callback(filter, 0, dc).unwrap();
+        graph.connect(filter, 0, output, 0).unwrap();
+        // The changes are applied between render cycles, so keep rendering until they are.
+        let mut updated = false;
+        for _ in 0..100 {
+            if graph.update().unwrap() {
+                updated = true;
+                break;
+            }
+            render_offline(&graph.node_audio_unit(output).unwrap(), 512, 512).unwrap();
+        }
+        assert!(updated, "the graph was never updated");
+
+        let rendered = render_offline(&graph.node_audio_unit(output).unwrap(), 4096, 512).unwrap();
+        assert!(rendered[2048..].iter().all(|s| (s - 0.5).abs() < 0.01));
+        assert_eq!(graph.node_count().unwrap(), 2);
+        graph.stop().unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! Asynchronous instantiation of **AudioUnit**s via `AudioComponentInstantiate`.
+//!
+//! Version 3 and sandboxed audio units must be instantiated asynchronously, optionally in a
+//! separate process. The completion handler expected by `AudioComponentInstantiate` is an
+//! Objective-C block, which we construct by hand following the
+//! [block ABI](https://clang.llvm.org/docs/Block-ABI-Apple.html).
+
+use super::AudioUnit;
+use crate::error::Error;
+use std::os::raw::{c_int, c_ulong, c_void};
+use std::ptr;
+use std::sync::mpsc;
+use sys;
+
+bitflags! {
+    /// Options controlling how an **AudioUnit** is instantiated by
+    /// [**AudioUnit::new_async**](../struct.AudioUnit.html#method.new_async).
+    ///
+    /// Original documentation [here](https://developer.apple.com/documentation/audiotoolbox/audiocomponentinstantiationoptions).
+    pub struct InstantiationOptions: u32 {
+        /// Load the audio unit in a separate process, isolating the host from crashes within it.
+        ///
+        /// **Available** in OS X v10.11 and later.
+        const LOAD_OUT_OF_PROCESS = sys::kAudioComponentInstantiation_LoadOutOfProcess;
+        /// Load the audio unit within the host process where possible.
+        ///
+        /// **Available** in OS X v10.11 and later.
+        const LOAD_IN_PROCESS = sys::kAudioComponentInstantiation_LoadInProcess;
+    }
+}
+
+/// The type of closure called once asynchronous instantiation completes.
+type CompletionFn = dyn FnOnce(Result<AudioUnit, Error>) + Send;
+
+/// The layout of an Objective-C block literal.
+#[repr(C)]
+struct BlockLiteral {
+    isa: *const c_void,
+    flags: c_int,
+    reserved: c_int,
+    invoke: unsafe extern "C" fn(*mut BlockLiteral, sys::AudioComponentInstance, sys::OSStatus),
+    descriptor: *const BlockDescriptor,
+    // The captured state. A `*mut Box<CompletionFn>` that is freed when the block is invoked.
+    completion: *mut Box<CompletionFn>,
+}
+
+/// The descriptor of a block literal without copy or dispose helpers.
+#[repr(C)]
+struct BlockDescriptor {
+    reserved: c_ulong,
+    size: c_ulong,
+}
+
+static BLOCK_DESCRIPTOR: BlockDescriptor = BlockDescriptor {
+    reserved: 0,
+    size: std::mem::size_of::<BlockLiteral>() as c_ulong,
+};
+
+extern "C" {
+    static _NSConcreteStackBlock: c_void;
+}
+
+impl AudioUnit {
+    /// Asynchronously instantiate the first audio unit matching the given description.
+    ///
+    /// This is required for version 3 and sandboxed audio units, which cannot be created via
+    /// [**AudioUnit::new**](./struct.AudioUnit.html#method.new). As with `new`, the unit is
+    /// initialized before being passed to the completion.
+    ///
+    /// The `completion` is called exactly once on a queue chosen by Core Audio, so it must not
+    /// assume it runs on any particular thread. Use a channel or similar to hand the result back.
+    /// An error returned directly by this function means that the completion will not be called.
+    ///
+    /// **Available** in OS X v10.11 and later.
+    pub fn new_async<F>(
+        desc: sys::AudioComponentDescription,
+        options: InstantiationOptions,
+        completion: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(Result<AudioUnit, Error>) + Send + 'static,
+    {
+        unsafe {
+            let component = sys::AudioComponentFindNext(ptr::null_mut(), &desc as *const _);
+            if component.is_null() {
+                return Err(Error::NoMatchingDefaultAudioUnitFound);
+            }
+
+            let completion: Box<CompletionFn> = Box::new(completion);
+            // The block lives on our stack; `AudioComponentInstantiate` copies it to the heap
+            // (along with the pointer to our completion) before returning.
+            let mut block = BlockLiteral {
+                isa: &_NSConcreteStackBlock as *const c_void,
+                flags: 0,
+                reserved: 0,
+                invoke: instantiate_completion_invoke,
+                descriptor: &BLOCK_DESCRIPTOR as *const _,
+                completion: Box::into_raw(Box::new(completion)),
+            };
+            sys::AudioComponentInstantiate(
+                component,
+                options.bits(),
+                &mut block as *mut BlockLiteral as *mut c_void,
+            );
+        }
+        Ok(())
+    }
+
+    /// Asynchronously instantiate a version 3 audio unit (an app extension) out of process.
+    ///
+    /// This is the same as [**AudioUnit::new_async**](./struct.AudioUnit.html#method.new_async)
+    /// with `InstantiationOptions::LOAD_OUT_OF_PROCESS` always set, which is how most third-party
+    /// version 3 units must be loaded.
+    ///
+    /// Out-of-process units run sandboxed in a separate extension process, so:
+    ///
+    /// - a crash in the unit does not bring down the host, but rendering incurs some additional
+    ///   latency and CPU overhead for the inter-process communication;
+    /// - the unit can only access files within its own container, so paths passed to it, e.g.
+    ///   via properties, may not be readable by it;
+    /// - the host process must be allowed to communicate with the extension, which sandboxed
+    ///   hosts may need an entitlement for.
+    ///
+    /// The `completion` is called exactly once on a queue chosen by Core Audio.
+    ///
+    /// **Available** in OS X v10.11 and later.
+    pub fn instantiate_v3<F>(
+        desc: sys::AudioComponentDescription,
+        options: InstantiationOptions,
+        completion: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(Result<AudioUnit, Error>) + Send + 'static,
+    {
+        let options = options | InstantiationOptions::LOAD_OUT_OF_PROCESS;
+        AudioUnit::new_async(desc, options, completion)
+    }
+
+    /// The same as [**AudioUnit::new_async**](./struct.AudioUnit.html#method.new_async) but blocks
+    /// the current thread until instantiation completes.
+    ///
+    /// Note that Core Audio may deliver the completion on the main queue, so calling this from
+    /// the main thread of an application may deadlock. Prefer `new_async` on the main thread.
+    ///
+    /// **Available** in OS X v10.11 and later.
+    pub fn new_instantiated(
+        desc: sys::AudioComponentDescription,
+        options: InstantiationOptions,
+    ) -> Result<AudioUnit, Error> {
+        let (tx, rx) = mpsc::channel();
+        AudioUnit::new_async(desc, options, move |result| {
+            // The receiver only hangs up once it no longer cares about the result.
+            tx.send(result).ok();
+        })?;
+        rx.recv().map_err(|_| Error::Unspecified)?
+    }
+}
+
+/// The invoke function of the completion block passed to `AudioComponentInstantiate`.
+unsafe extern "C" fn instantiate_completion_invoke(
+    block: *mut BlockLiteral,
+    instance: sys::AudioComponentInstance,
+    status: sys::OSStatus,
+) {
+    // Take back ownership of the completion. The block is only ever invoked once.
+    let completion: Box<Box<CompletionFn>> = Box::from_raw((*block).completion);
+    let result = Error::from_os_status(status).and_then(|()| {
+        let mut audio_unit = AudioUnit::from_raw(instance, true);
+        audio_unit.initialize()?;
+        Ok(audio_unit)
+    });
+    (*completion)(result);
+}
@@ -2,31 +2,37 @@
 /// These functions are only implemented for macOS, not iOS.
 use crate::error::Error;
 use std::collections::VecDeque;
-use std::ffi::CStr;
-use std::os::raw::{c_char, c_void};
+use std::os::raw::c_void;
+use std::process;
 use std::ptr::null;
+use std::slice;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{mem, thread};
 
-use core_foundation_sys::string::{CFStringGetCString, CFStringGetCStringPtr, CFStringRef};
+use core_foundation_sys::base::CFRelease;
+use core_foundation_sys::string::CFStringRef;
 use sys;
 use sys::pid_t;
 use sys::{
-    kAudioDevicePropertyAvailableNominalSampleRates, kAudioDevicePropertyDeviceIsAlive,
-    kAudioDevicePropertyDeviceNameCFString, kAudioDevicePropertyHogMode,
-    kAudioDevicePropertyNominalSampleRate, kAudioDevicePropertyScopeOutput, kAudioHardwareNoError,
-    kAudioHardwarePropertyDefaultInputDevice, kAudioHardwarePropertyDefaultOutputDevice,
-    kAudioHardwarePropertyDevices, kAudioObjectPropertyElementMaster,
-    kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject,
+    kAudioDevicePropertyAvailableNominalSampleRates, kAudioDevicePropertyBufferFrameSizeRange,
+    kAudioDevicePropertyDeviceIsAlive, kAudioDevicePropertyDeviceNameCFString,
+    kAudioDevicePropertyHogMode, kAudioDevicePropertyLatency,
+    kAudioDevicePropertyNominalSampleRate, kAudioDevicePropertyScopeInput,
+    kAudioDevicePropertyScopeOutput, kAudioDevicePropertyStreamConfiguration,
+    kAudioDevicePropertyStreams, kAudioHardwareNoError, kAudioHardwarePropertyDefaultInputDevice,
+    kAudioHardwarePropertyDefaultOutputDevice, kAudioHardwarePropertyDevices,
+    kAudioObjectPropertyElementMaster, kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject,
     kAudioOutputUnitProperty_CurrentDevice, kAudioOutputUnitProperty_EnableIO,
     kAudioStreamPropertyAvailablePhysicalFormats, kAudioStreamPropertyPhysicalFormat,
-    kCFStringEncodingUTF8, AudioDeviceID, AudioObjectAddPropertyListener,
+    kAudioStreamPropertyVirtualFormat, AudioBuffer, AudioBufferList, AudioDeviceGetCurrentTime,
+    AudioDeviceID, AudioDeviceTranslateTime, AudioObjectAddPropertyListener,
     AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectID,
     AudioObjectPropertyAddress, AudioObjectRemovePropertyListener, AudioObjectSetPropertyData,
-    AudioStreamBasicDescription, AudioStreamRangedDescription, AudioValueRange, OSStatus,
+    AudioStreamBasicDescription, AudioStreamID, AudioStreamRangedDescription, AudioTimeStamp,
+    AudioValueRange, OSStatus,
 };
 
 use crate::audio_unit::audio_format::{AudioFormat, LinearPcmFlags};
@@ -34,6 +40,36 @@ use crate::audio_unit::sample_format::SampleFormat;
 use crate::audio_unit::stream_format::StreamFormat;
 use crate::audio_unit::{AudioUnit, Element, IOType, Scope};
 
+/// A range of values reported by a property, e.g. the sample rates supported by a device.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ValueRange {
+    /// The smallest value within the range.
+    pub min: f64,
+    /// The largest value within the range.
+    pub max: f64,
+}
+
+impl ValueRange {
+    /// Whether the given value lies within the range, including its bounds.
+    pub fn contains(&self, value: f64) -> bool {
+        self.min <= value && value <= self.max
+    }
+
+    /// Restrict the given value to the range.
+    pub fn clamp(&self, value: f64) -> f64 {
+        value.max(self.min).min(self.max)
+    }
+}
+
+impl From<AudioValueRange> for ValueRange {
+    fn from(range: AudioValueRange) -> Self {
+        ValueRange {
+            min: range.mMinimum,
+            max: range.mMaximum,
+        }
+    }
+}
+
 /// Helper function to get the device id of the default input or output device.
 pub fn get_default_device_id(input: bool) -> Option<AudioDeviceID> {
     let selector = if input {
@@ -114,6 +150,87 @@ pub fn audio_unit_from_device_id(
     Ok(audio_unit)
 }
 
+impl AudioUnit {
+    /// Create a **HalOutput** unit configured to capture audio from an input device, ready for an
+    /// input callback to be set via
+    /// [**set_input_callback**](./struct.AudioUnit.html#method.set_input_callback).
+    ///
+    /// While uninitialized, the unit is configured as follows:
+    ///
+    /// - input is enabled on element `1` and output is disabled on element `0`;
+    /// - the given device, or the default input device if `None`, is selected;
+    /// - `format` is set as the format of the audio delivered to the application, i.e. on the
+    ///   output scope of element `1`;
+    /// - the unit doesn't allocate its own buffers, as the input callback provides them;
+    /// - the maximum frames per slice is raised to the largest buffer size of the device, so that
+    ///   rendering doesn't fail if the buffer size of the device is increased.
+    ///
+    /// The unit doesn't convert between sample rates on input, so the sample rate of `format`
+    /// must match that of the device. See
+    /// [**set_device_sample_rate_blocking**](./macos_helpers/fn.set_device_sample_rate_blocking.html).
+    ///
+    /// Returns `Error::NoDefaultDevice` if `device` is `None` and there is no default input
+    /// device.
+    pub fn new_input_capture(
+        device: Option<AudioDeviceID>,
+        format: StreamFormat,
+    ) -> Result<AudioUnit, Error> {
+        let device_id = match device {
+            Some(device_id) => device_id,
+            None => get_default_device_id(true).ok_or(Error::NoDefaultDevice)?,
+        };
+
+        let mut audio_unit = AudioUnit::new(IOType::HalOutput)?;
+        audio_unit.uninitialize()?;
+
+        let enable_input = 1u32;
+        audio_unit.set_property(
+            kAudioOutputUnitProperty_EnableIO,
+            Scope::Input,
+            Element::Input,
+            Some(&enable_input),
+        )?;
+        let disable_output = 0u32;
+        audio_unit.set_property(
+            kAudioOutputUnitProperty_EnableIO,
+            Scope::Output,
+            Element::Output,
+            Some(&disable_output),
+        )?;
+
+        audio_unit.set_property(
+            kAudioOutputUnitProperty_CurrentDevice,
+            Scope::Global,
+            Element::Output,
+            Some(&device_id),
+        )?;
+
+        let asbd = format.to_asbd();
+        audio_unit.set_property(
+            sys::kAudioUnitProperty_StreamFormat,
+            Scope::Output,
+            Element::Input,
+            Some(&asbd),
+        )?;
+
+        let should_allocate = 0u32;
+        audio_unit.set_property(
+            sys::kAudioUnitProperty_ShouldAllocateBuffer,
+            Scope::Output,
+            Element::Input,
+            Some(&should_allocate),
+        )?;
+
+        let max_buffer_frames = get_buffer_frame_size_range(device_id)?.max as u32;
+        if max_buffer_frames > audio_unit.maximum_frames_per_slice()? {
+            audio_unit.set_maximum_frames_per_slice(max_buffer_frames)?;
+        }
+
+        audio_unit.initialize()?;
+        Ok(audio_unit)
+    }
+}
+
 /// List all audio device ids on the system.
 pub fn get_audio_device_ids() -> Result<Vec<AudioDeviceID>, Error> {
     let property_address = AudioObjectPropertyAddress {
@@ -179,7 +296,7 @@ pub fn get_device_name(device_id: AudioDeviceID) -> Result<String, Error> {
 
     let device_name: CFStringRef = null();
     let data_size = mem::size_of::<CFStringRef>();
-    let c_str = unsafe {
+    unsafe {
         let status = AudioObjectGetPropertyData(
             device_id,
             &property_address as *const _,
@@ -189,34 +306,174 @@ pub fn get_device_name(device_id: AudioDeviceID) -> Result<String, Error> {
             &device_name as *const _ as *mut _,
         );
         try_status_or_return!(status);
+        if device_name.is_null() {
+            return Err(Error::Unspecified);
+        }
+        // The name is owned by the caller, so release it once it has been copied.
+        let name = super::cf_string_to_string(device_name);
+        CFRelease(device_name as *const c_void);
+        name.ok_or(Error::Unspecified)
+    }
+}
 
-        let c_string: *const c_char = CFStringGetCStringPtr(device_name, kCFStringEncodingUTF8);
-        if c_string.is_null() {
-            let status = AudioObjectGetPropertyData(
-                device_id,
-                &property_address as *const _,
-                0,
-                null(),
-                &data_size as *const _ as *mut _,
-                &device_name as *const _ as *mut _,
-            );
-            try_status_or_return!(status);
-            let mut buf: [i8; 255] = [0; 255];
-            let result = CFStringGetCString(
-                device_name,
-                buf.as_mut_ptr(),
-                buf.len() as _,
-                kCFStringEncodingUTF8,
-            );
-            if result == 0 {
-                return Err(Error::Unknown(result as i32));
+/// Get the total number of input channels offered by a device.
+pub fn get_device_input_channels(device_id: AudioDeviceID) -> Result<u32, Error> {
+    get_device_channels(device_id, kAudioDevicePropertyScopeInput)
+}
+
+/// Get the total number of output channels offered by a device.
+pub fn get_device_output_channels(device_id: AudioDeviceID) -> Result<u32, Error> {
+    get_device_channels(device_id, kAudioDevicePropertyScopeOutput)
+}
+
+/// Get the latency of a device in frames for the given scope, which must be either
+/// `Scope::Input` or `Scope::Output`. Other scopes return `Error::Unspecified`.
+///
+/// This is the inherent latency of the hardware, e.g. of its converters, and does not include
+/// the buffer size or the latency of the device's streams.
+pub fn get_device_latency(device_id: AudioDeviceID, scope: Scope) -> Result<u32, Error> {
+    let scope = match scope {
+        Scope::Input => kAudioDevicePropertyScopeInput,
+        Scope::Output => kAudioDevicePropertyScopeOutput,
+        _ => return Err(Error::Unspecified),
+    };
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyLatency,
+        mScope: scope,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let mut latency: u32 = 0;
+    let mut data_size = mem::size_of::<u32>() as u32;
+    unsafe {
+        let status = AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &mut data_size as *mut _,
+            &mut latency as *mut _ as *mut c_void,
+        );
+        Error::from_os_status(status)?;
+    }
+    Ok(latency)
+}
+
+/// Get the ranges of nominal sample rates supported by a device.
+///
+/// A range with equal bounds represents a single discrete sample rate.
+pub fn get_available_sample_rates(device_id: AudioDeviceID) -> Result<Vec<ValueRange>, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyAvailableNominalSampleRates,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let ranges = unsafe {
+        let data_size = 0u32;
+        let status = AudioObjectGetPropertyDataSize(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+        );
+        Error::from_os_status(status)?;
+        let n_ranges = data_size as usize / mem::size_of::<AudioValueRange>();
+        let mut ranges: Vec<AudioValueRange> = Vec::with_capacity(n_ranges);
+        let status = AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+            ranges.as_mut_ptr() as *mut _,
+        );
+        Error::from_os_status(status)?;
+        ranges.set_len(data_size as usize / mem::size_of::<AudioValueRange>());
+        ranges
+    };
+    Ok(ranges.into_iter().map(ValueRange::from).collect())
+}
+
+/// Get the range of buffer sizes in frames supported by a device.
+pub fn get_buffer_frame_size_range(device_id: AudioDeviceID) -> Result<ValueRange, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyBufferFrameSizeRange,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let range = unsafe {
+        let mut range: AudioValueRange = mem::zeroed();
+        let data_size = mem::size_of::<AudioValueRange>() as u32;
+        let status = AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+            &mut range as *mut _ as *mut _,
+        );
+        Error::from_os_status(status)?;
+        range
+    };
+    Ok(range.into())
+}
+
+/// Sum the channels of all streams of a device in the given scope, using the `AudioBufferList`
+/// returned for `kAudioDevicePropertyStreamConfiguration`.
+fn get_device_channels(device_id: AudioDeviceID, scope: u32) -> Result<u32, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyStreamConfiguration,
+        mScope: scope,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+
+    macro_rules! try_status_or_return {
+        ($status:expr) => {
+            if $status != kAudioHardwareNoError as i32 {
+                return Err(Error::Unknown($status));
             }
-            let name: &CStr = CStr::from_ptr(buf.as_ptr());
-            return Ok(name.to_str().unwrap().to_owned());
-        }
-        CStr::from_ptr(c_string as *mut _)
+        };
+    }
+
+    let data_size = 0u32;
+    let status = unsafe {
+        AudioObjectGetPropertyDataSize(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+        )
     };
-    Ok(c_str.to_string_lossy().into_owned())
+    try_status_or_return!(status);
+
+    // The `AudioBufferList` is variable length, so allocate a suitably aligned buffer of at
+    // least the reported size.
+    let len = data_size as usize / mem::size_of::<u64>() + 1;
+    let mut data = vec![0u64; len];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+            data.as_mut_ptr() as *mut _,
+        )
+    };
+    try_status_or_return!(status);
+
+    if (data_size as usize) < mem::size_of::<u32>() {
+        return Ok(0);
+    }
+    let channels = unsafe {
+        let buffer_list = data.as_ptr() as *const AudioBufferList;
+        let ptr = (*buffer_list).mBuffers.as_ptr();
+        let len = (*buffer_list).mNumberBuffers as usize;
+        let buffers: &[AudioBuffer] = slice::from_raw_parts(ptr, len);
+        buffers.iter().map(|buffer| buffer.mNumberChannels).sum()
+    };
+    Ok(channels)
 }
 
 /// Change the sample rate of a device.
@@ -318,6 +575,162 @@ pub fn set_device_sample_rate(device_id: AudioDeviceID, new_rate: f64) -> Result
     }
 }
 
+/// Get the nominal sample rate of a device.
+pub fn get_device_sample_rate(device_id: AudioDeviceID) -> Result<f64, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyNominalSampleRate,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let mut sample_rate: f64 = 0.0;
+    let mut data_size = mem::size_of::<f64>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &mut data_size as *mut _,
+            &mut sample_rate as *mut _ as *mut c_void,
+        )
+    };
+    Error::from_os_status(status)?;
+    Ok(sample_rate)
+}
+
+/// Change the nominal sample rate of a device and block until the device reports the new rate.
+///
+/// Devices apply a new sample rate asynchronously, so reading the rate right after setting it
+/// often returns the old value. This registers a **RateListener** before setting the rate, then
+/// waits until either a change notification or the current value confirms the new rate.
+///
+/// Returns `Error::SampleRateChangeTimedOut` if the device does not report the new rate within
+/// the given `timeout`. Changes commonly take up to half a second.
+pub fn set_device_sample_rate_blocking(
+    device_id: AudioDeviceID,
+    new_rate: f64,
+    timeout: Duration,
+) -> Result<(), Error> {
+    if get_device_sample_rate(device_id)? as u32 == new_rate as u32 {
+        return Ok(());
+    }
+
+    // Register the listener before setting the rate so that the notification can't be missed.
+    let (sender, receiver) = channel();
+    let mut listener = RateListener::new(device_id, Some(sender));
+    listener.register()?;
+
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyNominalSampleRate,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let status = unsafe {
+        AudioObjectSetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            mem::size_of::<f64>() as u32,
+            &new_rate as *const _ as *const _,
+        )
+    };
+    Error::from_os_status(status)?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let wait = remaining.min(Duration::from_millis(100));
+        if let Ok(reported_rate) = receiver.recv_timeout(wait) {
+            if reported_rate as u32 == new_rate as u32 {
+                return Ok(());
+            }
+        }
+        // Some devices only notify once for several changes, so check the value itself too.
+        if get_device_sample_rate(device_id)? as u32 == new_rate as u32 {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::SampleRateChangeTimedOut);
+        }
+    }
+}
+
+/// List the ids of all streams of a device, both input and output.
+pub fn get_device_streams(device_id: AudioDeviceID) -> Result<Vec<AudioStreamID>, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyStreams,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+
+    let data_size = 0u32;
+    let status = unsafe {
+        AudioObjectGetPropertyDataSize(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+        )
+    };
+    Error::from_os_status(status)?;
+
+    let stream_count = data_size as usize / mem::size_of::<AudioStreamID>();
+    let mut streams: Vec<AudioStreamID> = vec![0; stream_count];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+            streams.as_mut_ptr() as *mut _,
+        )
+    };
+    Error::from_os_status(status)?;
+    streams.truncate(data_size as usize / mem::size_of::<AudioStreamID>());
+    Ok(streams)
+}
+
+/// Get the virtual format of a stream, i.e. the format in which audio is exchanged with the
+/// device's clients.
+pub fn get_stream_virtual_format(stream_id: AudioStreamID) -> Result<StreamFormat, Error> {
+    get_stream_format(stream_id, kAudioStreamPropertyVirtualFormat)
+}
+
+/// Get the physical format of a stream, i.e. the format of the audio in the hardware.
+///
+/// Matching the virtual format to the physical format avoids any conversion by the HAL, which
+/// is necessary for bit-perfect playback.
+pub fn get_stream_physical_format(stream_id: AudioStreamID) -> Result<StreamFormat, Error> {
+    get_stream_format(stream_id, kAudioStreamPropertyPhysicalFormat)
+}
+
+/// Get the format stored by the given `AudioStreamBasicDescription` property of a stream.
+fn get_stream_format(stream_id: AudioStreamID, selector: u32) -> Result<StreamFormat, Error> {
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let maybe_asbd: mem::MaybeUninit<AudioStreamBasicDescription> = mem::MaybeUninit::zeroed();
+    let data_size = mem::size_of::<AudioStreamBasicDescription>() as u32;
+    let asbd = unsafe {
+        let status = AudioObjectGetPropertyData(
+            stream_id,
+            &property_address as *const _,
+            0,
+            null(),
+            &data_size as *const _ as *mut _,
+            &maybe_asbd as *const _ as *mut _,
+        );
+        Error::from_os_status(status)?;
+        maybe_asbd.assume_init()
+    };
+    StreamFormat::from_asbd(asbd)
+}
+
 /// Find the closest match of the physical formats to the provided `StreamFormat`.
 /// This function will pick the first format it finds that supports the provided sample format, rate and number of channels.
 /// The provided format flags in the `StreamFormat` are ignored.
@@ -792,3 +1205,123 @@ pub fn toggle_hog_mode(device_id: AudioDeviceID) -> Result<pid_t, Error> {
     };
     Ok(pid)
 }
+
+/// Take exclusive access (hog mode) of a device for the calling process.
+///
+/// Does nothing if the calling process already has exclusive access. Returns
+/// `Error::HogModeOwnedByOtherProcess` with the pid of the owner if another process has it.
+pub fn take_hog_mode(device_id: AudioDeviceID) -> Result<(), Error> {
+    let own_pid = process::id() as pid_t;
+    match get_hogging_pid(device_id)? {
+        pid if pid == own_pid => return Ok(()),
+        -1 => (),
+        pid => return Err(Error::HogModeOwnedByOtherProcess(pid)),
+    }
+    // Another process may have taken ownership in the meantime.
+    match toggle_hog_mode(device_id)? {
+        pid if pid == own_pid => Ok(()),
+        pid => Err(Error::HogModeOwnedByOtherProcess(pid)),
+    }
+}
+
+/// Release exclusive access (hog mode) of a device held by the calling process.
+///
+/// Does nothing if no process has exclusive access. Returns `Error::HogModeOwnedByOtherProcess`
+/// with the pid of the owner if another process has it.
+pub fn release_hog_mode(device_id: AudioDeviceID) -> Result<(), Error> {
+    let own_pid = process::id() as pid_t;
+    match get_hogging_pid(device_id)? {
+        -1 => Ok(()),
+        pid if pid == own_pid => {
+            toggle_hog_mode(device_id)?;
+            Ok(())
+        }
+        pid => Err(Error::HogModeOwnedByOtherProcess(pid)),
+    }
+}
+
+/// Get the current time of a device, with its sample time, host time and rate scalar filled in.
+///
+/// The device must be running, e.g. because an **AudioUnit** using it has been started,
+/// otherwise Core Audio returns `kAudioHardwareNotRunningError`.
+pub fn get_device_current_time(device_id: AudioDeviceID) -> Result<AudioTimeStamp, Error> {
+    let mut time: AudioTimeStamp = unsafe { mem::zeroed() };
+    let status = unsafe { AudioDeviceGetCurrentTime(device_id, &mut time as *mut _) };
+    Error::from_os_status(status)?;
+    Ok(time)
+}
+
+/// Translate a time of a device to another of its time representations, e.g. a host time to
+/// the corresponding sample time of the device's clock.
+///
+/// The valid fields of `from` are given by its `mFlags`. The fields to translate to are given
+/// by `to_flags`, e.g. `kAudioTimeStampSampleTimeValid`, and are filled in the returned time.
+///
+/// The device must be running, e.g. because an **AudioUnit** using it has been started,
+/// otherwise Core Audio returns `kAudioHardwareNotRunningError`.
+pub fn translate_device_time(
+    device_id: AudioDeviceID,
+    from: &AudioTimeStamp,
+    to_flags: u32,
+) -> Result<AudioTimeStamp, Error> {
+    let mut time: AudioTimeStamp = unsafe { mem::zeroed() };
+    time.mFlags = to_flags;
+    let status =
+        unsafe { AudioDeviceTranslateTime(device_id, from as *const _, &mut time as *mut _) };
+    Error::from_os_status(status)?;
+    Ok(time)
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_input_capture_configures_the_default_input_device() {
+        // Machines without an input device, e.g. some CI runners, can't run this test.
+        let device_id = match get_default_device_id(true) {
+            Some(device_id) => device_id,
+            None => return,
+        };
+        let format = StreamFormat {
+            sample_rate: get_device_sample_rate(device_id).unwrap(),
+            sample_format: SampleFormat::F32,
+            flags: LinearPcmFlags::IS_FLOAT
+                | LinearPcmFlags::IS_PACKED
+                | LinearPcmFlags::IS_NON_INTERLEAVED,
+            channels: 1,
+        };
+        let audio_unit = AudioUnit::new_input_capture(None, format).unwrap();
+
+        assert!(audio_unit.has_io(Scope::Input, Element::Input).unwrap());
+        assert!(!audio_unit.has_io(Scope::Output, Element::Output).unwrap());
+        let current_device: AudioDeviceID = audio_unit
+            .get_property(
+                kAudioOutputUnitProperty_CurrentDevice,
+                Scope::Global,
+                Element::Output,
+            )
+            .unwrap();
+        assert_eq!(current_device, device_id);
+
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd = audio_unit
+            .get_property(id, Scope::Output, Element::Input)
+            .unwrap();
+        let client_format = StreamFormat::from_asbd(asbd).unwrap();
+        assert_eq!(client_format.sample_format, SampleFormat::F32);
+        assert_eq!(client_format.channels, 1);
+        assert!(client_format
+            .flags
+            .contains(LinearPcmFlags::IS_NON_INTERLEAVED));
+
+        let id = sys::kAudioUnitProperty_ShouldAllocateBuffer;
+        let should_allocate: u32 = audio_unit
+            .get_property(id, Scope::Output, Element::Input)
+            .unwrap();
+        assert_eq!(should_allocate, 0);
+
+        let max_buffer_frames = get_buffer_frame_size_range(device_id).unwrap().max as u32;
+        assert!(audio_unit.maximum_frames_per_slice().unwrap() >= max_buffer_frames);
+    }
+}
@@ -0,0 +1,74 @@
+//! A wrapper around Apple's **AUMIDISynth** unit, a General MIDI synthesizer with support for
+//! preloading patches.
+//!
+//! Unlike the **DLSSynth**, the **AUMIDISynth** loads instruments lazily upon their first program
+//! change, which can delay the first notes played with them. Use
+//! [**preload_patches**](./struct.MIDISynth.html#method.preload_patches) to load the patches used
+//! by e.g. a MIDI file ahead of playback.
+
+use super::{AudioUnit, Element, MusicDeviceType, Scope};
+use crate::error::{AudioError, Error};
+use core_foundation_sys::base::CFRelease;
+use std::os::raw::c_void;
+use std::path::Path;
+use sys;
+
+/// An **AUMIDISynth** unit, synthesizing MIDI using a DLS or SoundFont sound bank.
+///
+/// **Available** in OS X v10.8 and later.
+pub struct MIDISynth {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(MIDISynth);
+
+impl MIDISynth {
+    /// Create and initialize a new **MIDISynth** unit using the system's sound bank.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MusicDeviceType::MIDISynth)?;
+        Ok(MIDISynth { audio_unit })
+    }
+
+    /// Load the DLS or SoundFont (`.sf2`) sound bank at the given path via
+    /// `kMusicDeviceProperty_SoundBankURL`.
+    ///
+    /// Returns `AudioError::FileNotFound` if there is no file at the given path.
+    pub fn set_sound_bank(&mut self, path: &Path) -> Result<(), Error> {
+        if !path.is_file() {
+            return Err(Error::Audio(AudioError::FileNotFound));
+        }
+        let url = super::cf_url_from_path(path)?;
+        let id = sys::kMusicDeviceProperty_SoundBankURL;
+        let result = self
+            .audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&url));
+        unsafe { CFRelease(url as *const c_void) };
+        result
+    }
+
+    /// Enable or disable preloading via `kAUMIDISynthProperty_EnablePreload`.
+    ///
+    /// While enabled, program changes sent to the synth load the requested patch rather than
+    /// selecting it, and notes are ignored.
+    pub fn set_preload_enabled(&mut self, enable: bool) -> Result<(), Error> {
+        let id = sys::kAUMIDISynthProperty_EnablePreload;
+        let enable = enable as u32;
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&enable))
+    }
+
+    /// Load the given General MIDI programs (in the range `0..128`) ahead of playback, avoiding
+    /// the latency of loading them upon their first program change.
+    ///
+    /// This enables preloading, sends a program change on channel `0` for each of the `patches`
+    /// and disables preloading again. Preloading is disabled even if sending a program change
+    /// fails.
+    pub fn preload_patches(&mut self, patches: &[u8]) -> Result<(), Error> {
+        self.set_preload_enabled(true)?;
+        let result = patches
+            .iter()
+            .try_for_each(|&patch| self.audio_unit.send_midi(0xC0, patch & 0x7F, 0, 0));
+        let disabled = self.set_preload_enabled(false);
+        result.and(disabled)
+    }
+}
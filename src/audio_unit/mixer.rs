@@ -0,0 +1,526 @@
+//! Methods and types specific to Apple's mixer **AudioUnit**s.
+//!
+//! See the [**MixerType**](../types/enum.MixerType.html) enum for the available mixer units.
+
+use super::render_callback::{Args, Data};
+use super::{AudioUnit, Element, MixerType, Scope, StreamFormat};
+use crate::error::Error;
+use sys;
+
+/// The level of a mixer bus as read by
+/// [**MultiChannelMixer::input_level**](./struct.MultiChannelMixer.html#method.input_level) or
+/// [**MultiChannelMixer::output_level**](./struct.MultiChannelMixer.html#method.output_level).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LevelMeter {
+    /// The post-fader average power of the first channel of the bus in decibels.
+    pub average_db: f32,
+    /// The post-fader peak hold level of the first channel of the bus in decibels.
+    pub peak_db: f32,
+}
+
+/// An **AudioUnit** of the **MultiChannelMixer** subtype, mixing any number of input buses into
+/// a single output bus.
+///
+/// ```no_run
+/// # use coreaudio::audio_unit::mixer::MultiChannelMixer;
+/// # use coreaudio::audio_unit::render_callback::{self, data};
+/// # fn main() -> Result<(), coreaudio::Error> {
+/// type Args = render_callback::Args<data::NonInterleaved<f32>>;
+/// let mut mixer = MultiChannelMixer::new()?;
+/// mixer.set_input_bus_count(2)?;
+/// for bus in 0..2 {
+///     mixer.set_input_render_callback(bus, |_args: Args| Ok(()))?;
+/// }
+/// mixer.set_input_pan(0, -1.0)?;
+/// mixer.set_input_pan(1, 1.0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiChannelMixer {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(MultiChannelMixer);
+
+impl MultiChannelMixer {
+    /// Create and initialize a new **MultiChannelMixer**.
+    ///
+    /// **Available** in OS X v10.5 and later.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MixerType::MultiChannelMixer)?;
+        Ok(MultiChannelMixer { audio_unit })
+    }
+
+    /// The number of input buses of the mixer.
+    pub fn input_bus_count(&self) -> Result<u32, Error> {
+        let id = sys::kAudioUnitProperty_ElementCount;
+        self.audio_unit
+            .get_property(id, Scope::Input, Element::Output)
+    }
+
+    /// Set the number of input buses of the mixer.
+    ///
+    /// The mixer is uninitialized while the bus count changes.
+    pub fn set_input_bus_count(&mut self, count: u32) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_ElementCount;
+        self.reconfigure(|au| au.set_property(id, Scope::Input, Element::Output, Some(&count)))
+    }
+
+    /// Set the stream format of the audio provided to the given input bus.
+    ///
+    /// The mixer is uninitialized while the format changes.
+    pub fn set_input_stream_format(
+        &mut self,
+        bus: u32,
+        stream_format: StreamFormat,
+    ) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd = stream_format.to_asbd();
+        self.reconfigure(|au| au.set_property(id, Scope::Input, bus, Some(&asbd)))
+    }
+
+    /// Pass a render callback providing the audio for the given input bus.
+    ///
+    /// See [**AudioUnit::set_input_bus_render_callback**](../struct.AudioUnit.html#method.set_input_bus_render_callback).
+    pub fn set_input_render_callback<F, D>(&mut self, bus: u32, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.audio_unit.set_input_bus_render_callback(bus, f)
+    }
+
+    /// Set the volume of the given input bus as a linear gain from `0.0` to `1.0`.
+    pub fn set_input_volume(&mut self, bus: u32, volume: f32) -> Result<(), Error> {
+        let id = sys::kMultiChannelMixerParam_Volume;
+        self.audio_unit
+            .set_parameter(id, Scope::Input, bus, volume, 0)
+    }
+
+    /// Set the volume of the mixer's output as a linear gain from `0.0` to `1.0`.
+    pub fn set_output_volume(&mut self, volume: f32) -> Result<(), Error> {
+        let id = sys::kMultiChannelMixerParam_Volume;
+        self.audio_unit
+            .set_parameter(id, Scope::Output, Element::Output, volume, 0)
+    }
+
+    /// Enable or disable the given input bus. Disabled buses are not rendered.
+    pub fn set_input_enabled(&mut self, bus: u32, enabled: bool) -> Result<(), Error> {
+        let id = sys::kMultiChannelMixerParam_Enable;
+        let value = if enabled { 1.0 } else { 0.0 };
+        self.audio_unit
+            .set_parameter(id, Scope::Input, bus, value, 0)
+    }
+
+    /// Set the pan of the given input bus, from `-1.0` (left) to `1.0` (right).
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn set_input_pan(&mut self, bus: u32, pan: f32) -> Result<(), Error> {
+        let id = sys::kMultiChannelMixerParam_Pan;
+        self.audio_unit.set_parameter(id, Scope::Input, bus, pan, 0)
+    }
+
+    /// Enable or disable metering on the output bus and all input buses.
+    ///
+    /// Metering must be enabled before levels can be read. As it applies to the buses that
+    /// exist when it is enabled, enable it after
+    /// [**set_input_bus_count**](./struct.MultiChannelMixer.html#method.set_input_bus_count).
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn enable_metering(&mut self, enable: bool) -> Result<(), Error> {
+        self.audio_unit.enable_metering(enable)
+    }
+
+    /// Read the current level of the given input bus.
+    ///
+    /// Metering must first be enabled with
+    /// [**enable_metering**](./struct.MultiChannelMixer.html#method.enable_metering).
+    pub fn input_level(&self, bus: u32) -> Result<LevelMeter, Error> {
+        self.level(Scope::Input, bus)
+    }
+
+    /// Read the current level of the output bus.
+    ///
+    /// Metering must first be enabled with
+    /// [**enable_metering**](./struct.MultiChannelMixer.html#method.enable_metering).
+    pub fn output_level(&self) -> Result<LevelMeter, Error> {
+        self.level(Scope::Output, Element::Output as u32)
+    }
+
+    fn level(&self, scope: Scope, bus: u32) -> Result<LevelMeter, Error> {
+        let average_id = sys::kMultiChannelMixerParam_PostAveragePower;
+        let peak_id = sys::kMultiChannelMixerParam_PostPeakHoldLevel;
+        Ok(LevelMeter {
+            average_db: self.audio_unit.parameter(average_id, scope, bus)?,
+            peak_db: self.audio_unit.parameter(peak_id, scope, bus)?,
+        })
+    }
+
+    /// Apply a change that requires the mixer to be uninitialized, then initialize it again.
+    fn reconfigure<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut AudioUnit) -> Result<(), Error>,
+    {
+        self.audio_unit.uninitialize()?;
+        let result = f(&mut self.audio_unit);
+        self.audio_unit.initialize()?;
+        result
+    }
+}
+
+/// An **AudioUnit** of the **MatrixMixer** subtype, routing any input channel to any output
+/// channel with a separate gain for each crosspoint.
+///
+/// The matrix mixer must be configured in a particular order: its buses and their formats are
+/// set via [**configure**](./struct.MatrixMixer.html#method.configure), after which the input and
+/// output channels must be enabled and the crosspoint volumes set, as all volumes default to
+/// silence.
+///
+/// Channels are numbered across all buses, e.g. with two stereo input buses the input channels
+/// of the second bus are `2` and `3`.
+///
+/// ```no_run
+/// # use coreaudio::audio_unit::mixer::MatrixMixer;
+/// # use coreaudio::audio_unit::{SampleFormat, StreamFormat};
+/// # use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+/// # fn main() -> Result<(), coreaudio::Error> {
+/// let stereo = StreamFormat {
+///     sample_rate: 44100.0,
+///     sample_format: SampleFormat::F32,
+///     flags: LinearPcmFlags::IS_FLOAT
+///         | LinearPcmFlags::IS_PACKED
+///         | LinearPcmFlags::IS_NON_INTERLEAVED,
+///     channels: 2,
+/// };
+/// let mut mixer = MatrixMixer::new()?;
+/// mixer.configure(&[stereo], &[stereo])?;
+/// mixer.set_master_volume(1.0)?;
+/// for channel in 0..2 {
+///     mixer.set_input_enabled(channel, true)?;
+///     mixer.set_input_volume(channel, 1.0)?;
+///     mixer.set_output_enabled(channel, true)?;
+///     mixer.set_output_volume(channel, 1.0)?;
+/// }
+/// // Swap the left and right channels.
+/// mixer.set_crosspoint_volume(0, 1, 1.0)?;
+/// mixer.set_crosspoint_volume(1, 0, 1.0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MatrixMixer {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(MatrixMixer);
+
+impl MatrixMixer {
+    /// The element addressing the master volume of the matrix.
+    const MASTER_ELEMENT: u32 = 0xFFFF_FFFF;
+
+    /// Create and initialize a new **MatrixMixer**.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MixerType::MatrixMixer)?;
+        Ok(MatrixMixer { audio_unit })
+    }
+
+    /// Set the number of input and output buses along with their stream formats, one format per
+    /// bus.
+    ///
+    /// The mixer is uninitialized while it is configured and initialized again afterwards. The
+    /// channels must be enabled after this call.
+    pub fn configure(
+        &mut self,
+        input_formats: &[StreamFormat],
+        output_formats: &[StreamFormat],
+    ) -> Result<(), Error> {
+        self.audio_unit.uninitialize()?;
+        let buses = [
+            (Scope::Input, input_formats),
+            (Scope::Output, output_formats),
+        ];
+        for &(scope, formats) in &buses {
+            let id = sys::kAudioUnitProperty_ElementCount;
+            let count = formats.len() as u32;
+            self.audio_unit
+                .set_property(id, scope, Element::Output, Some(&count))?;
+            for (bus, stream_format) in formats.iter().enumerate() {
+                let id = sys::kAudioUnitProperty_StreamFormat;
+                let asbd = stream_format.to_asbd();
+                self.audio_unit
+                    .set_property(id, scope, bus as u32, Some(&asbd))?;
+            }
+        }
+        self.audio_unit.initialize()
+    }
+
+    /// Pass a render callback providing the audio for the given input bus.
+    ///
+    /// See [**AudioUnit::set_input_bus_render_callback**](../struct.AudioUnit.html#method.set_input_bus_render_callback).
+    pub fn set_input_render_callback<F, D>(&mut self, bus: u32, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.audio_unit.set_input_bus_render_callback(bus, f)
+    }
+
+    /// Set the gain with which the given input channel is routed to the given output channel.
+    pub fn set_crosspoint_volume(
+        &mut self,
+        input_channel: u32,
+        output_channel: u32,
+        gain: f32,
+    ) -> Result<(), Error> {
+        let element = crosspoint_element(input_channel, output_channel);
+        self.set_volume(Scope::Global, element, gain)
+    }
+
+    /// The gain with which the given input channel is routed to the given output channel.
+    pub fn crosspoint_volume(&self, input_channel: u32, output_channel: u32) -> Result<f32, Error> {
+        let id = sys::kMatrixMixerParam_Volume;
+        let element = crosspoint_element(input_channel, output_channel);
+        self.audio_unit.parameter(id, Scope::Global, element)
+    }
+
+    /// The number of input channels across all input buses.
+    pub fn input_channel_count(&self) -> Result<u32, Error> {
+        self.channel_count(Scope::Input)
+    }
+
+    /// The number of output channels across all output buses.
+    pub fn output_channel_count(&self) -> Result<u32, Error> {
+        self.channel_count(Scope::Output)
+    }
+
+    /// Set the gain of the given input channel.
+    pub fn set_input_volume(&mut self, input_channel: u32, gain: f32) -> Result<(), Error> {
+        self.set_volume(Scope::Input, input_channel, gain)
+    }
+
+    /// Set the gain of the given output channel.
+    pub fn set_output_volume(&mut self, output_channel: u32, gain: f32) -> Result<(), Error> {
+        self.set_volume(Scope::Output, output_channel, gain)
+    }
+
+    /// Set the master gain applied to the whole matrix.
+    pub fn set_master_volume(&mut self, gain: f32) -> Result<(), Error> {
+        self.set_volume(Scope::Global, Self::MASTER_ELEMENT, gain)
+    }
+
+    /// Enable or disable the given input channel.
+    pub fn set_input_enabled(&mut self, input_channel: u32, enabled: bool) -> Result<(), Error> {
+        self.set_enabled(Scope::Input, input_channel, enabled)
+    }
+
+    /// Enable or disable the given output channel.
+    pub fn set_output_enabled(&mut self, output_channel: u32, enabled: bool) -> Result<(), Error> {
+        self.set_enabled(Scope::Output, output_channel, enabled)
+    }
+
+    fn channel_count(&self, scope: Scope) -> Result<u32, Error> {
+        let mut channels = 0;
+        for bus in 0..self.audio_unit.element_count(scope)? {
+            let id = sys::kAudioUnitProperty_StreamFormat;
+            let asbd: sys::AudioStreamBasicDescription =
+                self.audio_unit.get_property(id, scope, bus)?;
+            channels += asbd.mChannelsPerFrame;
+        }
+        Ok(channels)
+    }
+
+    fn set_volume(&mut self, scope: Scope, element: u32, gain: f32) -> Result<(), Error> {
+        let id = sys::kMatrixMixerParam_Volume;
+        self.audio_unit.set_parameter(id, scope, element, gain, 0)
+    }
+
+    fn set_enabled(&mut self, scope: Scope, element: u32, enabled: bool) -> Result<(), Error> {
+        let id = sys::kMatrixMixerParam_Enable;
+        let value = if enabled { 1.0 } else { 0.0 };
+        self.audio_unit.set_parameter(id, scope, element, value, 0)
+    }
+}
+
+/// The element addressing the crosspoint between the given input and output channels of a
+/// **MatrixMixer**, e.g. for use with `kMatrixMixerParam_Volume` in the global scope.
+///
+/// Crosspoints are addressed by the input channel in the upper and the output channel in the
+/// lower 16 bits of the element.
+pub fn crosspoint_element(input_channel: u32, output_channel: u32) -> u32 {
+    (input_channel << 16) | (output_channel & 0xFFFF)
+}
+
+/// The algorithm with which a **SpatialMixer** renders an input bus.
+///
+/// Original documentation [here](https://developer.apple.com/documentation/audiotoolbox/auspatializationalgorithm).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpatializationAlgorithm {
+    /// Simple panning, with the lowest CPU usage.
+    EqualPowerPanning,
+    /// A model of the listener's head, better suited to headphones than equal power panning.
+    SphericalHead,
+    /// Head-related transfer function based rendering, for realistic headphone playback.
+    Hrtf,
+    /// Ambisonics based rendering, e.g. for multichannel speaker setups.
+    SoundField,
+    /// Panning between the nearest pair of speakers of a multichannel output.
+    VectorBasedPanning,
+    /// Pass the input through without spatialization. Only for stereo inputs.
+    StereoPassThrough,
+    /// A higher quality, but more expensive, variant of `Hrtf`.
+    ///
+    /// **Available** in OS X v10.10 and later.
+    HrtfHq,
+    /// Choose the algorithm based on the output type of the mixer.
+    ///
+    /// **Available** in OS X v10.15 and later.
+    UseOutputType,
+}
+
+impl SpatializationAlgorithm {
+    /// Convert the algorithm to its associated `u32` for compatibility with the original API.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            SpatializationAlgorithm::EqualPowerPanning => {
+                sys::kSpatializationAlgorithm_EqualPowerPanning
+            }
+            SpatializationAlgorithm::SphericalHead => sys::kSpatializationAlgorithm_SphericalHead,
+            SpatializationAlgorithm::Hrtf => sys::kSpatializationAlgorithm_HRTF,
+            SpatializationAlgorithm::SoundField => sys::kSpatializationAlgorithm_SoundField,
+            SpatializationAlgorithm::VectorBasedPanning => {
+                sys::kSpatializationAlgorithm_VectorBasedPanning
+            }
+            SpatializationAlgorithm::StereoPassThrough => {
+                sys::kSpatializationAlgorithm_StereoPassThrough
+            }
+            SpatializationAlgorithm::HrtfHq => sys::kSpatializationAlgorithm_HRTFHQ,
+            SpatializationAlgorithm::UseOutputType => sys::kSpatializationAlgorithm_UseOutputType,
+        }
+    }
+}
+
+/// An **AudioUnit** of the **SpatialMixer** subtype, positioning each of its mono input buses in
+/// 3D space around the listener.
+///
+/// The position of each input bus is given by its azimuth, elevation and distance relative to
+/// the listener.
+///
+/// **Available** in OS X v10.10 and later.
+pub struct SpatialMixer {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(SpatialMixer);
+
+impl SpatialMixer {
+    /// Create and initialize a new **SpatialMixer**.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MixerType::SpatialMixer)?;
+        Ok(SpatialMixer { audio_unit })
+    }
+
+    /// Pass a render callback providing the audio for the given input bus.
+    ///
+    /// See [**AudioUnit::set_input_bus_render_callback**](../struct.AudioUnit.html#method.set_input_bus_render_callback).
+    pub fn set_input_render_callback<F, D>(&mut self, bus: u32, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.audio_unit.set_input_bus_render_callback(bus, f)
+    }
+
+    /// Set the horizontal angle of the given input bus in degrees, from `-180.0` to `180.0`,
+    /// where `0.0` is in front of the listener and positive angles are to the right.
+    pub fn set_azimuth(&mut self, bus: u32, degrees: f32) -> Result<(), Error> {
+        self.set_input_parameter(sys::k3DMixerParam_Azimuth, bus, degrees)
+    }
+
+    /// Set the vertical angle of the given input bus in degrees, from `-90.0` (below) to `90.0`
+    /// (above).
+    pub fn set_elevation(&mut self, bus: u32, degrees: f32) -> Result<(), Error> {
+        self.set_input_parameter(sys::k3DMixerParam_Elevation, bus, degrees)
+    }
+
+    /// Set the distance of the given input bus from the listener in meters.
+    pub fn set_distance(&mut self, bus: u32, meters: f32) -> Result<(), Error> {
+        self.set_input_parameter(sys::k3DMixerParam_Distance, bus, meters)
+    }
+
+    /// Set the gain of the given input bus in decibels, from `-120.0` to `20.0`.
+    pub fn set_gain(&mut self, bus: u32, db: f32) -> Result<(), Error> {
+        self.set_input_parameter(sys::k3DMixerParam_Gain, bus, db)
+    }
+
+    /// Set how much of the given input bus is sent to the internal reverb, from `0.0` (dry) to
+    /// `100.0` (wet).
+    ///
+    /// The internal reverb must be enabled via
+    /// [**set_uses_internal_reverb**](./struct.SpatialMixer.html#method.set_uses_internal_reverb).
+    pub fn set_reverb_blend(&mut self, bus: u32, percent: f32) -> Result<(), Error> {
+        self.set_input_parameter(sys::k3DMixerParam_ReverbBlend, bus, percent)
+    }
+
+    /// Enable or disable the mixer's internal reverb.
+    pub fn set_uses_internal_reverb(&mut self, enable: bool) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_UsesInternalReverb;
+        let enable = enable as u32;
+        self.audio_unit
+            .set_property(id, Scope::Global, Element::Output, Some(&enable))
+    }
+
+    /// Set the algorithm with which the given input bus is rendered.
+    pub fn set_spatialization_algorithm(
+        &mut self,
+        bus: u32,
+        algorithm: SpatializationAlgorithm,
+    ) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_SpatializationAlgorithm;
+        let algorithm = algorithm.as_u32();
+        self.audio_unit
+            .set_property(id, Scope::Input, bus, Some(&algorithm))
+    }
+
+    fn set_input_parameter(&mut self, id: u32, bus: u32, value: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_parameter(id, Scope::Input, bus, value, 0)
+    }
+}
+
+impl AudioUnit {
+    /// Enable or disable the mixer's internal metering on its output bus and all of its input
+    /// buses.
+    ///
+    /// Metering must be enabled before levels can be read with
+    /// [**mixer_channel_level_db**](./struct.AudioUnit.html#method.mixer_channel_level_db).
+    ///
+    /// This is only supported by mixer units, such as the **MultiChannelMixer**.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn enable_metering(&mut self, enable: bool) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_MeteringMode;
+        let metering_mode = enable as u32;
+        self.set_property(id, Scope::Output, Element::Output, Some(&metering_mode))?;
+        let id = sys::kAudioUnitProperty_ElementCount;
+        let input_bus_count: u32 = self.get_property(id, Scope::Input, Element::Output)?;
+        for bus in 0..input_bus_count {
+            let id = sys::kAudioUnitProperty_MeteringMode;
+            self.set_property(id, Scope::Input, bus, Some(&metering_mode))?;
+        }
+        Ok(())
+    }
+
+    /// Read the post-fader average power of the given input bus of a mixer unit in decibels.
+    ///
+    /// The level is that of the first channel of the bus. Metering must first be enabled with
+    /// [**enable_metering**](./struct.AudioUnit.html#method.enable_metering).
+    ///
+    /// This is only supported by mixer units, such as the **MultiChannelMixer**.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn mixer_channel_level_db(&self, bus: u32) -> Result<f32, Error> {
+        let id = sys::kMultiChannelMixerParam_PostAveragePower;
+        self.parameter(id, Scope::Input, bus)
+    }
+}
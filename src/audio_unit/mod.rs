@@ -6,9 +6,7 @@
 //! TODO: The following are `kAudioUnitSubType`s (along with their const u32) generated by
 //! rust-bindgen that we could not find any documentation on:
 //!
-//! - MIDISynth            = 1836284270,
 //! - RoundTripAAC         = 1918984547,
-//! - SpatialMixer         = 862217581,
 //! - SphericalHeadPanner  = 1936746610,
 //! - VectorPanner         = 1986158963,
 //! - SoundFieldPanner     = 1634558569,
@@ -19,27 +17,118 @@
 //! fixes!
 
 use crate::error::Error;
+use core_foundation_sys::base::{kCFAllocatorDefault, CFRelease};
+use core_foundation_sys::string::{
+    kCFStringEncodingUTF8, CFStringCreateWithBytes, CFStringGetCString, CFStringGetCStringPtr,
+    CFStringGetLength, CFStringRef,
+};
+use std::ffi::CStr;
+use std::fmt;
 use std::mem;
-use std::os::raw::{c_uint, c_void};
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_uint, c_void};
 use std::ptr;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use sys;
 
 pub use self::audio_format::AudioFormat;
 pub use self::sample_format::{Sample, SampleFormat};
-pub use self::stream_format::StreamFormat;
+pub use self::stream_format::{CompressedStreamFormat, StreamFormat};
 pub use self::types::{
     EffectType, FormatConverterType, GeneratorType, IOType, MixerType, MusicDeviceType, Type,
 };
 
+/// Implement the accessors shared by the typed wrappers around a single **AudioUnit**, which is
+/// held in their `audio_unit` field.
+///
+/// Wrappers of units that process audio may also pass `render_callback` to forward
+/// `set_render_callback`, providing the unit's input.
+macro_rules! impl_audio_unit_wrapper {
+    ($wrapper:ident) => {
+        impl $wrapper {
+            /// The underlying **AudioUnit**, e.g. for connecting it to other units.
+            pub fn audio_unit(&self) -> &$crate::audio_unit::AudioUnit {
+                &self.audio_unit
+            }
+
+            /// The underlying **AudioUnit**, e.g. for connecting it to other units.
+            pub fn audio_unit_mut(&mut self) -> &mut $crate::audio_unit::AudioUnit {
+                &mut self.audio_unit
+            }
+
+            /// Consume the wrapper, returning the underlying **AudioUnit**.
+            pub fn into_audio_unit(self) -> $crate::audio_unit::AudioUnit {
+                self.audio_unit
+            }
+        }
+    };
+    ($wrapper:ident, render_callback) => {
+        impl_audio_unit_wrapper!($wrapper);
+
+        impl $wrapper {
+            /// Pass a render callback providing the audio to process.
+            ///
+            /// See [**AudioUnit::set_render_callback**](../struct.AudioUnit.html#method.set_render_callback).
+            pub fn set_render_callback<F, D>(&mut self, f: F) -> Result<(), $crate::error::Error>
+            where
+                F: FnMut($crate::audio_unit::render_callback::Args<D>) -> Result<(), ()> + 'static,
+                D: $crate::audio_unit::render_callback::Data,
+            {
+                self.audio_unit.set_render_callback(f)
+            }
+        }
+    };
+}
+
 #[cfg(target_os = "macos")]
 pub mod macos_helpers;
 
+#[cfg(feature = "audio_toolbox")]
+pub mod audio_file_player;
 pub mod audio_format;
+pub mod builder;
+pub mod channel_layout;
+pub mod command_queue;
+pub mod components;
+pub mod converter;
+#[cfg(feature = "audio_toolbox")]
+pub mod dls_synth;
+pub mod effect;
+#[cfg(feature = "audio_toolbox")]
+pub mod event_listener;
+#[cfg(feature = "audio_toolbox")]
+pub mod graph;
+pub mod instantiate;
+#[cfg(feature = "audio_toolbox")]
+pub mod midi_synth;
+pub mod mixer;
+#[cfg(feature = "audio_toolbox")]
+pub mod music_device;
+pub mod parameter;
+pub mod play_through;
+pub mod properties;
 pub mod render_callback;
+#[cfg(feature = "audio_toolbox")]
+pub mod resampling;
+pub mod routing;
 pub mod sample_format;
+#[cfg(feature = "audio_toolbox")]
+pub mod sampler;
+pub mod scheduled_sound_player;
+pub mod state;
 pub mod stream_format;
+pub mod time_pitch;
+pub mod timing;
 pub mod types;
+pub mod voice_processing;
+
+#[cfg(all(test, target_os = "macos"))]
+mod test_util;
 
 /// The input and output **Scope**s.
 ///
@@ -59,26 +148,125 @@ pub enum Scope {
 
 /// Represents the **Input** and **Output** **Element**s.
 ///
-/// These are used when specifying which **Element** we're setting the properties of.
+/// These are used when specifying which **Element** we're setting the properties of. Functions
+/// that accept an `impl Into<u32>` element may also be given the index of a bus directly.
 #[derive(Copy, Clone, Debug)]
 pub enum Element {
     Output = 0,
     Input = 1,
 }
 
+impl From<Element> for u32 {
+    fn from(elem: Element) -> Self {
+        elem as u32
+    }
+}
+
+/// Convert a four character code such as `b"aufx"` into the `u32` used to identify component
+/// types, sub types and manufacturers.
+pub fn four_char_code(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+/// Set the maximum frames per slice of all of the given units, e.g. those of a manually
+/// connected chain, so that none of them fails to render the number of frames requested by the
+/// output.
+///
+/// Each unit whose value differs is uninitialized while the value is set and initialized again
+/// afterwards, so the units should not be rendering when this is called. Units that already have
+/// the given value are left untouched.
+pub fn propagate_max_frames_per_slice(units: &mut [AudioUnit], frames: u32) -> Result<(), Error> {
+    for unit in units {
+        if unit.maximum_frames_per_slice()? == frames {
+            continue;
+        }
+        unit.uninitialize()?;
+        unit.set_maximum_frames_per_slice(frames)?;
+        unit.initialize()?;
+    }
+    Ok(())
+}
+
+/// A four character code such as `appl`, identifying a component type, sub type or manufacturer.
+///
+/// ```
+/// # use coreaudio::audio_unit::FourCC;
+/// let apple: FourCC = "appl".parse().unwrap();
+/// assert_eq!(apple.as_u32(), coreaudio::sys::kAudioUnitManufacturer_Apple);
+/// assert_eq!(apple.to_string(), "appl");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FourCC(pub u32);
+
+impl FourCC {
+    /// The `u32` representation of the code as used by the original API.
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<FourCC> for u32 {
+    fn from(code: FourCC) -> Self {
+        code.0
+    }
+}
+
+impl FromStr for FourCC {
+    type Err = Error;
+    /// Parse a code from exactly four ASCII characters, returning `Error::InvalidFourCharCode`
+    /// otherwise.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match *s.as_bytes() {
+            [a, b, c, d] if s.is_ascii() => Ok(FourCC(four_char_code(&[a, b, c, d]))),
+            _ => Err(Error::InvalidFourCharCode),
+        }
+    }
+}
+
+impl fmt::Display for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = self.0.to_be_bytes();
+        if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            bytes.iter().try_for_each(|&b| write!(f, "{}", b as char))
+        } else {
+            // Not all codes are printable, e.g. those created from plain integers.
+            write!(f, "{:#010x}", self.0)
+        }
+    }
+}
+
 /// A rust representation of the sys::AudioUnit, including a pointer to the current rendering callback.
 ///
 /// Find the original Audio Unit Programming Guide [here](https://developer.apple.com/library/mac/documentation/MusicAudio/Conceptual/AudioUnitProgrammingGuide/TheAudioUnit/TheAudioUnit.html).
 pub struct AudioUnit {
     instance: sys::AudioUnit,
     maybe_render_callback: Option<*mut render_callback::InputProcFnWrapper>,
+    maybe_render_format_listener: Option<*mut render_callback::FormatListener>,
+    // The render callbacks of input buses other than bus `0`, along with their bus.
+    bus_render_callbacks: Vec<(
+        u32,
+        *mut render_callback::InputProcFnWrapper,
+        *mut render_callback::FormatListener,
+    )>,
     maybe_input_callback: Option<InputCallback>,
+    render_notify_callbacks: Vec<*mut render_callback::InputProcFnWrapper>,
+    #[cfg(feature = "audio_toolbox")]
+    maybe_midi_output_callback: Option<*mut music_device::MidiOutputFnWrapper>,
+    // Shared with the render callback, which outputs silence while this is `true`.
+    render_paused: Arc<AtomicBool>,
+    // Set by the render callbacks when they are called without valid buffers.
+    render_buffers_invalid: Arc<AtomicBool>,
+    // The slices scheduled on a ScheduledSoundPlayer unit that it may not be done with yet.
+    scheduled_slices: Vec<*mut scheduled_sound_player::ScheduledSlice>,
+    // The MaximumFramesPerSlice as last read or set, so that scheduled parameter events can be
+    // validated without reading the property on the render thread.
+    max_frames_per_slice: Option<u32>,
+    dispose_on_drop: bool,
 }
 
 struct InputCallback {
-    // The audio buffer list to which input data is rendered.
-    buffer_list: *mut sys::AudioBufferList,
     callback: *mut render_callback::InputProcFnWrapper,
+    format_listener: *mut render_callback::FormatListener,
 }
 
 macro_rules! try_os_status {
@@ -107,6 +295,10 @@ impl AudioUnit {
     /// Note: the `AudioUnit` is constructed with the `kAudioUnitManufacturer_Apple` Manufacturer
     /// Identifier, as this is the only Audio Unit Manufacturer Identifier documented by Apple in
     /// the AudioUnit reference (see [here](https://developer.apple.com/library/prerelease/mac/documentation/AudioUnit/Reference/AUComponentServicesReference/index.html#//apple_ref/doc/constant_group/Audio_Unit_Manufacturer_Identifier)).
+    /// The exceptions are `Custom` subtypes, e.g. `EffectType::Custom`, which are matched against
+    /// the components of any manufacturer, using the first one found. To pick the manufacturer of
+    /// a third party unit, see
+    /// [**AudioUnit::new_with_description**](./struct.AudioUnit#method.new_with_description).
     pub fn new<T>(ty: T) -> Result<AudioUnit, Error>
     where
         T: Into<Type>,
@@ -120,8 +312,16 @@ impl AudioUnit {
     where
         T: Into<Type>,
     {
-        const MANUFACTURER_IDENTIFIER: u32 = sys::kAudioUnitManufacturer_Apple;
         let au_type: Type = ty.into();
+        // A manufacturer of `0` matches any manufacturer, as `Custom` subtypes may belong to third
+        // party units.
+        let manufacturer = match au_type {
+            Type::Effect(EffectType::Custom(_))
+            | Type::Mixer(MixerType::Custom(_))
+            | Type::Generator(GeneratorType::Custom(_))
+            | Type::MusicDevice(MusicDeviceType::Custom(_)) => 0,
+            _ => sys::kAudioUnitManufacturer_Apple,
+        };
         let sub_type_u32 = match au_type.as_subtype_u32() {
             Some(u) => u,
             None => return Err(Error::NoKnownSubtype),
@@ -131,11 +331,39 @@ impl AudioUnit {
         let desc = sys::AudioComponentDescription {
             componentType: au_type.as_u32() as c_uint,
             componentSubType: sub_type_u32 as c_uint,
-            componentManufacturer: MANUFACTURER_IDENTIFIER,
+            componentManufacturer: manufacturer,
             componentFlags: flags,
             componentFlagsMask: mask,
         };
+        AudioUnit::new_with_component_description(&desc)
+    }
+
+    /// Construct a new AudioUnit from the raw four character codes identifying its component,
+    /// e.g. a third-party plugin that has no corresponding [**Type**](./enum.Type).
+    ///
+    /// The codes may be created from byte strings via [**four_char_code**](./fn.four_char_code),
+    /// e.g. `four_char_code(b"aufx")` for the effect type.
+    ///
+    /// Returns `Error::NoMatchingDefaultAudioUnitFound` if no such component is installed.
+    pub fn new_with_description(
+        component_type: u32,
+        sub_type: u32,
+        manufacturer: u32,
+    ) -> Result<AudioUnit, Error> {
+        let desc = sys::AudioComponentDescription {
+            componentType: component_type,
+            componentSubType: sub_type,
+            componentManufacturer: manufacturer,
+            componentFlags: 0,
+            componentFlagsMask: 0,
+        };
+        AudioUnit::new_with_component_description(&desc)
+    }
 
+    /// Construct and initialize an AudioUnit from the first component matching the description.
+    fn new_with_component_description(
+        desc: &sys::AudioComponentDescription,
+    ) -> Result<AudioUnit, Error> {
         unsafe {
             // Find the default audio unit for the description.
             //
@@ -145,7 +373,7 @@ impl AudioUnit {
             // find the first system audio unit matching the description, using a system-defined
             // ordering. If you instead pass a previously found audio unit reference in this
             // parameter, the function locates the next audio unit matching the description.
-            let component = sys::AudioComponentFindNext(ptr::null_mut(), &desc as *const _);
+            let component = sys::AudioComponentFindNext(ptr::null_mut(), desc as *const _);
             if component.is_null() {
                 return Err(Error::NoMatchingDefaultAudioUnitFound);
             }
@@ -160,14 +388,72 @@ impl AudioUnit {
 
             // Initialise the audio unit!
             try_os_status!(sys::AudioUnitInitialize(instance));
+            let id = sys::kAudioUnitProperty_MaximumFramesPerSlice;
+            let max_frames_per_slice = get_property(instance, id, Scope::Global, Element::Output);
             Ok(AudioUnit {
                 instance,
                 maybe_render_callback: None,
+                maybe_render_format_listener: None,
+                bus_render_callbacks: Vec::new(),
                 maybe_input_callback: None,
+                render_notify_callbacks: Vec::new(),
+                #[cfg(feature = "audio_toolbox")]
+                maybe_midi_output_callback: None,
+                render_paused: Arc::new(AtomicBool::new(false)),
+                render_buffers_invalid: Arc::new(AtomicBool::new(false)),
+                scheduled_slices: Vec::new(),
+                max_frames_per_slice: max_frames_per_slice.ok(),
+                dispose_on_drop: true,
             })
         }
     }
 
+    /// Adopt an **AudioUnit** instance that was created outside of this crate, e.g. by another
+    /// library or directly via `AudioComponentInstanceNew`.
+    ///
+    /// The instance is used as-is; it is not initialized by this function.
+    ///
+    /// # Safety
+    ///
+    /// - `instance` must be a valid, non-null audio component instance.
+    /// - If `dispose_on_drop` is `true`, ownership of the instance is transferred to the returned
+    ///   **AudioUnit**, which stops, uninitializes and disposes it when dropped. No one else may
+    ///   dispose the instance.
+    /// - If `dispose_on_drop` is `false`, the instance is left untouched on drop and the caller
+    ///   remains responsible for disposing it, but only after the returned **AudioUnit** has been
+    ///   dropped. Callbacks set through the returned **AudioUnit** are freed when it is dropped,
+    ///   so the caller must ensure the instance no longer invokes them by then, e.g. by stopping
+    ///   it or removing them.
+    pub unsafe fn from_raw(instance: sys::AudioUnit, dispose_on_drop: bool) -> AudioUnit {
+        let id = sys::kAudioUnitProperty_MaximumFramesPerSlice;
+        let max_frames_per_slice = get_property(instance, id, Scope::Global, Element::Output);
+        AudioUnit {
+            instance,
+            maybe_render_callback: None,
+            maybe_render_format_listener: None,
+            bus_render_callbacks: Vec::new(),
+            maybe_input_callback: None,
+            render_notify_callbacks: Vec::new(),
+            #[cfg(feature = "audio_toolbox")]
+            maybe_midi_output_callback: None,
+            render_paused: Arc::new(AtomicBool::new(false)),
+            render_buffers_invalid: Arc::new(AtomicBool::new(false)),
+            scheduled_slices: Vec::new(),
+            max_frames_per_slice: max_frames_per_slice.ok(),
+            dispose_on_drop,
+        }
+    }
+
+    /// The underlying audio component instance, for calling Core Audio functions that are not
+    /// yet wrapped by this crate.
+    ///
+    /// The instance remains owned by the **AudioUnit** and is only valid for as long as the
+    /// **AudioUnit** is alive. It must not be disposed of, and any callbacks or other state changed
+    /// through it are not tracked by the **AudioUnit**.
+    pub fn as_raw(&self) -> sys::AudioUnit {
+        self.instance
+    }
+
     /// On successful initialization, the audio formats for input and output are valid
     /// and the audio unit is ready to render. During initialization, an audio unit
     /// allocates memory according to the maximum number of audio frames it can produce
@@ -179,6 +465,7 @@ impl AudioUnit {
         unsafe {
             try_os_status!(sys::AudioUnitInitialize(self.instance));
         }
+        self.max_frames_per_slice = self.maximum_frames_per_slice().ok();
         Ok(())
     }
 
@@ -210,16 +497,22 @@ impl AudioUnit {
     ///
     /// - **id**: The identifier of the property.
     /// - **scope**: The audio unit scope for the property.
-    /// - **elem**: The audio unit element for the property.
+    /// - **elem**: The audio unit element for the property. This may be an **Element** or the
+    ///   index of a bus.
     /// - **maybe_data**: The value that you want to apply to the property.
     pub fn set_property<T>(
         &mut self,
         id: u32,
         scope: Scope,
-        elem: Element,
+        elem: impl Into<u32>,
         maybe_data: Option<&T>,
     ) -> Result<(), Error> {
-        set_property(self.instance, id, scope, elem, maybe_data)
+        let result = set_property(self.instance, id, scope, elem, maybe_data);
+        if id == sys::kAudioUnitProperty_MaximumFramesPerSlice {
+            // Keep the cached value read by `schedule_parameters` in sync with the unit.
+            self.max_frames_per_slice = self.maximum_frames_per_slice().ok();
+        }
+        result
     }
 
     /// Gets the value of an **AudioUnit** property.
@@ -231,11 +524,114 @@ impl AudioUnit {
     ///
     /// - **id**: The identifier of the property.
     /// - **scope**: The audio unit scope for the property.
-    /// - **elem**: The audio unit element for the property.
-    pub fn get_property<T>(&self, id: u32, scope: Scope, elem: Element) -> Result<T, Error> {
+    /// - **elem**: The audio unit element for the property. This may be an **Element** or the
+    ///   index of a bus.
+    pub fn get_property<T>(&self, id: u32, scope: Scope, elem: impl Into<u32>) -> Result<T, Error> {
         get_property(self.instance, id, scope, elem)
     }
 
+    /// Gets the value of an **AudioUnit** property whose data is a `CFStringRef` as a `String`.
+    ///
+    /// The `CFStringRef` returned by the audio unit is owned by the caller, so it is released once
+    /// it has been copied. Returns `None` if the property holds no string or an empty one.
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **id**: The identifier of the property.
+    /// - **scope**: The audio unit scope for the property.
+    /// - **elem**: The audio unit element for the property. This may be an **Element** or the
+    ///   index of a bus.
+    pub fn get_cfstring_property(
+        &self,
+        id: u32,
+        scope: Scope,
+        elem: impl Into<u32>,
+    ) -> Result<Option<String>, Error> {
+        let cf_string: CFStringRef = self.get_property(id, scope, elem)?;
+        if cf_string.is_null() {
+            return Ok(None);
+        }
+        let string = unsafe {
+            let string = cf_string_to_string(cf_string);
+            CFRelease(cf_string as *const c_void);
+            string.ok_or(Error::Unspecified)?
+        };
+        if string.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(string))
+        }
+    }
+
+    /// Gets the current value of an **AudioUnit** parameter.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **id**: The identifier of the parameter.
+    /// - **scope**: The audio unit scope for the parameter.
+    /// - **elem**: The audio unit element for the parameter. This may be an **Element** or the
+    ///   index of a bus, e.g. the input bus of a mixer.
+    pub fn parameter<P, E>(&self, id: P, scope: Scope, elem: E) -> Result<f32, Error>
+    where
+        P: Into<u32>,
+        E: Into<u32>,
+    {
+        let mut value: sys::AudioUnitParameterValue = 0.0;
+        unsafe {
+            try_os_status!(sys::AudioUnitGetParameter(
+                self.instance,
+                id.into(),
+                scope as c_uint,
+                elem.into(),
+                &mut value as *mut _,
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Sets the value of an **AudioUnit** parameter.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **id**: The identifier of the parameter.
+    /// - **scope**: The audio unit scope for the parameter.
+    /// - **elem**: The audio unit element for the parameter. This may be an **Element** or the
+    ///   index of a bus, e.g. the input bus of a mixer.
+    /// - **value**: The new value of the parameter.
+    /// - **buffer_offset_frames**: The number of frames into the next render buffer at which the
+    ///   change should take effect. Use `0` to apply the change immediately.
+    pub fn set_parameter<P, E>(
+        &mut self,
+        id: P,
+        scope: Scope,
+        elem: E,
+        value: f32,
+        buffer_offset_frames: u32,
+    ) -> Result<(), Error>
+    where
+        P: Into<u32>,
+        E: Into<u32>,
+    {
+        unsafe {
+            try_os_status!(sys::AudioUnitSetParameter(
+                self.instance,
+                id.into(),
+                scope as c_uint,
+                elem.into(),
+                value,
+                buffer_offset_frames,
+            ));
+        }
+        Ok(())
+    }
+
     /// Starts an I/O **AudioUnit**, which in turn starts the audio unit processing graph that it is
     /// connected to.
     ///
@@ -247,6 +643,26 @@ impl AudioUnit {
         Ok(())
     }
 
+    /// Starts an I/O **AudioUnit** at the given time via `kAudioOutputUnitProperty_StartTime`,
+    /// e.g. to start several units or devices in sync.
+    ///
+    /// The `time_stamp` should usually have a valid host time, i.e. its `mFlags` should contain
+    /// `kAudioTimeStampHostTimeValid`. The `flags` of the start request are reserved by Core
+    /// Audio and should currently be `0`.
+    ///
+    /// The unit is started immediately but does not begin rendering until the given time.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn start_at(&mut self, time_stamp: &sys::AudioTimeStamp, flags: u32) -> Result<(), Error> {
+        let params = sys::AudioOutputUnitStartAtTimeParams {
+            mTimestamp: *time_stamp,
+            mFlags: flags,
+        };
+        let id = sys::kAudioOutputUnitProperty_StartTime;
+        self.set_property(id, Scope::Global, Element::Output, Some(&params))?;
+        self.start()
+    }
+
     /// Stops an I/O **AudioUnit**, which in turn stops the audio unit processing graph that it is
     /// connected to.
     ///
@@ -258,18 +674,191 @@ impl AudioUnit {
         Ok(())
     }
 
+    /// Whether or not the I/O **AudioUnit** is currently running, according to Core Audio's
+    /// `kAudioOutputUnitProperty_IsRunning`.
+    ///
+    /// This reflects the actual state of the unit, e.g. after it was stopped by another part of
+    /// the application or failed to start.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn is_running(&self) -> Result<bool, Error> {
+        let id = sys::kAudioOutputUnitProperty_IsRunning;
+        let is_running: u32 = self.get_property(id, Scope::Global, Element::Output)?;
+        Ok(is_running != 0)
+    }
+
+    /// Starts an I/O **AudioUnit** and returns a **RunningGuard** that stops it again when
+    /// dropped, e.g. at the end of a test or a short-lived playback scope.
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::{AudioUnit, IOType};
+    /// # fn main() -> Result<(), coreaudio::Error> {
+    /// let mut audio_unit = AudioUnit::new(IOType::DefaultOutput)?;
+    /// {
+    ///     let _running = audio_unit.start_guarded()?;
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// } // The unit is stopped here.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn start_guarded(&mut self) -> Result<RunningGuard<'_>, Error> {
+        self.start()?;
+        Ok(RunningGuard { audio_unit: self })
+    }
+
+    /// Starts an I/O **AudioUnit**, blocks the current thread for the given `duration` and then
+    /// stops the unit.
+    pub fn run_for(&mut self, duration: Duration) -> Result<(), Error> {
+        self.start()?;
+        thread::sleep(duration);
+        self.stop()
+    }
+
     /// Set the **AudioUnit**'s sample rate.
     ///
+    /// This sets the sample rate of the input scope of the output element. See
+    /// [**set_sample_rate_for**](./struct.AudioUnit.html#method.set_sample_rate_for) to set it on
+    /// a different scope or element.
+    ///
     /// **Available** in iOS 2.0 and later.
     pub fn set_sample_rate(&mut self, sample_rate: f64) -> Result<(), Error> {
-        let id = sys::kAudioUnitProperty_SampleRate;
-        self.set_property(id, Scope::Input, Element::Output, Some(&sample_rate))
+        self.set_sample_rate_for(sample_rate, Scope::Input, Element::Output)
     }
 
     /// Get the **AudioUnit**'s sample rate.
+    ///
+    /// This is the sample rate of the input scope of the output element.
     pub fn sample_rate(&self) -> Result<f64, Error> {
+        self.sample_rate_for(Scope::Input, Element::Output)
+    }
+
+    /// Set the sample rate of the given scope and element via `kAudioUnitProperty_SampleRate`,
+    /// leaving the rest of its stream format untouched.
+    ///
+    /// Some units, e.g. the **AUConverter**, allow the sample rate to differ between their input
+    /// and output scopes.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn set_sample_rate_for(
+        &mut self,
+        sample_rate: f64,
+        scope: Scope,
+        element: impl Into<u32>,
+    ) -> Result<(), Error> {
         let id = sys::kAudioUnitProperty_SampleRate;
-        self.get_property(id, Scope::Input, Element::Output)
+        self.set_property(id, scope, element, Some(&sample_rate))
+    }
+
+    /// Get the sample rate of the given scope and element via `kAudioUnitProperty_SampleRate`.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn sample_rate_for(&self, scope: Scope, element: impl Into<u32>) -> Result<f64, Error> {
+        let id = sys::kAudioUnitProperty_SampleRate;
+        self.get_property(id, scope, element)
+    }
+
+    /// Set the sample rate at which an I/O unit exchanges audio with the application.
+    ///
+    /// This sets the sample rate of the audio provided to the output element and, if the unit has
+    /// input, of the audio delivered by the input element. The unit converts between these rates
+    /// and that of the device as necessary. To change the rate of the device itself, see
+    /// `macos_helpers::set_device_sample_rate`.
+    pub fn set_io_sample_rate(&mut self, sample_rate: f64) -> Result<(), Error> {
+        self.set_sample_rate_for(sample_rate, Scope::Input, Element::Output)?;
+        if self.has_io(Scope::Input, Element::Input)? {
+            self.set_sample_rate_for(sample_rate, Scope::Output, Element::Input)?;
+        }
+        Ok(())
+    }
+
+    /// The maximum number of frames that the **AudioUnit** is prepared to render per call.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn maximum_frames_per_slice(&self) -> Result<u32, Error> {
+        let id = sys::kAudioUnitProperty_MaximumFramesPerSlice;
+        self.get_property(id, Scope::Global, Element::Output)
+    }
+
+    /// Set the maximum number of frames that the **AudioUnit** is prepared to render per call.
+    ///
+    /// Rendering more frames fails with `Error::TooManyFramesToProcess`, so all units
+    /// in a chain must be configured for the largest number of frames requested by the output.
+    /// See [**propagate_max_frames_per_slice**](./fn.propagate_max_frames_per_slice.html).
+    ///
+    /// The **AudioUnit** must be uninitialized when this is set.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn set_maximum_frames_per_slice(&mut self, frames: u32) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_MaximumFramesPerSlice;
+        self.set_property(id, Scope::Global, Element::Output, Some(&frames))
+    }
+
+    /// Whether the **AudioUnit** has been told that it is rendering offline, i.e. faster than
+    /// realtime.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn offline_render(&self) -> Result<bool, Error> {
+        let id = sys::kAudioUnitProperty_OfflineRender;
+        let offline: u32 = self.get_property(id, Scope::Global, Element::Output)?;
+        Ok(offline != 0)
+    }
+
+    /// Tell the **AudioUnit** whether it is rendering offline, i.e. faster than realtime, via
+    /// `kAudioUnitProperty_OfflineRender`.
+    ///
+    /// Units rendering offline may use higher quality algorithms or disable optimizations meant
+    /// for realtime rendering, so this should be set when bouncing audio to match the output of
+    /// other hosts. Not all units support the property.
+    ///
+    /// **Available** in OS X v10.3 and later.
+    pub fn set_offline_render(&mut self, offline: bool) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_OfflineRender;
+        let offline = offline as u32;
+        self.set_property(id, Scope::Global, Element::Output, Some(&offline))
+    }
+
+    /// Tell the **AudioUnit** how much latency, in seconds, lies between its output and the point
+    /// at which the audio is presented, e.g. heard from the speakers, via
+    /// `kAudioUnitProperty_PresentationLatency`.
+    ///
+    /// Time-based units such as synchronized delays use this to compensate for the delay. The
+    /// value is the latency of everything *after* the unit, not including the unit's own
+    /// latency: the sum of the `kAudioUnitProperty_Latency` of each unit downstream of it, plus
+    /// the output latency and safety offset of the device. In a chain `A -> B -> C -> output`,
+    /// `C` is told only the device latency, `B` the latency of `C` plus the device latency and so
+    /// on, so the first unit in a chain is told the largest value.
+    ///
+    /// **Available** in OS X v10.4 and later.
+    pub fn set_presentation_latency(&mut self, seconds: f64) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_PresentationLatency;
+        self.set_property(id, Scope::Output, Element::Output, Some(&seconds))
+    }
+
+    /// Set the render quality of the **AudioUnit**, trading CPU usage for quality.
+    ///
+    /// The quality ranges from `0` (minimum) to `127` (maximum) and larger values are clamped to
+    /// `127`. Apple defines the reference points `0` (min), `32` (low), `64` (medium), `96` (high)
+    /// and `127` (max).
+    ///
+    /// Only some units honour this property: units that perform sample rate conversion such as
+    /// the **AUConverter**, **Varispeed** and the I/O units (when the stream format sample rate
+    /// differs from that of the device), as well as the **MatrixReverb** and the **DLSSynth**.
+    /// Most other units ignore it.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn set_render_quality(&mut self, quality: u8) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_RenderQuality;
+        let quality = quality.min(127) as u32;
+        self.set_property(id, Scope::Global, Element::Output, Some(&quality))
+    }
+
+    /// Get the render quality of the **AudioUnit** in the range `0` to `127`.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn render_quality(&self) -> Result<u8, Error> {
+        let id = sys::kAudioUnitProperty_RenderQuality;
+        let quality: u32 = self.get_property(id, Scope::Global, Element::Output)?;
+        Ok(quality.min(127) as u8)
     }
 
     /// Sets the current **StreamFormat** for the AudioUnit.
@@ -312,6 +901,106 @@ impl AudioUnit {
     pub fn input_stream_format(&self) -> Result<StreamFormat, Error> {
         self.stream_format(Scope::Input)
     }
+
+    /// Retrieve the description of the component from which the **AudioUnit** was instantiated.
+    fn component_description(&self) -> Result<sys::AudioComponentDescription, Error> {
+        unsafe {
+            let component = sys::AudioComponentInstanceGetComponent(self.instance);
+            let mut desc = mem::MaybeUninit::<sys::AudioComponentDescription>::uninit();
+            try_os_status!(sys::AudioComponentGetDescription(
+                component,
+                desc.as_mut_ptr()
+            ));
+            Ok(desc.assume_init())
+        }
+    }
+
+    /// Whether or not the I/O **AudioUnit** has input or output available on the given scope and
+    /// element.
+    ///
+    /// This can be used to check that enabling I/O via `kAudioOutputUnitProperty_EnableIO` has
+    /// taken effect, e.g. `has_io(Scope::Input, Element::Input)` for device input.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn has_io(&self, scope: Scope, element: Element) -> Result<bool, Error> {
+        let id = sys::kAudioOutputUnitProperty_HasIO;
+        let has_io: u32 = self.get_property(id, scope, element)?;
+        Ok(has_io != 0)
+    }
+
+    /// Get the nickname of the **AudioUnit**.
+    ///
+    /// Hosts may give each instance of an audio unit a nickname in order to distinguish it from
+    /// other instances of the same unit. Returns `None` if no nickname is set.
+    ///
+    /// **Available** in OS X v10.11 and later.
+    pub fn nickname(&self) -> Result<Option<String>, Error> {
+        let id = sys::kAudioUnitProperty_NickName;
+        self.get_cfstring_property(id, Scope::Global, Element::Output)
+    }
+
+    /// Set the nickname of the **AudioUnit**.
+    ///
+    /// **Available** in OS X v10.11 and later.
+    pub fn set_nickname(&mut self, nickname: &str) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_NickName;
+        set_cf_string_property(self.instance, id, Scope::Global, Element::Output, nickname)
+    }
+
+    /// Get the context name of the **AudioUnit**.
+    ///
+    /// The context name is set by a host to describe where the audio unit lives within the host,
+    /// e.g. the name of the track or bus that it is inserted on. Returns an empty `String` if no
+    /// context name is set.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn context_name(&self) -> Result<String, Error> {
+        let id = sys::kAudioUnitProperty_ContextName;
+        let context_name = self.get_cfstring_property(id, Scope::Global, Element::Output)?;
+        Ok(context_name.unwrap_or_default())
+    }
+
+    /// Set the context name of the **AudioUnit**.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn set_context_name(&mut self, context_name: &str) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_ContextName;
+        set_cf_string_property(
+            self.instance,
+            id,
+            Scope::Global,
+            Element::Output,
+            context_name,
+        )
+    }
+}
+
+/// Stops the running **AudioUnit** when dropped.
+///
+/// Returned by [**AudioUnit::start_guarded**](./struct.AudioUnit.html#method.start_guarded). The
+/// guard dereferences to the **AudioUnit**, so the unit may still be configured while running.
+pub struct RunningGuard<'a> {
+    audio_unit: &'a mut AudioUnit,
+}
+
+impl<'a> Deref for RunningGuard<'a> {
+    type Target = AudioUnit;
+    fn deref(&self) -> &AudioUnit {
+        self.audio_unit
+    }
+}
+
+impl<'a> DerefMut for RunningGuard<'a> {
+    fn deref_mut(&mut self) -> &mut AudioUnit {
+        self.audio_unit
+    }
+}
+
+impl<'a> Drop for RunningGuard<'a> {
+    fn drop(&mut self) {
+        // There is nothing sensible to do with a failure to stop while dropping.
+        self.audio_unit.stop().ok();
+    }
 }
 
 unsafe impl Send for AudioUnit {}
@@ -325,13 +1014,23 @@ impl Drop for AudioUnit {
             //
             // A user should explicitly terminate the `AudioUnit` if they want to handle errors (we
             // still need to provide a way to actually do that).
-            self.stop().ok();
-            error::Error::from_os_status(sys::AudioUnitUninitialize(self.instance)).ok();
+            if self.dispose_on_drop {
+                self.stop().ok();
+                error::Error::from_os_status(sys::AudioUnitUninitialize(self.instance)).ok();
+            }
 
             self.free_render_callback();
+            self.free_bus_render_callbacks();
             self.free_input_callback();
+            self.free_render_notify_callbacks();
+            #[cfg(feature = "audio_toolbox")]
+            self.free_midi_output_callback();
+            self.free_scheduled_slices();
 
-            error::Error::from_os_status(sys::AudioComponentInstanceDispose(self.instance)).ok();
+            if self.dispose_on_drop {
+                error::Error::from_os_status(sys::AudioComponentInstanceDispose(self.instance))
+                    .ok();
+            }
         }
     }
 }
@@ -352,13 +1051,14 @@ impl Drop for AudioUnit {
 /// - **au**: The AudioUnit instance.
 /// - **id**: The identifier of the property.
 /// - **scope**: The audio unit scope for the property.
-/// - **elem**: The audio unit element for the property.
+/// - **elem**: The audio unit element for the property. This may be an **Element** or the index
+///   of a bus.
 /// - **maybe_data**: The value that you want to apply to the property.
 pub fn set_property<T>(
     au: sys::AudioUnit,
     id: u32,
     scope: Scope,
-    elem: Element,
+    elem: impl Into<u32>,
     maybe_data: Option<&T>,
 ) -> Result<(), Error> {
     let (data_ptr, size) = maybe_data
@@ -369,7 +1069,7 @@ pub fn set_property<T>(
         })
         .unwrap_or_else(|| (::std::ptr::null(), 0));
     let scope = scope as c_uint;
-    let elem = elem as c_uint;
+    let elem = elem.into() as c_uint;
     unsafe {
         try_os_status!(sys::AudioUnitSetProperty(
             au, id, scope, elem, data_ptr, size
@@ -388,15 +1088,16 @@ pub fn set_property<T>(
 /// - **au**: The AudioUnit instance.
 /// - **id**: The identifier of the property.
 /// - **scope**: The audio unit scope for the property.
-/// - **elem**: The audio unit element for the property.
+/// - **elem**: The audio unit element for the property. This may be an **Element** or the index
+///   of a bus.
 pub fn get_property<T>(
     au: sys::AudioUnit,
     id: u32,
     scope: Scope,
-    elem: Element,
+    elem: impl Into<u32>,
 ) -> Result<T, Error> {
     let scope = scope as c_uint;
-    let elem = elem as c_uint;
+    let elem = elem.into() as c_uint;
     let mut size = ::std::mem::size_of::<T>() as u32;
     unsafe {
         let mut data_uninit = ::std::mem::MaybeUninit::<T>::uninit();
@@ -410,6 +1111,126 @@ pub fn get_property<T>(
     }
 }
 
+/// Gets the value of an **AudioUnit** property whose data is a variable-length array of `T`.
+///
+/// The size of the property is queried first so that a buffer of the correct length can be
+/// allocated.
+fn get_property_array<T>(
+    au: sys::AudioUnit,
+    id: u32,
+    scope: Scope,
+    elem: impl Into<u32>,
+) -> Result<Vec<T>, Error> {
+    let scope = scope as c_uint;
+    let elem = elem.into() as c_uint;
+    let mut size = 0u32;
+    unsafe {
+        try_os_status!(sys::AudioUnitGetPropertyInfo(
+            au,
+            id,
+            scope,
+            elem,
+            &mut size as *mut _,
+            ptr::null_mut(),
+        ));
+        let len = size as usize / mem::size_of::<T>();
+        let mut data: Vec<T> = Vec::with_capacity(len);
+        try_os_status!(sys::AudioUnitGetProperty(
+            au,
+            id,
+            scope,
+            elem,
+            data.as_mut_ptr() as *mut c_void,
+            &mut size as *mut _,
+        ));
+        // The unit may report fewer bytes than it initially asked for.
+        data.set_len(size as usize / mem::size_of::<T>());
+        Ok(data)
+    }
+}
+
+/// Sets the value of an **AudioUnit** property whose data is a `CFStringRef`.
+///
+/// The audio unit retains its own reference to the string, so ours is released after setting.
+fn set_cf_string_property(
+    au: sys::AudioUnit,
+    id: u32,
+    scope: Scope,
+    elem: impl Into<u32>,
+    value: &str,
+) -> Result<(), Error> {
+    let cf_string = cf_string_from_str(value)?;
+    let result = set_property(au, id, scope, elem, Some(&cf_string));
+    unsafe { CFRelease(cf_string as *const c_void) };
+    result
+}
+
+/// Create a new `CFStringRef` with the contents of the given `str`.
+///
+/// The returned string is owned by the caller and must be released with `CFRelease`.
+fn cf_string_from_str(value: &str) -> Result<CFStringRef, Error> {
+    let cf_string = unsafe {
+        CFStringCreateWithBytes(
+            kCFAllocatorDefault,
+            value.as_ptr(),
+            value.len() as _,
+            kCFStringEncodingUTF8,
+            false as _,
+        )
+    };
+    if cf_string.is_null() {
+        return Err(Error::Unspecified);
+    }
+    Ok(cf_string)
+}
+
+/// Create a new file `CFURLRef` pointing at the given path.
+///
+/// The returned URL is owned by the caller and must be released with `CFRelease`.
+#[cfg(feature = "audio_toolbox")]
+fn cf_url_from_path(path: &std::path::Path) -> Result<core_foundation_sys::url::CFURLRef, Error> {
+    use core_foundation_sys::url::CFURLCreateFromFileSystemRepresentation;
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = path.as_os_str().as_bytes();
+    let cf_url = unsafe {
+        CFURLCreateFromFileSystemRepresentation(
+            kCFAllocatorDefault,
+            bytes.as_ptr(),
+            bytes.len() as _,
+            path.is_dir() as _,
+        )
+    };
+    if cf_url.is_null() {
+        return Err(Error::Unspecified);
+    }
+    Ok(cf_url)
+}
+
+/// Copy the contents of a `CFStringRef` into a `String`.
+///
+/// Returns `None` if the string could not be represented as UTF-8.
+unsafe fn cf_string_to_string(cf_string: CFStringRef) -> Option<String> {
+    let c_string: *const c_char = CFStringGetCStringPtr(cf_string, kCFStringEncodingUTF8);
+    if !c_string.is_null() {
+        return Some(CStr::from_ptr(c_string).to_string_lossy().into_owned());
+    }
+    // The fast path is not available for all strings, so copy into our own buffer. A UTF-16 code
+    // unit never takes more than 4 bytes in UTF-8, plus one byte for the null terminator.
+    let len = CFStringGetLength(cf_string) as usize * 4 + 1;
+    let mut buf: Vec<c_char> = vec![0; len];
+    let result = CFStringGetCString(
+        cf_string,
+        buf.as_mut_ptr(),
+        buf.len() as _,
+        kCFStringEncodingUTF8,
+    );
+    if result == 0 {
+        return None;
+    }
+    Some(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned())
+}
+
 /// Gets the value of a specified audio session property.
 ///
 /// **Available** in iOS 2.0 and later.
@@ -430,3 +1251,65 @@ pub fn audio_session_get_property<T>(id: u32) -> Result<T, Error> {
         Ok(data)
     }
 }
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::effect::{Delay, LowPassFilter};
+    use super::render_callback::{data, Args};
+    use super::test_util::render_offline;
+    use super::*;
+
+    #[test]
+    fn propagated_max_frames_per_slice_renders_large_slices_through_a_chain() {
+        // Well above the default of 1156 frames.
+        const FRAMES: u32 = 8192;
+
+        let mut source = Delay::new().unwrap().into_audio_unit();
+        source
+            .set_render_callback(|mut args: Args<data::NonInterleaved<f32>>| {
+                for channel in args.data.channels_mut() {
+                    channel.iter_mut().for_each(|sample| *sample = 0.0);
+                }
+                Ok(())
+            })
+            .unwrap();
+        let filter = LowPassFilter::new().unwrap().into_audio_unit();
+        let mut units = [source, filter];
+        propagate_max_frames_per_slice(&mut units, FRAMES).unwrap();
+        for unit in &units {
+            assert_eq!(unit.maximum_frames_per_slice().unwrap(), FRAMES);
+        }
+
+        let (source, filter) = units.split_at_mut(1);
+        let (source, filter) = (&source[0], &mut filter[0]);
+        unsafe { filter.connect_input(0, source, 0).unwrap() };
+        let rendered = render_offline(filter, FRAMES as usize, FRAMES).unwrap();
+        assert_eq!(rendered.len(), FRAMES as usize);
+        filter.disconnect_input(0).unwrap();
+    }
+
+    #[test]
+    fn offline_render_round_trips() {
+        let mut unit = AudioUnit::new(EffectType::Delay).unwrap();
+        unit.set_offline_render(true).unwrap();
+        assert!(unit.offline_render().unwrap());
+        unit.set_offline_render(false).unwrap();
+        assert!(!unit.offline_render().unwrap());
+    }
+
+    #[test]
+    fn cf_string_properties_round_trip() {
+        let mut unit = AudioUnit::new(EffectType::Delay).unwrap();
+        assert_eq!(unit.nickname().unwrap(), None);
+        assert_eq!(unit.context_name().unwrap(), "");
+
+        // Includes a non-ASCII character to exercise the UTF-8 conversion.
+        unit.set_nickname("Delay \u{2014} Guitar Bus").unwrap();
+        assert_eq!(
+            unit.nickname().unwrap().as_deref(),
+            Some("Delay \u{2014} Guitar Bus")
+        );
+        unit.set_context_name("Track 1").unwrap();
+        assert_eq!(unit.context_name().unwrap(), "Track 1");
+    }
+}
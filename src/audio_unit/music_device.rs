@@ -0,0 +1,388 @@
+//! Methods for playing instrument (music device) **AudioUnit**s via MIDI.
+//!
+//! Find the original Music Device reference [here](https://developer.apple.com/documentation/audiotoolbox/music_device).
+
+use super::types::Type;
+use super::{AudioUnit, Element, Scope};
+use crate::error::Error;
+use core_foundation_sys::array::{CFArrayGetCount, CFArrayGetValueAtIndex, CFArrayRef};
+use core_foundation_sys::base::CFRelease;
+use core_foundation_sys::string::CFStringRef;
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+use std::slice;
+use sys;
+
+/// A MIDI packet emitted by an **AudioUnit** via its MIDI output callback.
+#[derive(Copy, Clone, Debug)]
+pub struct MidiPacket<'a> {
+    /// The index of the MIDI output that emitted the packet. See
+    /// [**midi_output_names**](../struct.AudioUnit.html#method.midi_output_names).
+    pub output: u32,
+    /// The host time at which the events in the packet occur.
+    pub time_stamp: u64,
+    /// One or more complete MIDI messages.
+    pub data: &'a [u8],
+}
+
+/// An iterator over the packets of a `MIDIPacketList` emitted by an **AudioUnit**, as passed to
+/// its MIDI output callback.
+///
+/// The packets are read from the list as they are iterated, so iterating doesn't allocate.
+pub struct MidiPackets<'a> {
+    output: u32,
+    packet: *const sys::MIDIPacket,
+    remaining: usize,
+    packet_list: PhantomData<&'a sys::MIDIPacketList>,
+}
+
+impl<'a> Iterator for MidiPackets<'a> {
+    type Item = MidiPacket<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            // `MIDIPacket` is packed, so its fields may be unaligned.
+            let packet = self.packet;
+            let time_stamp = ptr::read_unaligned(ptr::addr_of!((*packet).timeStamp));
+            let length = ptr::read_unaligned(ptr::addr_of!((*packet).length)) as usize;
+            let data = ptr::addr_of!((*packet).data) as *const u8;
+            self.packet = next_midi_packet(data, length);
+            Some(MidiPacket {
+                output: self.output,
+                time_stamp,
+                data: slice::from_raw_parts(data, length),
+            })
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for MidiPackets<'a> {}
+
+/// The type of closure called with the MIDI packets emitted by an **AudioUnit**.
+type MidiOutputFn = dyn FnMut(MidiPackets) + Send;
+
+/// This type allows us to safely wrap a boxed MIDI output closure to use within the output proc.
+pub struct MidiOutputFnWrapper {
+    callback: Box<MidiOutputFn>,
+}
+
+impl AudioUnit {
+    /// Send a MIDI channel message to a music device (instrument) or music effect **AudioUnit**.
+    ///
+    /// Returns `Error::NotAMusicDevice` if the unit is neither a music device (`aumu`) nor a music
+    /// effect (`aumf`).
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **status**: The MIDI status byte, including the channel.
+    /// - **data1**: The first MIDI data byte.
+    /// - **data2**: The second MIDI data byte.
+    /// - **offset_frames**: The number of frames into the next buffer at which the event occurs.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn send_midi(
+        &mut self,
+        status: u8,
+        data1: u8,
+        data2: u8,
+        offset_frames: u32,
+    ) -> Result<(), Error> {
+        let os_status = unsafe {
+            sys::MusicDeviceMIDIEvent(
+                self.instance,
+                status as u32,
+                data1 as u32,
+                data2 as u32,
+                offset_frames,
+            )
+        };
+        // Only check the unit type on failure so that sending events stays cheap.
+        if os_status != 0 && !self.is_music_device() {
+            return Err(Error::NotAMusicDevice);
+        }
+        Error::from_os_status(os_status)
+    }
+
+    /// Send a MIDI note-on message.
+    ///
+    /// The `channel` is in the range `0..16`, while `note` and `velocity` are in the range
+    /// `0..128`.
+    pub fn note_on(
+        &mut self,
+        channel: u8,
+        note: u8,
+        velocity: u8,
+        offset_frames: u32,
+    ) -> Result<(), Error> {
+        let status = 0x90 | (channel & 0x0F);
+        self.send_midi(status, note & 0x7F, velocity & 0x7F, offset_frames)
+    }
+
+    /// Send a MIDI note-off message.
+    ///
+    /// The `channel` is in the range `0..16`, while `note` and `velocity` are in the range
+    /// `0..128`.
+    pub fn note_off(
+        &mut self,
+        channel: u8,
+        note: u8,
+        velocity: u8,
+        offset_frames: u32,
+    ) -> Result<(), Error> {
+        let status = 0x80 | (channel & 0x0F);
+        self.send_midi(status, note & 0x7F, velocity & 0x7F, offset_frames)
+    }
+
+    /// Send a MIDI control change message.
+    ///
+    /// The `channel` is in the range `0..16`, while `controller` and `value` are in the range
+    /// `0..128`.
+    pub fn control_change(
+        &mut self,
+        channel: u8,
+        controller: u8,
+        value: u8,
+        offset_frames: u32,
+    ) -> Result<(), Error> {
+        let status = 0xB0 | (channel & 0x0F);
+        self.send_midi(status, controller & 0x7F, value & 0x7F, offset_frames)
+    }
+
+    /// Send a MIDI pitch bend message.
+    ///
+    /// The `channel` is in the range `0..16`. The 14-bit `value` is in the range `0..16384`, where
+    /// `8192` is the center (no bend).
+    pub fn pitch_bend(&mut self, channel: u8, value: u16, offset_frames: u32) -> Result<(), Error> {
+        let status = 0xE0 | (channel & 0x0F);
+        let lsb = (value & 0x7F) as u8;
+        let msb = ((value >> 7) & 0x7F) as u8;
+        self.send_midi(status, lsb, msb, offset_frames)
+    }
+
+    /// Start a note using the extended note API, allowing for fractional (microtonal) pitches and
+    /// per-note control values.
+    ///
+    /// Returns the identifier of the started note, used to stop it again via
+    /// [**stop_note**](./struct.AudioUnit.html#method.stop_note).
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **instrument**: The instrument to play. Use `sys::kMusicNoteEvent_UseGroupInstrument` to
+    ///   play the instrument currently assigned to the group.
+    /// - **group**: The group (e.g. MIDI channel) on which to play the note.
+    /// - **pitch**: The MIDI note number, where fractional values denote microtonal pitches.
+    /// - **velocity**: The MIDI velocity in the range `0.0..128.0`.
+    /// - **controls**: Additional per-note control values as pairs of parameter identifier and
+    ///   value.
+    /// - **offset_frames**: The number of frames into the next buffer at which the note starts.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn start_note(
+        &mut self,
+        instrument: u32,
+        group: u32,
+        pitch: f32,
+        velocity: f32,
+        controls: &[(u32, f32)],
+        offset_frames: u32,
+    ) -> Result<sys::NoteInstanceID, Error> {
+        // `MusicDeviceNoteParams` is a variable length struct whose trailing array holds one
+        // `NoteParamsControlValue` per control, so we allocate enough `u32`s for all of them.
+        let params_size = mem::size_of::<sys::MusicDeviceNoteParams>()
+            + controls.len().saturating_sub(1) * mem::size_of::<sys::NoteParamsControlValue>();
+        let mut storage = vec![0u32; params_size / mem::size_of::<u32>()];
+        let params = storage.as_mut_ptr() as *mut sys::MusicDeviceNoteParams;
+
+        let mut note_instance_id: sys::NoteInstanceID = 0;
+        let os_status = unsafe {
+            // The argument count covers the pitch, the velocity and one entry for each control.
+            (*params).mArgCount = 2 + controls.len() as u32;
+            (*params).mPitch = pitch;
+            (*params).mVelocity = velocity;
+            let controls_ptr = (*params).mControls.as_mut_ptr();
+            for (i, &(id, value)) in controls.iter().enumerate() {
+                *controls_ptr.add(i) = sys::NoteParamsControlValue {
+                    mID: id,
+                    mValue: value,
+                };
+            }
+            sys::MusicDeviceStartNote(
+                self.instance,
+                instrument,
+                group,
+                &mut note_instance_id as *mut _,
+                offset_frames,
+                params as *const _,
+            )
+        };
+        if os_status != 0 && !self.is_music_device() {
+            return Err(Error::NotAMusicDevice);
+        }
+        Error::from_os_status(os_status)?;
+        Ok(note_instance_id)
+    }
+
+    /// Stop a note started via [**start_note**](./struct.AudioUnit.html#method.start_note).
+    ///
+    /// Parameters
+    /// ----------
+    ///
+    /// - **group**: The group on which the note was started.
+    /// - **note_instance_id**: The identifier returned by `start_note`.
+    /// - **offset_frames**: The number of frames into the next buffer at which the note stops.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn stop_note(
+        &mut self,
+        group: u32,
+        note_instance_id: sys::NoteInstanceID,
+        offset_frames: u32,
+    ) -> Result<(), Error> {
+        let os_status = unsafe {
+            sys::MusicDeviceStopNote(self.instance, group, note_instance_id, offset_frames)
+        };
+        if os_status != 0 && !self.is_music_device() {
+            return Err(Error::NotAMusicDevice);
+        }
+        Error::from_os_status(os_status)
+    }
+
+    /// Pass a closure that receives the MIDI emitted by the **AudioUnit**, e.g. by an arpeggiator
+    /// or other MIDI effect, via `kAudioUnitProperty_MIDIOutputCallback`.
+    ///
+    /// The closure is called on the render thread, so it should avoid blocking. The packets are
+    /// passed as an iterator reading them straight from Core Audio's packet list, so no allocation
+    /// is involved. Any previously set MIDI output closure is replaced.
+    ///
+    /// **Available** in OS X v10.5 and later.
+    pub fn set_midi_output_callback<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(MidiPackets) + Send + 'static,
+    {
+        let wrapper = Box::new(MidiOutputFnWrapper {
+            callback: Box::new(f),
+        });
+        let wrapper_ptr = Box::into_raw(wrapper);
+        let callback = sys::AUMIDIOutputCallbackStruct {
+            midiOutputCallback: Some(midi_output_proc),
+            userData: wrapper_ptr as *mut c_void,
+        };
+        if let Err(err) = self.set_property(
+            sys::kAudioUnitProperty_MIDIOutputCallback,
+            Scope::Global,
+            Element::Output,
+            Some(&callback),
+        ) {
+            unsafe {
+                let _: Box<MidiOutputFnWrapper> = Box::from_raw(wrapper_ptr);
+            }
+            return Err(err);
+        }
+
+        self.free_midi_output_callback();
+        self.maybe_midi_output_callback = Some(wrapper_ptr);
+        Ok(())
+    }
+
+    /// The names of the MIDI outputs of the **AudioUnit**, indexed by `MidiPacket::output`.
+    ///
+    /// **Available** in OS X v10.5 and later.
+    pub fn midi_output_names(&self) -> Result<Vec<String>, Error> {
+        let array: CFArrayRef = self.get_property(
+            sys::kAudioUnitProperty_MIDIOutputCallbackInfo,
+            Scope::Global,
+            Element::Output,
+        )?;
+        if array.is_null() {
+            return Ok(Vec::new());
+        }
+        unsafe {
+            let count = CFArrayGetCount(array);
+            let mut names = Vec::with_capacity(count as usize);
+            for i in 0..count {
+                let name = CFArrayGetValueAtIndex(array, i) as CFStringRef;
+                names.push(super::cf_string_to_string(name).unwrap_or_default());
+            }
+            CFRelease(array as *const c_void);
+            Ok(names)
+        }
+    }
+
+    /// Retrieves ownership over the MIDI output callback so that it is safely dropped.
+    pub(crate) fn free_midi_output_callback(&mut self) {
+        if let Some(callback) = self.maybe_midi_output_callback.take() {
+            let _: Box<MidiOutputFnWrapper> = unsafe { Box::from_raw(callback) };
+        }
+    }
+
+    /// Whether or not the **AudioUnit** is a music device or music effect and in turn accepts
+    /// MIDI events.
+    fn is_music_device(&self) -> bool {
+        let music_device = Type::MusicDevice(super::MusicDeviceType::DLSSynth).as_u32();
+        let music_effect = Type::MusicEffect.as_u32();
+        match self.component_description() {
+            Ok(desc) => desc.componentType == music_device || desc.componentType == music_effect,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Callback procedure that will be called each time the audio unit emits MIDI.
+extern "C" fn midi_output_proc(
+    user_data: *mut c_void,
+    _time_stamp: *const sys::AudioTimeStamp,
+    midi_output_num: u32,
+    packet_list: *const sys::MIDIPacketList,
+) -> sys::OSStatus {
+    let wrapper = user_data as *mut MidiOutputFnWrapper;
+    unsafe {
+        let packets = MidiPackets {
+            output: midi_output_num,
+            packet: ptr::addr_of!((*packet_list).packet) as *const sys::MIDIPacket,
+            remaining: (*packet_list).numPackets as usize,
+            packet_list: PhantomData,
+        };
+        (*(*wrapper).callback)(packets);
+    }
+    0
+}
+
+/// The equivalent of the `MIDIPacketNext` macro, which aligns packets to 4 bytes on ARM.
+fn next_midi_packet(data: *const u8, length: usize) -> *const sys::MIDIPacket {
+    let end = data as usize + length;
+    if cfg!(any(target_arch = "arm", target_arch = "aarch64")) {
+        ((end + 3) & !3) as *const sys::MIDIPacket
+    } else {
+        end as *const sys::MIDIPacket
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::super::MusicDeviceType;
+    use super::*;
+
+    #[test]
+    fn start_note_with_controls() {
+        let mut synth = AudioUnit::new(MusicDeviceType::DLSSynth).unwrap();
+        let instrument = sys::kMusicNoteEvent_UseGroupInstrument;
+        for controls in [&[][..], &[(7, 100.0)][..], &[(7, 100.0), (10, 0.0)][..]] {
+            let note = synth
+                .start_note(instrument, 0, 60.5, 100.0, controls, 0)
+                .unwrap();
+            synth.stop_note(0, note, 0).unwrap();
+        }
+    }
+}
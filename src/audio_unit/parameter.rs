@@ -0,0 +1,569 @@
+//! Types and methods for inspecting the parameters of an **AudioUnit**.
+//!
+//! Find the original `AudioUnitParameterInfo` reference [here](https://developer.apple.com/documentation/audiotoolbox/audiounitparameterinfo).
+
+use super::{AudioUnit, Element, Scope};
+use crate::error::Error;
+use core_foundation_sys::array::{CFArrayGetCount, CFArrayGetValueAtIndex, CFArrayRef};
+use core_foundation_sys::base::CFRelease;
+use core_foundation_sys::string::CFStringRef;
+use std::mem;
+use std::os::raw::{c_uint, c_void};
+use std::ptr;
+use sys;
+
+pub use self::flags::ParameterFlags;
+
+/// The unit of measurement in which an **AudioUnit** parameter's value is expressed.
+///
+/// Original documentation [here](https://developer.apple.com/documentation/audiotoolbox/audiounitparameterunit).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParameterUnit {
+    /// Untyped value generally between 0.0 and 1.0.
+    Generic = 0,
+    /// Takes an integer value (good for menu selections).
+    Indexed = 1,
+    /// 0.0 means false, non-zero means true.
+    Boolean = 2,
+    /// Usually from 0 -> 100, sometimes -50 -> +50.
+    Percent = 3,
+    /// Absolute or relative time.
+    Seconds = 4,
+    /// One sample frame equals (1.0 / sample_rate) seconds.
+    SampleFrames = 5,
+    /// -180 to 180 degrees.
+    Phase = 6,
+    /// Rate multiplier, for playback speed, etc. (e.g. 2.0 == twice as fast).
+    Rate = 7,
+    /// Absolute frequency/pitch in cycles/second.
+    Hertz = 8,
+    /// Unit of relative pitch.
+    Cents = 9,
+    /// Useful for coarse detuning.
+    RelativeSemiTones = 10,
+    /// Absolute pitch as defined in the MIDI spec (exact freq may depend on tuning table).
+    MidiNoteNumber = 11,
+    /// A generic MIDI controller value from 0 -> 127.
+    MidiController = 12,
+    /// Logarithmic relative gain.
+    Decibels = 13,
+    /// Linear relative gain.
+    LinearGain = 14,
+    /// -180 to 180 degrees, similar to phase but more general (good for 3D coord system).
+    Degrees = 15,
+    /// 0 -> 1, crossfade mix two sources according to sqrt(x) and sqrt(1.0 - x).
+    EqualPowerCrossfade = 16,
+    /// 0.0 -> 1.0, pow(x, 3.0) -> linear gain to simulate a reasonable mixer channel fader
+    /// response.
+    MixerFaderCurve1 = 17,
+    /// Standard left to right mixer pan.
+    Pan = 18,
+    /// Distance measured in meters.
+    Meters = 19,
+    /// Absolute frequency measurement: if f is freq in hertz then
+    /// absoluteCents = 1200 * log2(f / 440) + 6900.
+    AbsoluteCents = 20,
+    /// Octaves in relative pitch where a value of 1 is equal to 1200 cents.
+    Octaves = 21,
+    /// Beats per minute, ie tempo.
+    Bpm = 22,
+    /// Time relative to tempo, i.e. 1.0 at 120 BPM would equal 1/2 a second.
+    Beats = 23,
+    /// Parameter is expressed in milliseconds.
+    Milliseconds = 24,
+    /// For compression, expansion ratio, etc.
+    Ratio = 25,
+    /// This is the parameter unit type for parameters that present a custom unit name.
+    ///
+    /// The name can be found in the `unit_name` field of the **ParameterInfo**.
+    CustomUnit = 26,
+}
+
+impl ParameterUnit {
+    /// Convert the `AudioUnitParameterUnit` value into a **ParameterUnit**.
+    ///
+    /// Returns `None` if the value is not known.
+    pub fn from_u32(unit: u32) -> Option<Self> {
+        let unit = match unit {
+            0 => ParameterUnit::Generic,
+            1 => ParameterUnit::Indexed,
+            2 => ParameterUnit::Boolean,
+            3 => ParameterUnit::Percent,
+            4 => ParameterUnit::Seconds,
+            5 => ParameterUnit::SampleFrames,
+            6 => ParameterUnit::Phase,
+            7 => ParameterUnit::Rate,
+            8 => ParameterUnit::Hertz,
+            9 => ParameterUnit::Cents,
+            10 => ParameterUnit::RelativeSemiTones,
+            11 => ParameterUnit::MidiNoteNumber,
+            12 => ParameterUnit::MidiController,
+            13 => ParameterUnit::Decibels,
+            14 => ParameterUnit::LinearGain,
+            15 => ParameterUnit::Degrees,
+            16 => ParameterUnit::EqualPowerCrossfade,
+            17 => ParameterUnit::MixerFaderCurve1,
+            18 => ParameterUnit::Pan,
+            19 => ParameterUnit::Meters,
+            20 => ParameterUnit::AbsoluteCents,
+            21 => ParameterUnit::Octaves,
+            22 => ParameterUnit::Bpm,
+            23 => ParameterUnit::Beats,
+            24 => ParameterUnit::Milliseconds,
+            25 => ParameterUnit::Ratio,
+            26 => ParameterUnit::CustomUnit,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+pub mod flags {
+    use sys;
+
+    bitflags! {
+        /// Flags describing how an **AudioUnit** parameter may be used and displayed.
+        pub struct ParameterFlags: u32 {
+            /// The `cfNameString` (and the `unitName`, if present) must be released by the host.
+            ///
+            /// This is handled internally when retrieving a **ParameterInfo**.
+            const CF_NAME_RELEASE = sys::kAudioUnitParameterFlag_CFNameRelease;
+            /// The parameter should not be saved with presets.
+            const OMIT_FROM_PRESETS = sys::kAudioUnitParameterFlag_OmitFromPresets;
+            /// A host should plot the history of this parameter, typically used for meters.
+            const PLOT_HISTORY = sys::kAudioUnitParameterFlag_PlotHistory;
+            /// The parameter is a read-only meter.
+            const METER_READ_ONLY = sys::kAudioUnitParameterFlag_MeterReadOnly;
+            /// The parameter belongs to the clump given by the `clump_id`.
+            const HAS_CLUMP = sys::kAudioUnitParameterFlag_HasClump;
+            /// The values of the parameter can be displayed as strings.
+            const VALUES_HAVE_STRINGS = sys::kAudioUnitParameterFlag_ValuesHaveStrings;
+            /// The parameter should be displayed on a logarithmic scale.
+            const DISPLAY_LOGARITHMIC = sys::kAudioUnitParameterFlag_DisplayLogarithmic;
+            /// The parameter benefits from being set with high resolution.
+            const IS_HIGH_RESOLUTION = sys::kAudioUnitParameterFlag_IsHighResolution;
+            /// Changing the parameter is not real-time safe.
+            const NON_REAL_TIME = sys::kAudioUnitParameterFlag_NonRealTime;
+            /// The parameter supports scheduled ramps.
+            const CAN_RAMP = sys::kAudioUnitParameterFlag_CanRamp;
+            /// The parameter is intended for expert users.
+            const EXPERT_MODE = sys::kAudioUnitParameterFlag_ExpertMode;
+            /// The name of the parameter is given by the `cfNameString` rather than the fixed
+            /// size `name` buffer.
+            const HAS_CF_NAME_STRING = sys::kAudioUnitParameterFlag_HasCFNameString;
+            /// Changing the parameter may change the value of other parameters in all scopes.
+            const IS_GLOBAL_META = sys::kAudioUnitParameterFlag_IsGlobalMeta;
+            /// Changing the parameter may change the value of other parameters in the same element.
+            const IS_ELEMENT_META = sys::kAudioUnitParameterFlag_IsElementMeta;
+            /// The value of the parameter can be read.
+            const IS_READABLE = sys::kAudioUnitParameterFlag_IsReadable;
+            /// The value of the parameter can be written.
+            const IS_WRITABLE = sys::kAudioUnitParameterFlag_IsWritable;
+        }
+    }
+}
+
+/// A rust representation of the `AudioUnitParameterInfo` describing a single parameter.
+#[derive(Clone, Debug)]
+pub struct ParameterInfo {
+    /// The name of the parameter.
+    pub name: String,
+    /// The unit of the parameter's value, or `None` if the unit is not known to coreaudio-rs.
+    pub unit: Option<ParameterUnit>,
+    /// The name of the unit when `unit` is `ParameterUnit::CustomUnit`.
+    pub unit_name: Option<String>,
+    /// The clump that the parameter belongs to, if any.
+    pub clump_id: Option<u32>,
+    /// The minimum value of the parameter.
+    pub min_value: f32,
+    /// The maximum value of the parameter.
+    pub max_value: f32,
+    /// The default value of the parameter.
+    pub default_value: f32,
+    /// Flags describing the parameter.
+    pub flags: ParameterFlags,
+}
+
+impl ParameterInfo {
+    /// Convert an `AudioUnitParameterInfo` into a **ParameterInfo**.
+    ///
+    /// If the info has the `CF_NAME_RELEASE` flag set, the contained `CFStringRef`s are released.
+    ///
+    /// # Safety
+    ///
+    /// The `CFStringRef`s within `info` must be valid as indicated by its flags and must not be
+    /// used after this call if they are released.
+    pub unsafe fn from_sys(info: sys::AudioUnitParameterInfo) -> Self {
+        let flags = ParameterFlags::from_bits_truncate(info.flags);
+        let unit = ParameterUnit::from_u32(info.unit);
+
+        let name =
+            if flags.contains(ParameterFlags::HAS_CF_NAME_STRING) && !info.cfNameString.is_null() {
+                super::cf_string_to_string(info.cfNameString as _)
+            } else {
+                None
+            };
+        let name = name.unwrap_or_else(|| {
+            // The fixed size name is not guaranteed to be null terminated.
+            let bytes: Vec<u8> = info
+                .name
+                .iter()
+                .take_while(|&&c| c != 0)
+                .map(|&c| c as u8)
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        });
+
+        let unit_name = if unit == Some(ParameterUnit::CustomUnit) && !info.unitName.is_null() {
+            super::cf_string_to_string(info.unitName as _)
+        } else {
+            None
+        };
+
+        if flags.contains(ParameterFlags::CF_NAME_RELEASE) {
+            if flags.contains(ParameterFlags::HAS_CF_NAME_STRING) && !info.cfNameString.is_null() {
+                CFRelease(info.cfNameString as *const c_void);
+            }
+            if unit == Some(ParameterUnit::CustomUnit) && !info.unitName.is_null() {
+                CFRelease(info.unitName as *const c_void);
+            }
+        }
+
+        let clump_id = if flags.contains(ParameterFlags::HAS_CLUMP) {
+            Some(info.clumpID)
+        } else {
+            None
+        };
+
+        ParameterInfo {
+            name,
+            unit,
+            unit_name,
+            clump_id,
+            min_value: info.minValue,
+            max_value: info.maxValue,
+            default_value: info.defaultValue,
+            flags,
+        }
+    }
+}
+
+/// A parameter change to be scheduled with
+/// [**schedule_parameters**](../struct.AudioUnit.html#method.schedule_parameters).
+#[derive(Copy, Clone, Debug)]
+pub struct ScheduledParameterEvent {
+    /// The identifier of the parameter.
+    pub id: u32,
+    /// The audio unit scope of the parameter.
+    pub scope: Scope,
+    /// The audio unit element of the parameter.
+    pub element: u32,
+    /// When and how the parameter's value changes.
+    pub value: ScheduledParameterValue,
+}
+
+/// Describes how a **ScheduledParameterEvent** changes the value of its parameter.
+#[derive(Copy, Clone, Debug)]
+pub enum ScheduledParameterValue {
+    /// Change the value of the parameter immediately at the given offset into the next buffer.
+    Immediate {
+        /// The number of frames into the next buffer at which the change takes effect.
+        buffer_offset: u32,
+        /// The new value of the parameter.
+        value: f32,
+    },
+    /// Ramp the value of the parameter from `start_value` to `end_value` over the given number of
+    /// frames.
+    Ramped {
+        /// The number of frames into the next buffer at which the ramp starts. This may be
+        /// negative if the ramp started during a previous buffer.
+        start_buffer_offset: i32,
+        /// The length of the ramp in frames.
+        duration_in_frames: u32,
+        /// The value of the parameter at the start of the ramp.
+        start_value: f32,
+        /// The value of the parameter at the end of the ramp.
+        end_value: f32,
+    },
+}
+
+impl ScheduledParameterEvent {
+    /// Convert the **ScheduledParameterEvent** into an `AudioUnitParameterEvent`.
+    pub fn to_sys(&self) -> sys::AudioUnitParameterEvent {
+        let (event_type, event_values) = match self.value {
+            ScheduledParameterValue::Immediate {
+                buffer_offset,
+                value,
+            } => (
+                sys::kParameterEvent_Immediate,
+                sys::AudioUnitParameterEvent__bindgen_ty_1 {
+                    immediate: sys::AudioUnitParameterEvent__bindgen_ty_1__bindgen_ty_2 {
+                        bufferOffset: buffer_offset,
+                        value,
+                    },
+                },
+            ),
+            ScheduledParameterValue::Ramped {
+                start_buffer_offset,
+                duration_in_frames,
+                start_value,
+                end_value,
+            } => (
+                sys::kParameterEvent_Ramped,
+                sys::AudioUnitParameterEvent__bindgen_ty_1 {
+                    ramp: sys::AudioUnitParameterEvent__bindgen_ty_1__bindgen_ty_1 {
+                        startBufferOffset: start_buffer_offset,
+                        durationInFrames: duration_in_frames,
+                        startValue: start_value,
+                        endValue: end_value,
+                    },
+                },
+            ),
+        };
+        sys::AudioUnitParameterEvent {
+            scope: self.scope as u32,
+            element: self.element,
+            parameter: self.id,
+            eventType: event_type,
+            eventValues: event_values,
+        }
+    }
+}
+
+impl AudioUnit {
+    /// List the identifiers of all parameters available within the given scope.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn parameter_ids(&self, scope: Scope) -> Result<Vec<u32>, Error> {
+        let id = sys::kAudioUnitProperty_ParameterList;
+        super::get_property_array(self.instance, id, scope, 0u32)
+    }
+
+    /// Retrieve information about the parameter with the given identifier within the given scope.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn parameter_info(&self, id: u32, scope: Scope) -> Result<ParameterInfo, Error> {
+        // The parameter ID is passed as the element of the property.
+        let info: sys::AudioUnitParameterInfo =
+            self.get_property(sys::kAudioUnitProperty_ParameterInfo, scope, id)?;
+        Ok(unsafe { ParameterInfo::from_sys(info) })
+    }
+
+    /// The names of the values of an indexed parameter, e.g. the filter types of a filter-type
+    /// selector, where the name at index `i` corresponds to the value `min_value + i`.
+    ///
+    /// Returns `None` if the parameter's unit is not `ParameterUnit::Indexed`.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn parameter_value_strings(
+        &self,
+        id: u32,
+        scope: Scope,
+    ) -> Result<Option<Vec<String>>, Error> {
+        if self.parameter_info(id, scope)?.unit != Some(ParameterUnit::Indexed) {
+            return Ok(None);
+        }
+        // The parameter ID is passed as the element of the property.
+        let array: CFArrayRef =
+            self.get_property(sys::kAudioUnitProperty_ParameterValueStrings, scope, id)?;
+        if array.is_null() {
+            return Ok(None);
+        }
+        unsafe {
+            let count = CFArrayGetCount(array);
+            let mut strings = Vec::with_capacity(count as usize);
+            for i in 0..count {
+                let string = CFArrayGetValueAtIndex(array, i) as CFStringRef;
+                strings.push(super::cf_string_to_string(string).unwrap_or_default());
+            }
+            CFRelease(array as *const c_void);
+            Ok(Some(strings))
+        }
+    }
+
+    /// Get the string that the **AudioUnit** would display for the given value of the parameter,
+    /// e.g. `"-6.0 dB"`.
+    ///
+    /// Only parameters with the `VALUES_HAVE_STRINGS` flag support this.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn parameter_display_string(&self, id: u32, value: f32) -> Result<String, Error> {
+        let mut string_from_value = sys::AudioUnitParameterStringFromValue {
+            inParamID: id,
+            inValue: &value as *const _,
+            outString: ptr::null(),
+        };
+        let mut size = mem::size_of::<sys::AudioUnitParameterStringFromValue>() as u32;
+        unsafe {
+            Error::from_os_status(sys::AudioUnitGetProperty(
+                self.instance,
+                sys::kAudioUnitProperty_ParameterStringFromValue,
+                Scope::Global as c_uint,
+                0,
+                &mut string_from_value as *mut _ as *mut c_void,
+                &mut size as *mut _,
+            ))?;
+            let out_string = string_from_value.outString as CFStringRef;
+            if out_string.is_null() {
+                return Err(Error::Unspecified);
+            }
+            // The returned string is owned by us, so release it once it has been copied.
+            let string = super::cf_string_to_string(out_string);
+            CFRelease(out_string as *const c_void);
+            string.ok_or(Error::Unspecified)
+        }
+    }
+
+    /// Parse the given string into a value for the parameter the way the **AudioUnit** intends,
+    /// e.g. `"-6.0 dB"` into `-6.0`.
+    ///
+    /// Only parameters with the `VALUES_HAVE_STRINGS` flag support this.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn parameter_value_from_string(&self, id: u32, string: &str) -> Result<f32, Error> {
+        let cf_string = super::cf_string_from_str(string)?;
+        let mut value_from_string = sys::AudioUnitParameterValueFromString {
+            inParamID: id,
+            inString: cf_string as _,
+            outValue: 0.0,
+        };
+        let mut size = mem::size_of::<sys::AudioUnitParameterValueFromString>() as u32;
+        unsafe {
+            let status = sys::AudioUnitGetProperty(
+                self.instance,
+                sys::kAudioUnitProperty_ParameterValueFromString,
+                Scope::Global as c_uint,
+                0,
+                &mut value_from_string as *mut _ as *mut c_void,
+                &mut size as *mut _,
+            );
+            CFRelease(cf_string as *const c_void);
+            Error::from_os_status(status)?;
+        }
+        Ok(value_from_string.outValue)
+    }
+
+    /// Set a global parameter after checking the value against the range given by the
+    /// parameter's info, returning `Error::ParameterValueOutOfRange` if it lies outside of it.
+    pub(crate) fn set_global_parameter_checked(
+        &mut self,
+        id: u32,
+        value: f32,
+    ) -> Result<(), Error> {
+        let info = self.parameter_info(id, Scope::Global)?;
+        if !(info.min_value..=info.max_value).contains(&value) {
+            return Err(Error::ParameterValueOutOfRange);
+        }
+        self.set_parameter(id, Scope::Global, Element::Output, value, 0)
+    }
+
+    /// Schedule sample-accurate changes to parameters for the next render cycle.
+    ///
+    /// Ramped events should be scheduled from the render context (e.g. a render notification or
+    /// render callback) so that their buffer offsets refer to the buffer about to be rendered.
+    /// Nothing is allocated or read from the unit here, so this is safe to call on the render
+    /// thread. Instead, the unit's maximum frames per slice is cached when the **AudioUnit** is
+    /// created or initialized and whenever the property is set through it, e.g. via
+    /// [**set_maximum_frames_per_slice**](./struct.AudioUnit.html#method.set_maximum_frames_per_slice).
+    ///
+    /// Returns `Error::BufferOffsetOutOfRange` if an event starts beyond the unit's maximum frames
+    /// per slice, or `Error::MaximumFramesPerSliceUnknown` if the property could not be read when
+    /// it was cached. In either case no events are scheduled.
+    ///
+    /// **Available** in OS X v10.2 and later.
+    pub fn schedule_parameters(&mut self, events: &[ScheduledParameterEvent]) -> Result<(), Error> {
+        // The number of events converted on the stack and handed to the unit at once.
+        const CHUNK_LEN: usize = 16;
+
+        let max_frames = self
+            .max_frames_per_slice
+            .ok_or(Error::MaximumFramesPerSliceUnknown)?;
+        let out_of_range = events.iter().any(|event| match event.value {
+            ScheduledParameterValue::Immediate { buffer_offset, .. } => buffer_offset >= max_frames,
+            ScheduledParameterValue::Ramped {
+                start_buffer_offset,
+                ..
+            } => start_buffer_offset >= max_frames as i32,
+        });
+        if out_of_range {
+            return Err(Error::BufferOffsetOutOfRange);
+        }
+
+        let mut buffer: [sys::AudioUnitParameterEvent; CHUNK_LEN] = unsafe { mem::zeroed() };
+        for chunk in events.chunks(CHUNK_LEN) {
+            for (sys_event, event) in buffer.iter_mut().zip(chunk) {
+                *sys_event = event.to_sys();
+            }
+            unsafe {
+                Error::from_os_status(sys::AudioUnitScheduleParameters(
+                    self.instance,
+                    buffer.as_ptr(),
+                    chunk.len() as u32,
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Observe the value of a parameter that the **AudioUnit** may change internally, e.g. a
+    /// parameter modulated by an LFO.
+    ///
+    /// After the unit renders, the value of the parameter is polled at most once every 1024
+    /// frames and `f` is called with the new value whenever it differs from the last value
+    /// reported. `f` is called once with the initial value after the first render.
+    ///
+    /// Note that `f` is called on the render thread, so it must not block or allocate. Sending
+    /// the value to another thread via a lock-free queue or atomic is recommended.
+    ///
+    /// The observer remains registered until the **AudioUnit** is dropped.
+    pub fn add_parameter_observer<F>(
+        &mut self,
+        id: u32,
+        scope: Scope,
+        element: u32,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(f32) + Send + 'static,
+    {
+        const POLL_INTERVAL_FRAMES: u32 = 1024;
+        let instance = self.instance;
+        let mut last_value: Option<f32> = None;
+        // Start "due" so that the initial value is reported after the first render.
+        let mut frames_since_poll = POLL_INTERVAL_FRAMES;
+        self.add_render_notify_fn(
+            move |io_action_flags: *mut sys::AudioUnitRenderActionFlags,
+                  _in_time_stamp: *const sys::AudioTimeStamp,
+                  _in_bus_number: sys::UInt32,
+                  in_number_frames: sys::UInt32,
+                  _io_data: *mut sys::AudioBufferList|
+                  -> sys::OSStatus {
+                let flags = unsafe { *io_action_flags };
+                if flags & sys::kAudioUnitRenderAction_PostRender == 0 {
+                    return 0;
+                }
+                frames_since_poll = frames_since_poll.saturating_add(in_number_frames);
+                if frames_since_poll < POLL_INTERVAL_FRAMES {
+                    return 0;
+                }
+                frames_since_poll = 0;
+                let mut value: sys::AudioUnitParameterValue = 0.0;
+                let status = unsafe {
+                    sys::AudioUnitGetParameter(
+                        instance,
+                        id,
+                        scope as c_uint,
+                        element,
+                        &mut value as *mut _,
+                    )
+                };
+                // Failing to read the parameter should not disrupt rendering.
+                if status == 0 && last_value != Some(value) {
+                    last_value = Some(value);
+                    f(value);
+                }
+                0
+            },
+        )
+    }
+}
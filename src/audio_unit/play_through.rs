@@ -0,0 +1,66 @@
+//! Playing the output of an **AudioUnit** through the default output device.
+//!
+//! This is mostly useful for auditioning instrument and generator units without setting up a
+//! processing graph.
+
+use super::{AudioUnit, Element, IOType, Scope};
+use crate::error::Error;
+use sys;
+
+/// An **AudioUnit** whose output is being played through the default output device, as returned
+/// by [**AudioUnit::play_through_default_output**](../struct.AudioUnit.html#method.play_through_default_output).
+///
+/// Dropping the **PlayingUnit** stops and disposes both the unit and the output unit.
+pub struct PlayingUnit {
+    // Declared first so that the output stops pulling from the unit before it is disposed.
+    output: AudioUnit,
+    unit: AudioUnit,
+}
+
+impl PlayingUnit {
+    /// The unit being played, e.g. for sending it MIDI or changing its parameters.
+    pub fn unit(&self) -> &AudioUnit {
+        &self.unit
+    }
+
+    /// The unit being played, e.g. for sending it MIDI or changing its parameters.
+    pub fn unit_mut(&mut self) -> &mut AudioUnit {
+        &mut self.unit
+    }
+
+    /// The default output unit through which the unit is played.
+    pub fn output(&self) -> &AudioUnit {
+        &self.output
+    }
+}
+
+impl AudioUnit {
+    /// Connect the first output of the **AudioUnit** to a new default output unit and start
+    /// playing it, e.g. to audition an instrument or generator unit.
+    ///
+    /// The default output unit is configured with the stream format of the **AudioUnit**'s
+    /// output, so the **AudioUnit** should be fully configured and initialized beforehand.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn play_through_default_output(self) -> Result<PlayingUnit, Error> {
+        let mut output = AudioUnit::new(IOType::DefaultOutput)?;
+        output.uninitialize()?;
+
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd: sys::AudioStreamBasicDescription =
+            self.get_property(id, Scope::Output, Element::Output)?;
+        output.set_property(id, Scope::Input, Element::Output, Some(&asbd))?;
+
+        let connection = sys::AudioUnitConnection {
+            sourceAudioUnit: self.instance,
+            sourceOutputNumber: 0,
+            destInputNumber: 0,
+        };
+        let id = sys::kAudioUnitProperty_MakeConnection;
+        output.set_property(id, Scope::Input, Element::Output, Some(&connection))?;
+
+        output.initialize()?;
+        output.start()?;
+        Ok(PlayingUnit { output, unit: self })
+    }
+}
@@ -0,0 +1,137 @@
+//! Probing which of the well-known properties an **AudioUnit** supports.
+//!
+//! Core Audio offers no way of enumerating the properties of a unit, so instead each selector in
+//! a table of known properties is queried via `AudioUnitGetPropertyInfo`. This is mostly useful
+//! for debugging and for building generic inspectors.
+
+use super::{AudioUnit, Scope};
+use sys;
+
+/// A property that an **AudioUnit** responds to within a scope, as found by
+/// [**AudioUnit::supported_properties**](../struct.AudioUnit.html#method.supported_properties).
+#[derive(Copy, Clone, Debug)]
+pub struct SupportedProperty {
+    /// The property id, e.g. `sys::kAudioUnitProperty_StreamFormat`.
+    pub id: u32,
+    /// The name of the property, e.g. `"StreamFormat"`.
+    pub name: &'static str,
+    /// The scope in which the unit responds to the property.
+    pub scope: Scope,
+    /// The current size of the property's value in bytes.
+    pub size: u32,
+    /// Whether the property may be set.
+    pub writable: bool,
+}
+
+/// The properties probed by `supported_properties`, along with their names.
+///
+/// Properties whose element is a parameter id or similar, rather than a bus, are left out as
+/// they can't be probed generically.
+const KNOWN_PROPERTIES: &[(u32, &str)] = &[
+    (sys::kAudioUnitProperty_ClassInfo, "ClassInfo"),
+    (sys::kAudioUnitProperty_MakeConnection, "MakeConnection"),
+    (sys::kAudioUnitProperty_SampleRate, "SampleRate"),
+    (sys::kAudioUnitProperty_ParameterList, "ParameterList"),
+    (sys::kAudioUnitProperty_CPULoad, "CPULoad"),
+    (sys::kAudioUnitProperty_StreamFormat, "StreamFormat"),
+    (sys::kAudioUnitProperty_ElementCount, "ElementCount"),
+    (sys::kAudioUnitProperty_Latency, "Latency"),
+    (
+        sys::kAudioUnitProperty_SupportedNumChannels,
+        "SupportedNumChannels",
+    ),
+    (
+        sys::kAudioUnitProperty_MaximumFramesPerSlice,
+        "MaximumFramesPerSlice",
+    ),
+    (
+        sys::kAudioUnitProperty_AudioChannelLayout,
+        "AudioChannelLayout",
+    ),
+    (sys::kAudioUnitProperty_TailTime, "TailTime"),
+    (sys::kAudioUnitProperty_BypassEffect, "BypassEffect"),
+    (sys::kAudioUnitProperty_LastRenderError, "LastRenderError"),
+    (
+        sys::kAudioUnitProperty_SetRenderCallback,
+        "SetRenderCallback",
+    ),
+    (sys::kAudioUnitProperty_FactoryPresets, "FactoryPresets"),
+    (sys::kAudioUnitProperty_ContextName, "ContextName"),
+    (sys::kAudioUnitProperty_RenderQuality, "RenderQuality"),
+    (sys::kAudioUnitProperty_HostCallbacks, "HostCallbacks"),
+    (
+        sys::kAudioUnitProperty_InPlaceProcessing,
+        "InPlaceProcessing",
+    ),
+    (sys::kAudioUnitProperty_ElementName, "ElementName"),
+    (
+        sys::kAudioUnitProperty_SupportedChannelLayoutTags,
+        "SupportedChannelLayoutTags",
+    ),
+    (sys::kAudioUnitProperty_PresentPreset, "PresentPreset"),
+    (sys::kAudioUnitProperty_OfflineRender, "OfflineRender"),
+    (
+        sys::kAudioUnitProperty_PresentationLatency,
+        "PresentationLatency",
+    ),
+    (
+        sys::kAudioUnitProperty_MIDIOutputCallbackInfo,
+        "MIDIOutputCallbackInfo",
+    ),
+    (
+        sys::kAudioUnitProperty_MIDIOutputCallback,
+        "MIDIOutputCallback",
+    ),
+    (
+        sys::kAudioUnitProperty_ShouldAllocateBuffer,
+        "ShouldAllocateBuffer",
+    ),
+    (sys::kAudioUnitProperty_NickName, "NickName"),
+    (sys::kAudioOutputUnitProperty_ChannelMap, "ChannelMap"),
+    (sys::kAudioOutputUnitProperty_EnableIO, "EnableIO"),
+    (sys::kAudioOutputUnitProperty_StartTime, "StartTime"),
+    (
+        sys::kAudioOutputUnitProperty_SetInputCallback,
+        "SetInputCallback",
+    ),
+    (sys::kAudioOutputUnitProperty_HasIO, "HasIO"),
+];
+
+impl AudioUnit {
+    /// Probe which of the well-known properties the **AudioUnit** responds to, along with their
+    /// sizes and whether they are writable.
+    ///
+    /// Each property is probed on element `0` of the global, input and output scopes, and is
+    /// listed once per scope in which the unit responds to it. Properties the crate doesn't know
+    /// of, e.g. those specific to a third-party unit, are not found.
+    pub fn supported_properties(&self) -> Vec<SupportedProperty> {
+        let scopes = [Scope::Global, Scope::Input, Scope::Output];
+        let mut supported = Vec::new();
+        for &(id, name) in KNOWN_PROPERTIES {
+            for &scope in &scopes {
+                let mut size = 0u32;
+                let mut writable: sys::Boolean = 0;
+                let status = unsafe {
+                    sys::AudioUnitGetPropertyInfo(
+                        self.instance,
+                        id,
+                        scope as u32,
+                        0,
+                        &mut size as *mut _,
+                        &mut writable as *mut _,
+                    )
+                };
+                if status == 0 {
+                    supported.push(SupportedProperty {
+                        id,
+                        name,
+                        scope,
+                        size,
+                        writable: writable != 0,
+                    });
+                }
+            }
+        }
+        supported
+    }
+}
@@ -1,10 +1,15 @@
 use super::audio_format::LinearPcmFlags;
+use super::command_queue::CommandQueue;
 use super::{AudioUnit, Element, Scope};
 use crate::error::{self, Error};
-use std::cmp::Ordering;
 use std::mem;
 use std::os::raw::c_void;
+use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 use sys;
 
 pub use self::action_flags::ActionFlags;
@@ -26,6 +31,295 @@ pub type InputProcFn = dyn FnMut(
 /// This type allows us to safely wrap a boxed `RenderCallback` to use within the input proc.
 pub struct InputProcFnWrapper {
     callback: Box<InputProcFn>,
+    // The layout of the buffers handed to the callback, if it is a render or input callback.
+    buffer_descriptor: Option<BufferDescriptor>,
+}
+
+impl InputProcFnWrapper {
+    /// Wrap a closure taking the raw arguments of an input proc, e.g. a render notification, to be
+    /// registered along with `input_proc`.
+    pub(crate) fn new_raw<F>(f: F) -> Box<Self>
+    where
+        F: FnMut(
+                *mut sys::AudioUnitRenderActionFlags,
+                *const sys::AudioTimeStamp,
+                sys::UInt32,
+                sys::UInt32,
+                *mut sys::AudioBufferList,
+            ) -> sys::OSStatus
+            + 'static,
+    {
+        Box::new(InputProcFnWrapper {
+            callback: Box::new(f),
+            buffer_descriptor: None,
+        })
+    }
+
+    /// Wrap a typed render callback for buffers of the given stream format, to be registered
+    /// along with `input_proc` by something other than an **AudioUnit**, e.g. a graph node.
+    ///
+    /// Render cycles without buffers, or whose buffers don't match the layout of `D`, output
+    /// silence instead of calling `f`.
+    pub(crate) fn new_render<F, D>(mut f: F, stream_format: &super::StreamFormat) -> Box<Self>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        let render_fn = move |io_action_flags: *mut sys::AudioUnitRenderActionFlags,
+                              in_time_stamp: *const sys::AudioTimeStamp,
+                              in_bus_number: sys::UInt32,
+                              in_number_frames: sys::UInt32,
+                              io_data: *mut sys::AudioBufferList|
+              -> sys::OSStatus {
+            if io_data.is_null() || unsafe { (*io_data).mNumberBuffers } == 0 {
+                unsafe { output_silence(io_action_flags, io_data) };
+                return 0;
+            }
+            let data = match unsafe { D::try_from_input_proc_args(in_number_frames, io_data) } {
+                Ok(data) => data,
+                Err(err) => {
+                    unsafe { output_silence(io_action_flags, io_data) };
+                    return err.as_os_status();
+                }
+            };
+            let args = unsafe {
+                Args {
+                    data,
+                    time_stamp: *in_time_stamp,
+                    flags: action_flags::Handle::from_ptr(io_action_flags),
+                    bus_number: in_bus_number,
+                    num_frames: in_number_frames as usize,
+                }
+            };
+            match f(args) {
+                Ok(()) => 0,
+                Err(()) => error::Error::Unspecified.as_os_status(),
+            }
+        };
+        Box::new(InputProcFnWrapper {
+            callback: Box::new(render_fn),
+            buffer_descriptor: Some(BufferDescriptor::from_stream_format(stream_format)),
+        })
+    }
+}
+
+/// A description of the layout of the buffers handed to a callback, as configured when the
+/// callback was installed.
+///
+/// See [**AudioUnit::render_callback_format**](../struct.AudioUnit.html#method.render_callback_format).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BufferDescriptor {
+    /// The size of each sample in bytes.
+    pub sample_size: usize,
+    /// Whether the channels are interleaved within a single buffer, rather than each channel
+    /// having a buffer of its own.
+    pub interleaved: bool,
+    /// The number of channels.
+    pub channels: u32,
+}
+
+impl BufferDescriptor {
+    fn from_stream_format(stream_format: &super::StreamFormat) -> Self {
+        BufferDescriptor {
+            sample_size: stream_format.sample_format.size_in_bytes(),
+            interleaved: !stream_format
+                .flags
+                .contains(LinearPcmFlags::IS_NON_INTERLEAVED),
+            channels: stream_format.channels,
+        }
+    }
+}
+
+/// Watches the stream format of an **AudioUnit** on behalf of an installed callback, flagging
+/// when the format no longer matches the callback's buffer type.
+pub struct FormatListener {
+    scope: Scope,
+    element: u32,
+    does_stream_format_match: fn(&super::StreamFormat) -> bool,
+    // Shared with the callback, which stops calling the user's closure while this is `true`.
+    format_changed: Arc<AtomicBool>,
+    // The buffers of an input callback, reallocated for each new stream format.
+    input_buffers: Option<Arc<InputBufferExchange>>,
+}
+
+/// An `AudioBufferList` along with the buffers that it points to, allocated up front so that
+/// audio can be rendered into it without allocating on the render thread.
+struct AudioBufferListOwned {
+    // The samples of each buffer.
+    data: Vec<Vec<u8>>,
+    // Storage for the variable length `AudioBufferList` pointing into `data`.
+    list: Vec<u64>,
+    // The number of bytes per frame within each buffer.
+    frame_bytes: usize,
+    // The number of frames that each buffer can hold.
+    max_frames: usize,
+}
+
+impl AudioBufferListOwned {
+    /// Allocate buffers holding `max_frames` frames of audio in the given format.
+    fn new(max_frames: usize, stream_format: &super::StreamFormat) -> Self {
+        let channels = stream_format.channels as usize;
+        let non_interleaved = stream_format
+            .flags
+            .contains(LinearPcmFlags::IS_NON_INTERLEAVED);
+        let (num_buffers, channels_per_buffer, frame_bytes) = if non_interleaved {
+            (channels, 1, stream_format.bytes_per_channel(1))
+        } else {
+            (1, channels, stream_format.byte_size(1))
+        };
+        let mut data = vec![vec![0u8; max_frames * frame_bytes]; num_buffers];
+
+        let list_bytes = mem::size_of::<sys::AudioBufferList>()
+            + num_buffers.saturating_sub(1) * mem::size_of::<sys::AudioBuffer>();
+        let mut list = vec![0u64; list_bytes / mem::size_of::<u64>() + 1];
+        unsafe {
+            let list = list.as_mut_ptr() as *mut sys::AudioBufferList;
+            (*list).mNumberBuffers = num_buffers as u32;
+            let list_buffers = (*list).mBuffers.as_mut_ptr();
+            for (i, buffer) in data.iter_mut().enumerate() {
+                *list_buffers.add(i) = sys::AudioBuffer {
+                    mNumberChannels: channels_per_buffer as u32,
+                    mDataByteSize: buffer.len() as u32,
+                    mData: buffer.as_mut_ptr() as *mut c_void,
+                };
+            }
+        }
+
+        AudioBufferListOwned {
+            data,
+            list,
+            frame_bytes,
+            max_frames,
+        }
+    }
+
+    /// The `AudioBufferList` describing the buffers.
+    fn as_mut_ptr(&mut self) -> *mut sys::AudioBufferList {
+        self.list.as_mut_ptr() as *mut sys::AudioBufferList
+    }
+
+    /// Set the size of each buffer in the list to `frames` frames, as expected by
+    /// `AudioUnitRender`.
+    ///
+    /// Returns `false` without changing anything if the buffers cannot hold that many frames.
+    fn set_frames(&mut self, frames: usize) -> bool {
+        if frames > self.max_frames {
+            return false;
+        }
+        let byte_size = (frames * self.frame_bytes) as u32;
+        unsafe {
+            let list = self.as_mut_ptr();
+            let len = (*list).mNumberBuffers as usize;
+            let buffers = slice::from_raw_parts_mut((*list).mBuffers.as_mut_ptr(), len);
+            for buffer in buffers {
+                buffer.mDataByteSize = byte_size;
+            }
+        }
+        true
+    }
+}
+
+/// Hands the buffers of an input callback, reallocated by its format listener, over to the
+/// render thread without allocating or freeing memory on the render thread.
+#[derive(Default)]
+struct InputBufferExchange {
+    // Buffers for the latest stream format that the render thread has not yet picked up.
+    pending: AtomicPtr<AudioBufferListOwned>,
+    // Buffers replaced by the render thread, waiting to be freed by the format listener.
+    retired: AtomicPtr<AudioBufferListOwned>,
+}
+
+impl InputBufferExchange {
+    /// Offer buffers for a new stream format to the render thread, replacing any buffers that
+    /// were offered but not yet picked up.
+    fn offer(&self, buffers: AudioBufferListOwned) {
+        unsafe {
+            free_buffers(self.retired.swap(ptr::null_mut(), AtomicOrdering::AcqRel));
+            let buffers = Box::into_raw(Box::new(buffers));
+            free_buffers(self.pending.swap(buffers, AtomicOrdering::AcqRel));
+        }
+    }
+
+    /// Called on the render thread to swap the `current` buffers for any pending ones.
+    ///
+    /// The pending buffers are only taken once the previously retired buffers have been freed,
+    /// so that the replaced buffers never need to be freed here.
+    fn exchange(&self, current: &mut Box<AudioBufferListOwned>) {
+        if !self.retired.load(AtomicOrdering::Acquire).is_null() {
+            return;
+        }
+        let pending = self.pending.swap(ptr::null_mut(), AtomicOrdering::AcqRel);
+        if pending.is_null() {
+            return;
+        }
+        let retired = mem::replace(current, unsafe { Box::from_raw(pending) });
+        self.retired
+            .store(Box::into_raw(retired), AtomicOrdering::Release);
+    }
+}
+
+impl Drop for InputBufferExchange {
+    fn drop(&mut self) {
+        unsafe {
+            free_buffers(*self.pending.get_mut());
+            free_buffers(*self.retired.get_mut());
+        }
+    }
+}
+
+/// Free buffers that were handed over via an **InputBufferExchange**, if any.
+unsafe fn free_buffers(buffers: *mut AudioBufferListOwned) {
+    if !buffers.is_null() {
+        let _: Box<AudioBufferListOwned> = Box::from_raw(buffers);
+    }
+}
+
+/// The buffers holding a single block rendered by a fixed block size render callback.
+struct FixedBlock {
+    buffers: AudioBufferListOwned,
+    // The number of frames of the block that have already been handed to the audio unit.
+    position: usize,
+}
+
+impl FixedBlock {
+    fn new(block_size: usize, stream_format: &super::StreamFormat) -> Self {
+        FixedBlock {
+            buffers: AudioBufferListOwned::new(block_size, stream_format),
+            // Start with an exhausted block so that the first block is rendered immediately.
+            position: block_size,
+        }
+    }
+}
+
+/// How `AudioUnitRender` is retried when it fails with
+/// `kAudioUnitErr_CannotDoInCurrentContext`, i.e. `AudioUnitError::CannotDoInCurrentContext`.
+///
+/// Core Audio returns this error when the requested work cannot be done right now but may
+/// succeed if tried again later in the same render cycle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times `AudioUnitRender` is called per render cycle, including the
+    /// first attempt. Values of `0` and `1` both mean no retries.
+    pub max_attempts: u32,
+    /// Whether to issue a spin loop hint between attempts. Otherwise attempts are made back to
+    /// back. The render thread is never put to sleep.
+    pub spin: bool,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries, treating every error as fatal for the render cycle.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            spin: false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
 }
 
 /// Arguments given to the render callback function.
@@ -48,11 +342,25 @@ pub struct Args<D> {
     pub flags: action_flags::Handle,
 }
 
+impl<D> Args<D>
+where
+    D: data::ChannelCount,
+{
+    /// The number of channels in the buffers actually delivered to the callback.
+    ///
+    /// This is read from the `AudioBufferList` rather than the stream format, so generic callbacks
+    /// may use it to adapt to the delivered layout.
+    pub fn channel_count(&self) -> usize {
+        self.data.channel_count()
+    }
+}
+
 /// Format specific render callback data.
 pub mod data {
     use super::super::Sample;
     use super::super::StreamFormat;
     use crate::audio_unit::audio_format::LinearPcmFlags;
+    use crate::error::Error;
     use std::marker::PhantomData;
     use std::slice;
     use sys;
@@ -66,6 +374,58 @@ pub mod data {
         /// TODO document how to use this function safely.
         unsafe fn from_input_proc_args(num_frames: u32, io_data: *mut sys::AudioBufferList)
             -> Self;
+        /// Construct Self from arguments given to the `input_proc`, first checking that the
+        /// layout of the buffers is valid for this type of data.
+        ///
+        /// Returns `Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat` if it is
+        /// not. By default no checks are performed.
+        /// # Safety
+        /// The same as for `from_input_proc_args`.
+        unsafe fn try_from_input_proc_args(
+            num_frames: u32,
+            io_data: *mut sys::AudioBufferList,
+        ) -> Result<Self, Error>
+        where
+            Self: Sized,
+        {
+            Ok(Self::from_input_proc_args(num_frames, io_data))
+        }
+    }
+
+    /// Data types that know the number of channels in the `AudioBufferList` they wrap.
+    ///
+    /// Kept apart from **Data** so that existing implementations of **Data** don't need to
+    /// implement it.
+    pub trait ChannelCount {
+        /// The number of channels in the underlying `AudioBufferList`.
+        fn channel_count(&self) -> usize;
+    }
+
+    /// Check that the sample type `S` matches both the bit depth and the flags (including the
+    /// endianness) of the stream format.
+    fn does_sample_format_match<S: Sample>(stream_format: &StreamFormat) -> bool {
+        let sample_format = S::sample_format();
+        sample_format == stream_format.sample_format
+            && sample_format.does_match_flags(stream_format.flags)
+    }
+
+    /// Whether the buffer list consists of a single buffer holding exactly `frames` interleaved
+    /// frames of `S`, as expected by the **Interleaved** data types.
+    #[allow(non_snake_case)]
+    unsafe fn interleaved_buffer_size_matches<S>(
+        frames: u32,
+        io_data: *mut sys::AudioBufferList,
+    ) -> bool {
+        if io_data.is_null() || (*io_data).mNumberBuffers == 0 {
+            return false;
+        }
+        let sys::AudioBuffer {
+            mNumberChannels,
+            mDataByteSize,
+            mData,
+        } = (*io_data).mBuffers[0];
+        let expected_size = std::mem::size_of::<S>() * frames as usize * mNumberChannels as usize;
+        mDataByteSize as usize == expected_size && (!mData.is_null() || expected_size == 0)
     }
 
     /// A raw pointer to the audio data so that the user may handle it themselves.
@@ -86,6 +446,100 @@ pub mod data {
         }
     }
 
+    impl ChannelCount for Raw {
+        /// The sum of the channels of each buffer in the list.
+        fn channel_count(&self) -> usize {
+            self.iter().map(|(_, channels)| channels as usize).sum()
+        }
+    }
+
+    /// An iterator produced by a `Raw`, yielding the bytes and channel count of each buffer.
+    pub struct Buffers<'a> {
+        buffers: slice::Iter<'a, sys::AudioBuffer>,
+    }
+
+    /// An iterator produced by a `Raw`, yielding the mutable bytes and channel count of each
+    /// buffer.
+    pub struct BuffersMut<'a> {
+        buffers: slice::IterMut<'a, sys::AudioBuffer>,
+    }
+
+    impl<'a> Iterator for Buffers<'a> {
+        type Item = (&'a [u8], u32);
+        #[allow(non_snake_case)]
+        fn next(&mut self) -> Option<Self::Item> {
+            self.buffers.next().map(
+                |&sys::AudioBuffer {
+                     mNumberChannels,
+                     mDataByteSize,
+                     mData,
+                 }| {
+                    let bytes: &[u8] = if mData.is_null() {
+                        &[]
+                    } else {
+                        unsafe { slice::from_raw_parts(mData as *const u8, mDataByteSize as usize) }
+                    };
+                    (bytes, mNumberChannels)
+                },
+            )
+        }
+    }
+
+    impl<'a> Iterator for BuffersMut<'a> {
+        type Item = (&'a mut [u8], u32);
+        #[allow(non_snake_case)]
+        fn next(&mut self) -> Option<Self::Item> {
+            self.buffers.next().map(
+                |&mut sys::AudioBuffer {
+                     mNumberChannels,
+                     mDataByteSize,
+                     mData,
+                 }| {
+                    let bytes: &mut [u8] = if mData.is_null() {
+                        &mut []
+                    } else {
+                        unsafe {
+                            slice::from_raw_parts_mut(mData as *mut u8, mDataByteSize as usize)
+                        }
+                    };
+                    (bytes, mNumberChannels)
+                },
+            )
+        }
+    }
+
+    impl Raw {
+        /// An iterator yielding the raw bytes and the number of interleaved channels of each
+        /// `AudioBuffer` in the list.
+        ///
+        /// The length of each byte slice is given by the buffer's `mDataByteSize`.
+        pub fn iter(&self) -> Buffers<'_> {
+            Buffers {
+                buffers: unsafe { self.audio_buffers() }.iter(),
+            }
+        }
+
+        /// An iterator yielding the mutable raw bytes and the number of interleaved channels of
+        /// each `AudioBuffer` in the list.
+        ///
+        /// The length of each byte slice is given by the buffer's `mDataByteSize`.
+        pub fn iter_mut(&mut self) -> BuffersMut<'_> {
+            BuffersMut {
+                buffers: unsafe { self.audio_buffers() }.iter_mut(),
+            }
+        }
+
+        // The `AudioBuffer`s within the list, or an empty slice if there is no list.
+        unsafe fn audio_buffers<'a>(&self) -> &'a mut [sys::AudioBuffer] {
+            if self.data.is_null() {
+                return &mut [];
+            }
+            let ptr = (*self.data).mBuffers.as_ptr() as *mut sys::AudioBuffer;
+            let len = (*self.data).mNumberBuffers as usize;
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
     /// An interleaved linear PCM buffer with samples of type `S`.
     pub struct Interleaved<S: 'static> {
         /// The audio buffer.
@@ -131,17 +585,12 @@ pub mod data {
         type Item = &'a [S];
         #[allow(non_snake_case)]
         fn next(&mut self) -> Option<Self::Item> {
-            self.buffers.next().map(
-                |&sys::AudioBuffer {
-                     mNumberChannels,
-                     mData,
-                     ..
-                 }| {
-                    let len = mNumberChannels as usize * self.frames;
-                    let ptr = mData as *mut S;
-                    unsafe { slice::from_raw_parts(ptr, len) }
-                },
-            )
+            self.buffers.next().map(|&sys::AudioBuffer { mData, .. }| {
+                // Each non-interleaved buffer holds a single channel, as checked on
+                // construction.
+                let ptr = mData as *mut S;
+                unsafe { slice::from_raw_parts(ptr, self.frames) }
+            })
         }
     }
 
@@ -149,17 +598,12 @@ pub mod data {
         type Item = &'a mut [S];
         #[allow(non_snake_case)]
         fn next(&mut self) -> Option<Self::Item> {
-            self.buffers.next().map(
-                |&mut sys::AudioBuffer {
-                     mNumberChannels,
-                     mData,
-                     ..
-                 }| {
-                    let len = mNumberChannels as usize * self.frames;
+            self.buffers
+                .next()
+                .map(|&mut sys::AudioBuffer { mData, .. }| {
                     let ptr = mData as *mut S;
-                    unsafe { slice::from_raw_parts_mut(ptr, len) }
-                },
-            )
+                    unsafe { slice::from_raw_parts_mut(ptr, self.frames) }
+                })
         }
     }
 
@@ -192,7 +636,7 @@ pub mod data {
             stream_format
                 .flags
                 .contains(LinearPcmFlags::IS_NON_INTERLEAVED)
-                && S::sample_format().does_match_flags(stream_format.flags)
+                && does_sample_format_match::<S>(stream_format)
         }
 
         #[allow(non_snake_case)]
@@ -206,6 +650,60 @@ pub mod data {
                 sample_format: PhantomData,
             }
         }
+
+        /// Checks that each buffer holds exactly one channel of at least `frames` samples, so
+        /// that the channel slices can't read past the end of a buffer.
+        unsafe fn try_from_input_proc_args(
+            frames: u32,
+            io_data: *mut sys::AudioBufferList,
+        ) -> Result<Self, Error> {
+            let data = Self::from_input_proc_args(frames, io_data);
+            let channel_bytes = data.frames * std::mem::size_of::<S>();
+            let valid = data.buffers.iter().all(|buffer| {
+                buffer.mNumberChannels == 1
+                    && buffer.mDataByteSize as usize >= channel_bytes
+                    && (!buffer.mData.is_null() || channel_bytes == 0)
+            });
+            if !valid {
+                return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+            }
+            Ok(data)
+        }
+    }
+
+    impl<S> ChannelCount for NonInterleaved<S> {
+        /// The number of buffers in the list, one per channel.
+        fn channel_count(&self) -> usize {
+            self.buffers.len()
+        }
+    }
+
+    impl<S: Copy> Interleaved<S> {
+        /// Fill every channel of the buffer with the given `mono` samples, e.g. to output the
+        /// signal of a mono generator on a stereo device.
+        ///
+        /// `mono` must hold exactly one sample per frame, otherwise
+        /// `Error::BufferLengthMismatch` is returned and the buffer is left untouched.
+        pub fn fill_from_mono(&mut self, mono: &[S]) -> Result<(), Error> {
+            if mono.len() * self.channels != self.buffer.len() {
+                return Err(Error::BufferLengthMismatch);
+            }
+            for (i, sample) in self.buffer.iter_mut().enumerate() {
+                *sample = mono[i / self.channels];
+            }
+            Ok(())
+        }
+
+        /// Fill the buffer by interleaving the given channel `planes`.
+        ///
+        /// There must be one plane per channel, each holding exactly one sample per frame,
+        /// otherwise `Error::BufferLengthMismatch` is returned and the buffer is left untouched.
+        pub fn fill_from_planes(&mut self, planes: &[&[S]]) -> Result<(), Error> {
+            if planes.len() != self.channels {
+                return Err(Error::BufferLengthMismatch);
+            }
+            crate::util::interleave(planes, self.buffer)
+        }
     }
 
     // Implementation for an interleaved linear PCM audio format.
@@ -217,7 +715,7 @@ pub mod data {
             !stream_format
                 .flags
                 .contains(LinearPcmFlags::IS_NON_INTERLEAVED)
-                && S::sample_format().does_match_flags(stream_format.flags)
+                && does_sample_format_match::<S>(stream_format)
         }
 
         #[allow(non_snake_case)]
@@ -247,6 +745,25 @@ pub mod data {
                 sample_format: PhantomData,
             }
         }
+
+        /// Checks that the list holds a single buffer of exactly `frames` interleaved frames, so
+        /// that a mismatch is reported instead of panicking on the render thread.
+        unsafe fn try_from_input_proc_args(
+            frames: u32,
+            io_data: *mut sys::AudioBufferList,
+        ) -> Result<Self, Error> {
+            if !interleaved_buffer_size_matches::<S>(frames, io_data) {
+                return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+            }
+            Ok(Self::from_input_proc_args(frames, io_data))
+        }
+    }
+
+    impl<S> ChannelCount for Interleaved<S> {
+        /// The number of channels of the single interleaved buffer.
+        fn channel_count(&self) -> usize {
+            self.channels
+        }
     }
 
     // Implementation for an interleaved linear PCM audio format using plain bytes.
@@ -258,7 +775,7 @@ pub mod data {
             !stream_format
                 .flags
                 .contains(LinearPcmFlags::IS_NON_INTERLEAVED)
-                && S::sample_format().does_match_flags(stream_format.flags)
+                && does_sample_format_match::<S>(stream_format)
         }
 
         #[allow(non_snake_case)]
@@ -288,6 +805,25 @@ pub mod data {
                 sample_format: PhantomData,
             }
         }
+
+        /// Checks the size of the interleaved buffer before viewing it as bytes, as with
+        /// **Interleaved**.
+        unsafe fn try_from_input_proc_args(
+            frames: u32,
+            io_data: *mut sys::AudioBufferList,
+        ) -> Result<Self, Error> {
+            if !interleaved_buffer_size_matches::<S>(frames, io_data) {
+                return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+            }
+            Ok(Self::from_input_proc_args(frames, io_data))
+        }
+    }
+
+    impl<S> ChannelCount for InterleavedBytes<S> {
+        /// The number of channels of the single interleaved buffer.
+        fn channel_count(&self) -> usize {
+            self.channels
+        }
     }
 }
 
@@ -434,6 +970,11 @@ pub mod action_flags {
         pub fn from_ptr(ptr: *mut sys::AudioUnitRenderActionFlags) -> Self {
             Handle { ptr }
         }
+
+        /// The wrapped pointer.
+        pub(crate) fn as_ptr(&self) -> *mut sys::AudioUnitRenderActionFlags {
+            self.ptr
+        }
     }
 
     unsafe impl Send for Handle {}
@@ -461,7 +1002,233 @@ pub mod action_flags {
 
 impl AudioUnit {
     /// Pass a render callback (aka "Input Procedure") to the **AudioUnit**.
-    pub fn set_render_callback<F, D>(&mut self, mut f: F) -> Result<(), Error>
+    pub fn set_render_callback<F, D>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.set_render_callback_matching(f, D::does_stream_format_match)
+    }
+
+    /// Pass a render callback that is always called with blocks of exactly `block_size` frames,
+    /// regardless of the number of frames requested by the **AudioUnit** per render cycle.
+    ///
+    /// This is useful for DSP that requires a constant block size, e.g. FFT-based processing
+    /// with power-of-two blocks. The callback's output is buffered internally and handed to the
+    /// **AudioUnit** as requested, carrying leftover frames across render cycles. This adds up
+    /// to `block_size` frames of latency. All buffers are allocated up front, so no allocation
+    /// happens while rendering.
+    ///
+    /// The `time_stamp` passed to the callback is that of the render cycle during which the block
+    /// is rendered. Returns `Error::Unspecified` if `block_size` is `0`.
+    pub fn set_render_callback_fixed_block<F, D>(
+        &mut self,
+        block_size: usize,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd = self.get_property(id, Scope::Input, Element::Output)?;
+        let stream_format = super::StreamFormat::from_asbd(asbd)?;
+        if !D::does_stream_format_match(&stream_format) {
+            return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
+        }
+
+        if block_size == 0 {
+            return Err(Error::Unspecified);
+        }
+
+        let mut block = FixedBlock::new(block_size, &stream_format);
+        let fixed_block_fn = move |args: Args<data::Raw>| -> Result<(), ()> {
+            let Args {
+                mut data,
+                time_stamp,
+                bus_number,
+                num_frames,
+                flags,
+            } = args;
+            let frame_bytes = block.buffers.frame_bytes;
+            let mut frame = 0;
+            while frame < num_frames {
+                // Render the next block once the current one has been fully handed out.
+                if block.position == block_size {
+                    let args = Args {
+                        data: unsafe {
+                            D::try_from_input_proc_args(
+                                block_size as u32,
+                                block.buffers.as_mut_ptr(),
+                            )
+                            .map_err(|_| ())?
+                        },
+                        time_stamp,
+                        bus_number,
+                        num_frames: block_size,
+                        flags: action_flags::Handle::from_ptr(flags.as_ptr()),
+                    };
+                    f(args)?;
+                    block.position = 0;
+                }
+                let frames = (block_size - block.position).min(num_frames - frame);
+                let src_start = block.position * frame_bytes;
+                let dst_start = frame * frame_bytes;
+                let len = frames * frame_bytes;
+                for ((dst, _), src) in data.iter_mut().zip(&block.buffers.data) {
+                    let len = len.min(dst.len().saturating_sub(dst_start));
+                    dst[dst_start..dst_start + len]
+                        .copy_from_slice(&src[src_start..src_start + len]);
+                }
+                block.position += frames;
+                frame += frames;
+            }
+            Ok(())
+        };
+        self.set_render_callback_matching(fixed_block_fn, D::does_stream_format_match)
+    }
+
+    /// Pass a render callback that may produce fewer frames than requested, e.g. a one-shot
+    /// sampler reaching the end of its sample.
+    ///
+    /// The callback returns the number of frames it actually wrote to the start of the buffers.
+    /// The remaining frames are filled with silence, and if no frames were written at all the
+    /// buffers are also flagged with `OUTPUT_IS_SILENCE`. Returning an `Error` aborts the render
+    /// cycle as returning `Err` from a callback passed to
+    /// [**set_render_callback**](./struct.AudioUnit.html#method.set_render_callback) does.
+    pub fn set_render_callback_partial<F, D>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<usize, Error> + 'static,
+        D: Data,
+    {
+        let partial_fn = move |args: Args<data::Raw>| -> Result<(), ()> {
+            let Args {
+                mut data,
+                time_stamp,
+                bus_number,
+                num_frames,
+                mut flags,
+            } = args;
+            let args = Args {
+                data: unsafe {
+                    D::try_from_input_proc_args(num_frames as u32, data.data).map_err(|_| ())?
+                },
+                time_stamp,
+                bus_number,
+                num_frames,
+                flags: action_flags::Handle::from_ptr(flags.as_ptr()),
+            };
+            let frames_written = f(args).map_err(|_| ())?.min(num_frames);
+            if frames_written < num_frames {
+                for (bytes, _) in data.iter_mut() {
+                    let frame_bytes = bytes.len() / num_frames;
+                    for byte in &mut bytes[frames_written * frame_bytes..] {
+                        *byte = 0;
+                    }
+                }
+            }
+            if frames_written == 0 {
+                flags.insert(ActionFlags::OUTPUT_IS_SILENCE);
+            }
+            Ok(())
+        };
+        self.set_render_callback_matching(partial_fn, D::does_stream_format_match)
+    }
+
+    /// Pass a render callback that reads some `state` shared with other threads, e.g. filter
+    /// coefficients or a wavetable.
+    ///
+    /// The callback is given a reference to the state on every render cycle, while the caller
+    /// may keep clones of the `Arc` to read the same state elsewhere. For state that must be
+    /// updated while rendering, see
+    /// [**set_render_callback_with_commands**](./struct.AudioUnit.html#method.set_render_callback_with_commands).
+    ///
+    /// ```no_run
+    /// # use coreaudio::audio_unit::render_callback::{self, data};
+    /// # use coreaudio::audio_unit::{AudioUnit, IOType};
+    /// # use std::sync::Arc;
+    /// # fn main() -> Result<(), coreaudio::Error> {
+    /// type Args = render_callback::Args<data::NonInterleaved<f32>>;
+    ///
+    /// let wavetable: Arc<Vec<f32>> = Arc::new((0..512).map(|i| (i as f32 / 512.0) - 0.5).collect());
+    /// let mut position = 0;
+    /// let mut audio_unit = AudioUnit::new(IOType::DefaultOutput)?;
+    /// audio_unit.set_render_callback_with_state(wavetable, move |table: &Vec<f32>, mut args: Args| {
+    ///     for i in 0..args.num_frames {
+    ///         let sample = table[(position + i) % table.len()];
+    ///         for channel in args.data.channels_mut() {
+    ///             channel[i] = sample;
+    ///         }
+    ///     }
+    ///     position = (position + args.num_frames) % table.len();
+    ///     Ok(())
+    /// })?;
+    /// audio_unit.start()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_render_callback_with_state<S, F, D>(
+        &mut self,
+        state: Arc<S>,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        S: Send + Sync + 'static,
+        F: FnMut(&S, Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.set_render_callback(move |args: Args<D>| f(&state, args))
+    }
+
+    /// Pass a render callback providing the audio for the given input bus, e.g. one of the
+    /// inputs of a mixer unit.
+    ///
+    /// For bus `0` this is the same as
+    /// [**set_render_callback**](./struct.AudioUnit.html#method.set_render_callback). Setting a
+    /// callback for a bus that already has one replaces the previous callback.
+    pub fn set_input_bus_render_callback<F, D>(&mut self, bus: u32, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        if bus == Element::Output as u32 {
+            return self.set_render_callback(f);
+        }
+        let (callback, format_listener) =
+            self.install_render_callback(bus, f, D::does_stream_format_match)?;
+        self.free_bus_render_callback(bus);
+        self.bus_render_callbacks
+            .push((bus, callback, format_listener));
+        Ok(())
+    }
+
+    /// Install a render callback, checking that the current stream format and any future changes
+    /// to it match according to `does_stream_format_match`.
+    fn set_render_callback_matching<F, D>(
+        &mut self,
+        f: F,
+        does_stream_format_match: fn(&super::StreamFormat) -> bool,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        let (callback, format_listener) =
+            self.install_render_callback(Element::Output as u32, f, does_stream_format_match)?;
+        self.free_render_callback();
+        self.maybe_render_callback = Some(callback);
+        self.maybe_render_format_listener = Some(format_listener);
+        Ok(())
+    }
+
+    /// Install a render callback for the given input bus, returning the callback and format
+    /// listener that the caller must keep track of and free.
+    fn install_render_callback<F, D>(
+        &mut self,
+        bus: u32,
+        mut f: F,
+        does_stream_format_match: fn(&super::StreamFormat) -> bool,
+    ) -> Result<(*mut InputProcFnWrapper, *mut FormatListener), Error>
     where
         F: FnMut(Args<D>) -> Result<(), ()> + 'static,
         D: Data,
@@ -469,14 +1236,19 @@ impl AudioUnit {
         // First, we'll retrieve the stream format so that we can ensure that the given callback
         // format matches the audio unit's format.
         let id = sys::kAudioUnitProperty_StreamFormat;
-        let asbd = self.get_property(id, Scope::Input, Element::Output)?;
+        let asbd = self.get_property(id, Scope::Input, bus)?;
         let stream_format = super::StreamFormat::from_asbd(asbd)?;
 
         // If the stream format does not match, return an error indicating this.
-        if !D::does_stream_format_match(&stream_format) {
+        if !does_stream_format_match(&stream_format) {
             return Err(Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat);
         }
 
+        let render_paused = self.render_paused.clone();
+        let render_buffers_invalid = self.render_buffers_invalid.clone();
+        let format_changed = Arc::new(AtomicBool::new(false));
+        let callback_format_changed = format_changed.clone();
+
         // Here, we call the given render callback function within a closure that matches the
         // arguments of the required coreaudio "input_proc".
         //
@@ -488,8 +1260,28 @@ impl AudioUnit {
                                   in_number_frames: sys::UInt32,
                                   io_data: *mut sys::AudioBufferList|
               -> sys::OSStatus {
+            // The buffers may be missing while a device is being removed. There is nothing to
+            // render into, so report it to the control side rather than dereferencing them.
+            if io_data.is_null() || unsafe { (*io_data).mNumberBuffers } == 0 {
+                render_buffers_invalid.store(true, AtomicOrdering::Relaxed);
+                unsafe { output_silence(io_action_flags, io_data) };
+                return 0;
+            }
+            if render_paused.load(AtomicOrdering::Relaxed)
+                || callback_format_changed.load(AtomicOrdering::Relaxed)
+            {
+                unsafe { output_silence(io_action_flags, io_data) };
+                return 0;
+            }
+
+            let data = match unsafe { D::try_from_input_proc_args(in_number_frames, io_data) } {
+                Ok(data) => data,
+                Err(err) => {
+                    unsafe { output_silence(io_action_flags, io_data) };
+                    return err.as_os_status();
+                }
+            };
             let args = unsafe {
-                let data = D::from_input_proc_args(in_number_frames, io_data);
                 let flags = action_flags::Handle::from_ptr(io_action_flags);
                 Args {
                     data,
@@ -508,6 +1300,7 @@ impl AudioUnit {
 
         let input_proc_fn_wrapper = Box::new(InputProcFnWrapper {
             callback: Box::new(input_proc_fn),
+            buffer_descriptor: Some(BufferDescriptor::from_stream_format(&stream_format)),
         });
 
         // Setup render callback. Notice that we relinquish ownership of the Callback
@@ -521,20 +1314,65 @@ impl AudioUnit {
             inputProcRefCon: input_proc_fn_wrapper_ptr,
         };
 
-        self.set_property(
+        let format_listener = self.add_format_listener(
+            Scope::Input,
+            bus,
+            does_stream_format_match,
+            format_changed,
+            None,
+        )?;
+        if let Err(err) = self.set_property(
             sys::kAudioUnitProperty_SetRenderCallback,
             Scope::Input,
-            Element::Output,
+            bus,
             Some(&render_callback),
-        )?;
+        ) {
+            unsafe { remove_format_listener(self.instance, format_listener) };
+            return Err(err);
+        }
 
-        self.free_render_callback();
-        self.maybe_render_callback = Some(input_proc_fn_wrapper_ptr as *mut InputProcFnWrapper);
-        Ok(())
+        Ok((
+            input_proc_fn_wrapper_ptr as *mut InputProcFnWrapper,
+            format_listener,
+        ))
+    }
+
+    /// Pause or resume the render callback without removing it.
+    ///
+    /// While paused, the render callback is not called. Instead the output buffers are filled
+    /// with silence and flagged with `OUTPUT_IS_SILENCE`. As the callback is kept, all of its
+    /// state is preserved and rendering resumes immediately when unpaused. Unlike
+    /// [**stop**](./struct.AudioUnit.html#method.stop), the unit and its device keep running.
+    pub fn set_render_paused(&mut self, paused: bool) {
+        self.render_paused.store(paused, AtomicOrdering::Relaxed);
+    }
+
+    /// Whether or not the render callback is currently paused. See
+    /// [**set_render_paused**](./struct.AudioUnit.html#method.set_render_paused).
+    pub fn is_render_paused(&self) -> bool {
+        self.render_paused.load(AtomicOrdering::Relaxed)
     }
 
     /// Pass an input callback (aka "Input Procedure") to the **AudioUnit**.
-    pub fn set_input_callback<F, D>(&mut self, mut f: F) -> Result<(), Error>
+    pub fn set_input_callback<F, D>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.set_input_callback_with_retry(f, RetryPolicy::none())
+    }
+
+    /// The same as [**set_input_callback**](./struct.AudioUnit.html#method.set_input_callback),
+    /// but rendering the input is retried according to the given **RetryPolicy** when it fails
+    /// with `AudioUnitError::CannotDoInCurrentContext`.
+    ///
+    /// If all attempts fail, the error is returned for the current render cycle and the callback
+    /// is not called, as with any other render error.
+    pub fn set_input_callback_with_retry<F, D>(
+        &mut self,
+        mut f: F,
+        retry_policy: RetryPolicy,
+    ) -> Result<(), Error>
     where
         F: FnMut(Args<D>) -> Result<(), ()> + 'static,
         D: Data,
@@ -555,47 +1393,21 @@ impl AudioUnit {
             .flags
             .contains(LinearPcmFlags::IS_NON_INTERLEAVED);
 
-        // Pre-allocate a buffer list for input stream.
-        //
-        // First, get the current buffer size for pre-allocating the `AudioBuffer`s.
-        #[cfg(target_os = "macos")]
-        let mut buffer_frame_size: u32 = {
-            let id = sys::kAudioDevicePropertyBufferFrameSize;
-            let buffer_frame_size: u32 = self.get_property(id, Scope::Global, Element::Output)?;
-            buffer_frame_size
-        };
-        #[cfg(target_os = "ios")]
-        let mut buffer_frame_size: u32 = {
-            let id = sys::kAudioSessionProperty_CurrentHardwareIOBufferDuration;
-            let seconds: f32 = super::audio_session_get_property(id)?;
-            let id = sys::kAudioSessionProperty_CurrentHardwareSampleRate;
-            let sample_rate: f64 = super::audio_session_get_property(id)?;
-            (sample_rate * seconds as f64).round() as u32
-        };
-        let sample_bytes = stream_format.sample_format.size_in_bytes();
         let n_channels = stream_format.channels;
         if non_interleaved && n_channels > 1 {
             return Err(Error::NonInterleavedInputOnlySupportsMono);
         }
 
-        let data_byte_size = buffer_frame_size * sample_bytes as u32 * n_channels;
-        let mut data = vec![0u8; data_byte_size as usize];
-        let audio_buffer = sys::AudioBuffer {
-            mDataByteSize: data_byte_size,
-            mNumberChannels: n_channels,
-            mData: data.as_mut_ptr() as *mut _,
-        };
-        // Relieve ownership of the `Vec` until we're ready to drop the `AudioBufferList`.
-        mem::forget(data);
-
-        let audio_buffer_list = Box::new(sys::AudioBufferList {
-            mNumberBuffers: 1,
-            mBuffers: [audio_buffer],
-        });
+        // Pre-allocate the buffers for the largest number of frames that the unit may render, so
+        // that they can be reused by every callback. If the stream format changes, the format
+        // listener allocates new buffers and hands them over via the `input_buffers`.
+        let max_frames = self.maximum_frames_per_slice()? as usize;
+        let mut buffers = Box::new(AudioBufferListOwned::new(max_frames, &stream_format));
+        let input_buffers = Arc::new(InputBufferExchange::default());
+        let callback_input_buffers = input_buffers.clone();
 
-        // Relinquish ownership of the audio buffer list. Instead, we'll store a raw pointer and
-        // convert it back into a `Box` when `free_input_callback` is next called.
-        let audio_buffer_list_ptr = Box::into_raw(audio_buffer_list);
+        let format_changed = Arc::new(AtomicBool::new(false));
+        let callback_format_changed = format_changed.clone();
 
         // Here, we call the given input callback function within a closure that matches the
         // arguments of the required coreaudio "input_proc".
@@ -609,64 +1421,49 @@ impl AudioUnit {
                                   in_number_frames: sys::UInt32,
                                   _io_data: *mut sys::AudioBufferList|
               -> sys::OSStatus {
-            // If the buffer size has changed, ensure the AudioBuffer is the correct size.
-            if buffer_frame_size != in_number_frames {
-                unsafe {
-                    // Retrieve the up-to-date stream format.
-                    let id = sys::kAudioUnitProperty_StreamFormat;
-                    let asbd =
-                        match super::get_property(audio_unit, id, Scope::Output, Element::Input) {
-                            Err(err) => return err.as_os_status(),
-                            Ok(asbd) => asbd,
-                        };
-                    let stream_format = match super::StreamFormat::from_asbd(asbd) {
-                        Err(err) => return err.as_os_status(),
-                        Ok(fmt) => fmt,
-                    };
-                    let sample_bytes = stream_format.sample_format.size_in_bytes();
-                    let n_channels = stream_format.channels;
-                    let data_byte_size =
-                        in_number_frames as usize * sample_bytes * n_channels as usize;
-                    let ptr = (*audio_buffer_list_ptr).mBuffers.as_ptr() as *const sys::AudioBuffer;
-                    let len = (*audio_buffer_list_ptr).mNumberBuffers as usize;
-
-                    let buffers: &[sys::AudioBuffer] = slice::from_raw_parts(ptr, len);
-                    for &buffer in buffers {
-                        let ptr = buffer.mData as *mut u8;
-                        let len = buffer.mDataByteSize as usize;
-                        let cap = len;
-                        let mut vec = Vec::from_raw_parts(ptr, len, cap);
-                        match len.cmp(&data_byte_size) {
-                            Ordering::Greater => {
-                                vec.truncate(data_byte_size);
-                            }
-                            Ordering::Less => {
-                                vec.reserve_exact(data_byte_size - len);
-                            }
-                            Ordering::Equal => {}
-                        }
-                        mem::forget(vec);
-                    }
-                }
-                buffer_frame_size = in_number_frames;
+            // The buffer no longer matches the stream format, so skip rendering altogether.
+            if callback_format_changed.load(AtomicOrdering::Relaxed) {
+                return 0;
+            }
+
+            callback_input_buffers.exchange(&mut buffers);
+            if !buffers.set_frames(in_number_frames as usize) {
+                return error::AudioUnitError::TooManyFramesToProcess.as_os_status();
             }
+            let audio_buffer_list_ptr = buffers.as_mut_ptr();
 
             unsafe {
-                let status = sys::AudioUnitRender(
-                    audio_unit,
-                    io_action_flags,
-                    in_time_stamp,
-                    in_bus_number,
-                    in_number_frames,
-                    audio_buffer_list_ptr,
-                );
+                let cannot_do_now = error::AudioUnitError::CannotDoInCurrentContext.as_os_status();
+                let mut attempts = 1;
+                let status = loop {
+                    let status = sys::AudioUnitRender(
+                        audio_unit,
+                        io_action_flags,
+                        in_time_stamp,
+                        in_bus_number,
+                        in_number_frames,
+                        audio_buffer_list_ptr,
+                    );
+                    if status != cannot_do_now || attempts >= retry_policy.max_attempts {
+                        break status;
+                    }
+                    attempts += 1;
+                    if retry_policy.spin {
+                        std::hint::spin_loop();
+                    }
+                };
                 if status != 0 {
                     return status;
                 }
             }
 
+            let data = match unsafe {
+                D::try_from_input_proc_args(in_number_frames, audio_buffer_list_ptr)
+            } {
+                Ok(data) => data,
+                Err(err) => return err.as_os_status(),
+            };
             let args = unsafe {
-                let data = D::from_input_proc_args(in_number_frames, audio_buffer_list_ptr);
                 let flags = action_flags::Handle::from_ptr(io_action_flags);
                 Args {
                     data,
@@ -685,6 +1482,7 @@ impl AudioUnit {
 
         let input_proc_fn_wrapper = Box::new(InputProcFnWrapper {
             callback: Box::new(input_proc_fn),
+            buffer_descriptor: Some(BufferDescriptor::from_stream_format(&stream_format)),
         });
 
         // Setup input callback. Notice that we relinquish ownership of the Callback
@@ -698,25 +1496,269 @@ impl AudioUnit {
             inputProcRefCon: input_proc_fn_wrapper_ptr,
         };
 
-        self.set_property(
+        let format_listener = self.add_format_listener(
+            Scope::Output,
+            Element::Input,
+            D::does_stream_format_match,
+            format_changed,
+            Some(input_buffers),
+        )?;
+        if let Err(err) = self.set_property(
             sys::kAudioOutputUnitProperty_SetInputCallback,
             Scope::Global,
             Element::Output,
             Some(&render_callback),
-        )?;
+        ) {
+            unsafe { remove_format_listener(self.instance, format_listener) };
+            return Err(err);
+        }
 
         let input_callback = super::InputCallback {
-            buffer_list: audio_buffer_list_ptr,
             callback: input_proc_fn_wrapper_ptr as *mut InputProcFnWrapper,
+            format_listener,
         };
         self.free_input_callback();
         self.maybe_input_callback = Some(input_callback);
         Ok(())
     }
 
+    /// Capture `num_frames` frames of `f32` audio from an input **AudioUnit**.
+    ///
+    /// The **AudioUnit** must already be configured for input (see
+    /// [**audio_unit_from_device_id**](./macos_helpers/fn.audio_unit_from_device_id.html)) with
+    /// an `f32` input stream format. This installs an input callback, starts the unit, waits for
+    /// the requested number of frames and then stops the unit and removes the callback again.
+    ///
+    /// The callback copies the input into a ring buffer that is allocated up front for all of the
+    /// requested frames, so the I/O thread neither allocates nor blocks.
+    ///
+    /// The returned samples are interleaved, so the `Vec` has a length of `num_frames` multiplied
+    /// by the number of channels in the input stream format.
+    ///
+    /// Returns `Error::InputCallbackAlreadySet` if an input callback is already installed, and
+    /// `Error::CaptureTimedOut` if the unit stops delivering audio before the requested number of
+    /// frames have been captured.
+    pub fn capture_to_vec(&mut self, num_frames: usize) -> Result<Vec<f32>, Error> {
+        // If no audio arrives within this duration we assume the device has stopped delivering.
+        const TIMEOUT: Duration = Duration::from_secs(2);
+        // How long to wait for the callback to deliver more audio before checking again.
+        const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+        if self.maybe_input_callback.is_some() {
+            return Err(Error::InputCallbackAlreadySet);
+        }
+
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let asbd = self.get_property(id, Scope::Output, Element::Input)?;
+        let stream_format = super::StreamFormat::from_asbd(asbd)?;
+        let num_samples = num_frames * stream_format.channels as usize;
+
+        // The callback copies its samples into the ring, dropping those that no longer fit once
+        // all of the requested samples have been delivered.
+        let (mut sender, mut receiver) = CommandQueue::with_capacity(num_samples);
+        if stream_format
+            .flags
+            .contains(LinearPcmFlags::IS_NON_INTERLEAVED)
+        {
+            self.set_input_callback(move |args: Args<data::NonInterleaved<f32>>| {
+                // Non-interleaved input is limited to a single channel.
+                for channel in args.data.channels() {
+                    for &sample in channel {
+                        let _ = sender.send(sample);
+                    }
+                }
+                Ok(())
+            })?;
+        } else {
+            self.set_input_callback(move |args: Args<data::Interleaved<f32>>| {
+                for &sample in args.data.buffer.iter() {
+                    let _ = sender.send(sample);
+                }
+                Ok(())
+            })?;
+        }
+
+        let mut samples = Vec::with_capacity(num_samples);
+        let result = self.start().and_then(|()| {
+            let mut last_received = Instant::now();
+            while samples.len() < num_samples {
+                let len = samples.len();
+                samples.extend(receiver.drain());
+                if samples.len() > len {
+                    last_received = Instant::now();
+                } else if last_received.elapsed() > TIMEOUT {
+                    return Err(Error::CaptureTimedOut);
+                } else {
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+            Ok(())
+        });
+
+        // Remove the callback even if stopping failed, so that it isn't left installed.
+        let stopped = self.stop();
+        let removed = self.remove_input_callback();
+        result?;
+        stopped?;
+        removed?;
+
+        samples.truncate(num_samples);
+        Ok(samples)
+    }
+
+    /// Unregisters the input callback from the **AudioUnit** and frees it.
+    fn remove_input_callback(&mut self) -> Result<(), Error> {
+        let render_callback = sys::AURenderCallbackStruct {
+            inputProc: None,
+            inputProcRefCon: ptr::null_mut(),
+        };
+        self.set_property(
+            sys::kAudioOutputUnitProperty_SetInputCallback,
+            Scope::Global,
+            Element::Output,
+            Some(&render_callback),
+        )?;
+        self.free_input_callback();
+        Ok(())
+    }
+
+    /// Registers a closure with the **AudioUnit** that is called both before and after each render
+    /// operation. The closure is freed when the **AudioUnit** is dropped.
+    pub(crate) fn add_render_notify_fn<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(
+                *mut sys::AudioUnitRenderActionFlags,
+                *const sys::AudioTimeStamp,
+                sys::UInt32,
+                sys::UInt32,
+                *mut sys::AudioBufferList,
+            ) -> sys::OSStatus
+            + 'static,
+    {
+        let wrapper_ptr = Box::into_raw(InputProcFnWrapper::new_raw(f));
+        unsafe {
+            let status = sys::AudioUnitAddRenderNotify(
+                self.instance,
+                Some(input_proc),
+                wrapper_ptr as *mut c_void,
+            );
+            if let Err(err) = Error::from_os_status(status) {
+                let _: Box<InputProcFnWrapper> = Box::from_raw(wrapper_ptr);
+                return Err(err);
+            }
+        }
+        self.render_notify_callbacks.push(wrapper_ptr);
+        Ok(())
+    }
+
+    /// Unregisters and frees all closures added with `add_render_notify_fn`.
+    pub(crate) fn free_render_notify_callbacks(&mut self) {
+        for wrapper_ptr in self.render_notify_callbacks.drain(..) {
+            unsafe {
+                // We can't do anything useful about a failure here, the closure must be freed
+                // either way.
+                sys::AudioUnitRemoveRenderNotify(
+                    self.instance,
+                    Some(input_proc),
+                    wrapper_ptr as *mut c_void,
+                );
+                let _: Box<InputProcFnWrapper> = Box::from_raw(wrapper_ptr);
+            }
+        }
+    }
+
+    /// Check whether the installed render and input callbacks, including those of individual
+    /// buses, are still being called.
+    ///
+    /// If the stream format changes after a callback was installed, e.g. due to a change of the
+    /// device's sample format or a call to
+    /// [**set_stream_format**](./struct.AudioUnit.html#method.set_stream_format), so that it no
+    /// longer matches the callback's buffer type, the callback is no longer called. Render
+    /// callbacks output silence instead. In this case
+    /// `Error::StreamFormatChangedSinceCallbackInstalled` is returned until either the format is
+    /// changed back or the callback is replaced.
+    ///
+    /// If a render callback was called without valid buffers since the last check, e.g. because
+    /// its device was disconnected, `Error::RenderBuffersUnavailable` is returned once.
+    pub fn check_render_health(&self) -> Result<(), Error> {
+        if self
+            .render_buffers_invalid
+            .swap(false, AtomicOrdering::Relaxed)
+        {
+            return Err(Error::RenderBuffersUnavailable);
+        }
+        let input_format_listener = self
+            .maybe_input_callback
+            .as_ref()
+            .map(|input_callback| input_callback.format_listener);
+        let bus_format_listeners = self
+            .bus_render_callbacks
+            .iter()
+            .map(|&(_, _, listener)| listener);
+        let listeners = self
+            .maybe_render_format_listener
+            .iter()
+            .copied()
+            .chain(input_format_listener)
+            .chain(bus_format_listeners);
+        for listener in listeners {
+            if unsafe { (*listener).format_changed.load(AtomicOrdering::Relaxed) } {
+                return Err(Error::StreamFormatChangedSinceCallbackInstalled);
+            }
+        }
+        Ok(())
+    }
+
+    /// Listen for changes to the stream format on the given scope and element, flagging
+    /// `format_changed` whenever the format does not match.
+    ///
+    /// If `input_buffers` are given, buffers for the new format are offered to the callback on
+    /// each change.
+    fn add_format_listener(
+        &mut self,
+        scope: Scope,
+        element: impl Into<u32>,
+        does_stream_format_match: fn(&super::StreamFormat) -> bool,
+        format_changed: Arc<AtomicBool>,
+        input_buffers: Option<Arc<InputBufferExchange>>,
+    ) -> Result<*mut FormatListener, Error> {
+        let listener = Box::into_raw(Box::new(FormatListener {
+            scope,
+            element: element.into(),
+            does_stream_format_match,
+            format_changed,
+            input_buffers,
+        }));
+        let status = unsafe {
+            sys::AudioUnitAddPropertyListener(
+                self.instance,
+                sys::kAudioUnitProperty_StreamFormat,
+                Some(format_listener_proc),
+                listener as *mut c_void,
+            )
+        };
+        if let Err(err) = Error::from_os_status(status) {
+            let _: Box<FormatListener> = unsafe { Box::from_raw(listener) };
+            return Err(err);
+        }
+        Ok(listener)
+    }
+
+    /// The layout of the buffers handed to the render callback, if one is installed.
+    ///
+    /// This describes the stream format that the callback was checked against when it was
+    /// installed, e.g. for confirming at runtime that it matches the expected buffer type.
+    pub fn render_callback_format(&self) -> Option<BufferDescriptor> {
+        self.maybe_render_callback
+            .and_then(|callback| unsafe { (*callback).buffer_descriptor })
+    }
+
     /// Retrieves ownership over the render callback and returns it where it can be re-used or
     /// safely dropped.
     pub fn free_render_callback(&mut self) -> Option<Box<InputProcFnWrapper>> {
+        if let Some(listener) = self.maybe_render_format_listener.take() {
+            unsafe { remove_format_listener(self.instance, listener) };
+        }
         if let Some(callback) = self.maybe_render_callback.take() {
             // Here, we transfer ownership of the callback back to the current scope so that it
             // is dropped and cleaned up. Without this line, we would leak the Boxed callback.
@@ -726,28 +1768,46 @@ impl AudioUnit {
         None
     }
 
+    /// Retrieves ownership over the render callback of the given input bus, if any, so that it
+    /// is safely dropped.
+    fn free_bus_render_callback(&mut self, bus: u32) {
+        let instance = self.instance;
+        self.bus_render_callbacks
+            .retain(|&(b, callback, listener)| {
+                if b != bus {
+                    return true;
+                }
+                unsafe {
+                    remove_format_listener(instance, listener);
+                    let _: Box<InputProcFnWrapper> = Box::from_raw(callback);
+                }
+                false
+            });
+    }
+
+    /// Retrieves ownership over the render callbacks of all input buses so that they are safely
+    /// dropped.
+    pub(crate) fn free_bus_render_callbacks(&mut self) {
+        for (_, callback, listener) in self.bus_render_callbacks.drain(..) {
+            unsafe {
+                remove_format_listener(self.instance, listener);
+                let _: Box<InputProcFnWrapper> = Box::from_raw(callback);
+            }
+        }
+    }
+
     /// Retrieves ownership over the input callback and returns it where it can be re-used or
     /// safely dropped.
     pub fn free_input_callback(&mut self) -> Option<Box<InputProcFnWrapper>> {
         if let Some(input_callback) = self.maybe_input_callback.take() {
             let super::InputCallback {
-                buffer_list,
                 callback,
+                format_listener,
             } = input_callback;
             unsafe {
-                // Take ownership over the AudioBufferList in order to safely free it.
-                let buffer_list: Box<sys::AudioBufferList> = Box::from_raw(buffer_list);
-                // Free the allocated data from the individual audio buffers.
-                let ptr = buffer_list.mBuffers.as_ptr() as *const sys::AudioBuffer;
-                let len = buffer_list.mNumberBuffers as usize;
-                let buffers: &[sys::AudioBuffer] = slice::from_raw_parts(ptr, len);
-                for &buffer in buffers {
-                    let ptr = buffer.mData as *mut u8;
-                    let len = buffer.mDataByteSize as usize;
-                    let cap = len;
-                    let _ = Vec::from_raw_parts(ptr, len, cap);
-                }
-                // Take ownership over the callback so that it can be freed.
+                remove_format_listener(self.instance, format_listener);
+                // Take ownership over the callback so that it can be freed, along with the
+                // buffers that it renders the input to.
                 let callback: Box<InputProcFnWrapper> = Box::from_raw(callback);
                 return Some(callback);
             }
@@ -756,8 +1816,80 @@ impl AudioUnit {
     }
 }
 
+/// Stop listening for stream format changes and free the listener.
+unsafe fn remove_format_listener(instance: sys::AudioUnit, listener: *mut FormatListener) {
+    // We can't do anything useful about a failure here, the listener must be freed either way.
+    sys::AudioUnitRemovePropertyListenerWithUserData(
+        instance,
+        sys::kAudioUnitProperty_StreamFormat,
+        Some(format_listener_proc),
+        listener as *mut c_void,
+    );
+    let _: Box<FormatListener> = Box::from_raw(listener);
+}
+
+/// Callback procedure that will be called each time a property of an audio unit with an installed
+/// callback changes.
+extern "C" fn format_listener_proc(
+    in_ref_con: *mut c_void,
+    in_unit: sys::AudioUnit,
+    in_id: sys::AudioUnitPropertyID,
+    in_scope: sys::AudioUnitScope,
+    in_element: sys::AudioUnitElement,
+) {
+    let listener = unsafe { &*(in_ref_con as *const FormatListener) };
+    if in_id != sys::kAudioUnitProperty_StreamFormat
+        || in_scope != listener.scope as u32
+        || in_element != listener.element
+    {
+        return;
+    }
+    let id = sys::kAudioUnitProperty_StreamFormat;
+    let stream_format = super::get_property(in_unit, id, listener.scope, listener.element)
+        .and_then(super::StreamFormat::from_asbd);
+    let does_match = stream_format
+        .as_ref()
+        .map(|stream_format| (listener.does_stream_format_match)(stream_format))
+        .unwrap_or(false);
+    // Reallocate the input buffers while we're off the render thread.
+    if let (Some(input_buffers), Ok(stream_format)) = (&listener.input_buffers, &stream_format) {
+        let id = sys::kAudioUnitProperty_MaximumFramesPerSlice;
+        if let Ok(max_frames) =
+            super::get_property::<u32>(in_unit, id, Scope::Global, Element::Output)
+        {
+            input_buffers.offer(AudioBufferListOwned::new(
+                max_frames as usize,
+                stream_format,
+            ));
+        }
+    }
+    listener
+        .format_changed
+        .store(!does_match, AtomicOrdering::Relaxed);
+}
+
+/// Fill the given buffers with silence and flag them with `OUTPUT_IS_SILENCE`.
+unsafe fn output_silence(
+    io_action_flags: *mut sys::AudioUnitRenderActionFlags,
+    io_data: *mut sys::AudioBufferList,
+) {
+    if !io_data.is_null() {
+        let ptr = (*io_data).mBuffers.as_ptr();
+        let len = (*io_data).mNumberBuffers as usize;
+        let buffers: &[sys::AudioBuffer] = slice::from_raw_parts(ptr, len);
+        for buffer in buffers {
+            if !buffer.mData.is_null() {
+                ptr::write_bytes(buffer.mData as *mut u8, 0, buffer.mDataByteSize as usize);
+            }
+        }
+    }
+    if !io_action_flags.is_null() {
+        action_flags::Handle::from_ptr(io_action_flags).insert(ActionFlags::OUTPUT_IS_SILENCE);
+    }
+}
+
 /// Callback procedure that will be called each time our audio_unit requests audio.
-extern "C" fn input_proc(
+pub(crate) extern "C" fn input_proc(
     in_ref_con: *mut c_void,
     io_action_flags: *mut sys::AudioUnitRenderActionFlags,
     in_time_stamp: *const sys::AudioTimeStamp,
@@ -0,0 +1,251 @@
+//! A render callback fed with audio at a different sample rate than the **AudioUnit**'s, e.g.
+//! for playing 44.1kHz audio through a 48kHz device.
+//!
+//! The audio is handed from a **ResamplingProducer** to the render thread through a
+//! **CommandQueue** of samples, and converted to the unit's stream format by an `AudioConverter`
+//! within the render callback. The converter and all buffers are created up front, so nothing is
+//! allocated while rendering.
+
+use super::audio_format::LinearPcmFlags;
+use super::command_queue::{CommandQueue, CommandReceiver, CommandSender};
+use super::render_callback::{data, Args};
+use super::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
+use crate::error::Error;
+use std::os::raw::c_void;
+use std::ptr;
+use sys;
+
+/// The producing half of a resampling render callback, as returned by
+/// [**AudioUnit::set_render_callback_resampling**](../struct.AudioUnit.html#method.set_render_callback_resampling).
+///
+/// The producer accepts interleaved `f32` samples in the input format given when the callback
+/// was installed, and may be moved to another thread.
+pub struct ResamplingProducer {
+    sender: CommandSender<f32>,
+    channels: usize,
+}
+
+impl ResamplingProducer {
+    /// Queue as many whole frames of the given interleaved samples as fit, without blocking.
+    ///
+    /// Returns the number of frames queued. Frames that don't fit should be pushed again once
+    /// the render callback has consumed some of the queued audio.
+    pub fn push(&mut self, interleaved: &[f32]) -> usize {
+        let frames = (interleaved.len() / self.channels).min(self.available_frames());
+        for &sample in &interleaved[..frames * self.channels] {
+            // Can't fail, as only as many samples as are available are sent.
+            let _ = self.sender.send(sample);
+        }
+        frames
+    }
+
+    /// The number of frames that can currently be queued.
+    pub fn available_frames(&self) -> usize {
+        self.sender.available() / self.channels
+    }
+
+    /// The maximum number of frames that can be queued.
+    pub fn capacity_frames(&self) -> usize {
+        self.sender.capacity() / self.channels
+    }
+}
+
+/// An `AudioConverterRef`, disposed when dropped.
+struct Converter(sys::AudioConverterRef);
+
+impl Drop for Converter {
+    fn drop(&mut self) {
+        unsafe {
+            sys::AudioConverterDispose(self.0);
+        }
+    }
+}
+
+/// The state of the converter's input data proc, owned by the render callback.
+struct ConverterInput {
+    receiver: CommandReceiver<f32>,
+    // Holds the interleaved samples handed to the converter for a single request.
+    scratch: Box<[f32]>,
+    channels: usize,
+    buffer_list: sys::AudioBufferList,
+}
+
+impl AudioUnit {
+    /// Pass a render callback that plays the audio pushed to the returned
+    /// **ResamplingProducer**, converting it from `input_format` to the **AudioUnit**'s stream
+    /// format.
+    ///
+    /// The `input_format` must consist of interleaved `f32` samples with the same number of
+    /// channels as the unit's stream format, otherwise `Error::UnsupportedStreamFormat` is
+    /// returned. Its sample rate, however, may differ. The producer can hold one second of input
+    /// audio. When it runs dry, silence is played until more audio is pushed.
+    ///
+    /// Apart from the sample rate, the converter also handles differences in the sample format
+    /// and interleaving of the unit's stream format.
+    pub fn set_render_callback_resampling(
+        &mut self,
+        input_format: StreamFormat,
+    ) -> Result<ResamplingProducer, Error> {
+        let id = sys::kAudioUnitProperty_StreamFormat;
+        let output_asbd: sys::AudioStreamBasicDescription =
+            self.get_property(id, Scope::Input, Element::Output)?;
+        let output_format = StreamFormat::from_asbd(output_asbd)?;
+        let interleaved = !input_format
+            .flags
+            .contains(LinearPcmFlags::IS_NON_INTERLEAVED);
+        if input_format.sample_format != SampleFormat::F32
+            || !interleaved
+            || input_format.channels != output_format.channels
+            || input_format.sample_rate <= 0.0
+        {
+            return Err(Error::UnsupportedStreamFormat);
+        }
+
+        let input_asbd = input_format.to_asbd();
+        let mut converter: sys::AudioConverterRef = ptr::null_mut();
+        unsafe {
+            Error::from_os_status(sys::AudioConverterNew(
+                &input_asbd as *const _,
+                &output_asbd as *const _,
+                &mut converter as *mut _,
+            ))?;
+        }
+        let converter = Converter(converter);
+
+        // Size the scratch buffer for the input needed by the largest render cycle, plus some
+        // headroom for the converter's filter. Larger requests are served partially, after which
+        // the converter asks for the rest.
+        let channels = input_format.channels as usize;
+        let max_frames = self.maximum_frames_per_slice()? as f64;
+        let ratio = input_format.sample_rate / output_format.sample_rate;
+        let scratch_frames = (max_frames * ratio).ceil() as usize + 64;
+        let queue_frames = input_format.sample_rate.ceil() as usize;
+        let (sender, receiver) = CommandQueue::with_capacity(queue_frames * channels);
+        let mut input = Box::new(ConverterInput {
+            receiver,
+            scratch: vec![0.0; scratch_frames * channels].into_boxed_slice(),
+            channels,
+            buffer_list: sys::AudioBufferList {
+                mNumberBuffers: 1,
+                mBuffers: [sys::AudioBuffer {
+                    mNumberChannels: channels as u32,
+                    mDataByteSize: 0,
+                    mData: ptr::null_mut(),
+                }],
+            },
+        });
+
+        self.set_render_callback(move |args: Args<data::Raw>| {
+            let mut frames = args.num_frames as u32;
+            let status = unsafe {
+                sys::AudioConverterFillComplexBuffer(
+                    converter.0,
+                    Some(converter_input_proc),
+                    &mut *input as *mut ConverterInput as *mut c_void,
+                    &mut frames as *mut _,
+                    args.data.data,
+                    ptr::null_mut(),
+                )
+            };
+            if status == 0 {
+                Ok(())
+            } else {
+                Err(())
+            }
+        })?;
+        Ok(ResamplingProducer { sender, channels })
+    }
+}
+
+/// Hands the queued frames to the converter, padding with silent frames when the queue runs dry
+/// so that the converter never sees the end of the stream.
+///
+/// Only whole frames are taken from the queue, as the producer may be in the middle of pushing
+/// one, which would otherwise leave the channels misaligned for the rest of the stream.
+unsafe extern "C" fn converter_input_proc(
+    _converter: sys::AudioConverterRef,
+    io_number_data_packets: *mut sys::UInt32,
+    io_data: *mut sys::AudioBufferList,
+    out_data_packet_description: *mut *mut sys::AudioStreamPacketDescription,
+    in_user_data: *mut c_void,
+) -> sys::OSStatus {
+    let input = &mut *(in_user_data as *mut ConverterInput);
+    let channels = input.channels;
+    let frames = (*io_number_data_packets as usize).min(input.scratch.len() / channels);
+    let queued_samples = frames.min(input.receiver.pending() / channels) * channels;
+    let samples = &mut input.scratch[..frames * channels];
+    let (queued, silent) = samples.split_at_mut(queued_samples);
+    for sample in queued.iter_mut() {
+        // Can't fail, as only as many samples as are pending are received.
+        *sample = input.receiver.try_recv().unwrap_or(0.0);
+    }
+    silent.iter_mut().for_each(|sample| *sample = 0.0);
+
+    input.buffer_list.mBuffers[0].mData = samples.as_mut_ptr() as *mut c_void;
+    input.buffer_list.mBuffers[0].mDataByteSize = std::mem::size_of_val(samples) as u32;
+    *io_data = input.buffer_list;
+    *io_number_data_packets = frames as u32;
+    if !out_data_packet_description.is_null() {
+        *out_data_packet_description = ptr::null_mut();
+    }
+    0
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::super::test_util::{output_sample_rate, render_offline_channels};
+    use super::super::IOType;
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn channels_stay_aligned_while_pushing_during_render() {
+        const LEFT: f32 = 0.25;
+        const RIGHT: f32 = 0.75;
+
+        let mut output = AudioUnit::new(IOType::GenericOutput).unwrap();
+        // At the unit's own sample rate, the converter copies the samples unchanged.
+        let input_format = StreamFormat {
+            sample_rate: output_sample_rate(&output).unwrap(),
+            sample_format: SampleFormat::F32,
+            flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
+            channels: 2,
+        };
+        let mut producer = output.set_render_callback_resampling(input_format).unwrap();
+        output.start().unwrap();
+
+        // Push small chunks as fast as possible, so that pushes race with the render cycles.
+        let done = Arc::new(AtomicBool::new(false));
+        let pushing = {
+            let done = done.clone();
+            thread::spawn(move || {
+                let chunk = [LEFT, RIGHT].repeat(7);
+                while !done.load(Ordering::Relaxed) {
+                    producer.push(&chunk);
+                    thread::yield_now();
+                }
+            })
+        };
+        let mut rendered = Vec::new();
+        for _ in 0..200 {
+            rendered.push(render_offline_channels(&output, 512, 512).unwrap());
+        }
+        done.store(true, Ordering::Relaxed);
+        pushing.join().unwrap();
+        output.stop().unwrap();
+
+        let mut played = 0;
+        for channels in &rendered {
+            for (&left, &right) in channels[0].iter().zip(&channels[1]) {
+                if (left, right) == (LEFT, RIGHT) {
+                    played += 1;
+                } else {
+                    assert_eq!((left, right), (0.0, 0.0), "misaligned frame");
+                }
+            }
+        }
+        assert!(played > 0, "none of the pushed audio was played");
+    }
+}
@@ -0,0 +1,123 @@
+//! Wrappers around Apple's signal routing units, along with the bus and connection APIs used to
+//! wire them up.
+//!
+//! The **Splitter** copies one input to several outputs and the **Merger** combines several
+//! inputs into one output. For routing individual channels, see the
+//! [**MatrixMixer**](../mixer/struct.MatrixMixer.html).
+
+use super::render_callback::{Args, Data};
+use super::{AudioUnit, Element, FormatConverterType, Scope};
+use crate::error::Error;
+use sys;
+
+/// An **AudioUnit** of the **Splitter** subtype, copying its single input bus to each of its
+/// output buses.
+///
+/// Each output bus must be pulled separately, e.g. by connecting it to a different unit via
+/// [**AudioUnit::connect_input**](../struct.AudioUnit.html#method.connect_input).
+pub struct Splitter {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Splitter, render_callback);
+
+impl Splitter {
+    /// Create and initialize a new **Splitter**.
+    ///
+    /// **Available** in OS X v10.4 and later.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(FormatConverterType::Splitter)?;
+        Ok(Splitter { audio_unit })
+    }
+
+    /// The number of output buses to which the input is copied.
+    pub fn output_count(&self) -> Result<u32, Error> {
+        self.audio_unit.element_count(Scope::Output)
+    }
+}
+
+/// An **AudioUnit** of the **Merger** subtype, combining the audio of its input buses into its
+/// single output bus.
+pub struct Merger {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Merger);
+
+impl Merger {
+    /// Create and initialize a new **Merger**.
+    ///
+    /// **Available** in OS X v10.4 and later.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(FormatConverterType::Merger)?;
+        Ok(Merger { audio_unit })
+    }
+
+    /// The number of input buses that are merged.
+    pub fn input_count(&self) -> Result<u32, Error> {
+        self.audio_unit.element_count(Scope::Input)
+    }
+
+    /// Pass a render callback providing the audio for the given input bus.
+    ///
+    /// See [**AudioUnit::set_input_bus_render_callback**](../struct.AudioUnit.html#method.set_input_bus_render_callback).
+    pub fn set_input_render_callback<F, D>(&mut self, bus: u32, f: F) -> Result<(), Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        self.audio_unit.set_input_bus_render_callback(bus, f)
+    }
+}
+
+impl AudioUnit {
+    /// The number of buses of the **AudioUnit** within the given scope.
+    pub fn element_count(&self, scope: Scope) -> Result<u32, Error> {
+        let id = sys::kAudioUnitProperty_ElementCount;
+        self.get_property(id, scope, Element::Output)
+    }
+
+    /// Set the number of buses of the **AudioUnit** within the given scope.
+    ///
+    /// Only units with a variable number of buses, such as mixers, support this, and usually only
+    /// while uninitialized.
+    pub fn set_element_count(&mut self, scope: Scope, count: u32) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_ElementCount;
+        self.set_property(id, scope, Element::Output, Some(&count))
+    }
+
+    /// Connect the given output bus of `source` to the given input bus of this **AudioUnit**, so
+    /// that this unit pulls its input for that bus from `source` when rendering.
+    ///
+    /// # Safety
+    ///
+    /// The connection refers to `source` by its raw instance, so `source` must not be dropped
+    /// until the connection is removed via
+    /// [**disconnect_input**](./struct.AudioUnit.html#method.disconnect_input) or this unit is
+    /// dropped.
+    pub unsafe fn connect_input(
+        &mut self,
+        input_bus: u32,
+        source: &AudioUnit,
+        source_output_bus: u32,
+    ) -> Result<(), Error> {
+        let connection = sys::AudioUnitConnection {
+            sourceAudioUnit: source.instance,
+            sourceOutputNumber: source_output_bus,
+            destInputNumber: input_bus,
+        };
+        let id = sys::kAudioUnitProperty_MakeConnection;
+        self.set_property(id, Scope::Input, input_bus, Some(&connection))
+    }
+
+    /// Remove the connection feeding the given input bus of this **AudioUnit**, if any.
+    pub fn disconnect_input(&mut self, input_bus: u32) -> Result<(), Error> {
+        let connection = sys::AudioUnitConnection {
+            sourceAudioUnit: std::ptr::null_mut(),
+            sourceOutputNumber: 0,
+            destInputNumber: input_bus,
+        };
+        let id = sys::kAudioUnitProperty_MakeConnection;
+        self.set_property(id, Scope::Input, input_bus, Some(&connection))
+    }
+}
@@ -17,10 +17,17 @@ pub enum SampleFormat {
 
 impl SampleFormat {
     /// Check if the format flags are appropriate for the given format.
+    ///
+    /// Samples are always expected in the native byte order of the host, so flags describing the
+    /// opposite endianness never match.
     pub fn does_match_flags(&self, flags: audio_format::LinearPcmFlags) -> bool {
         let is_float = flags.contains(LinearPcmFlags::IS_FLOAT);
         let is_signed_integer = flags.contains(LinearPcmFlags::IS_SIGNED_INTEGER);
         let is_packed = flags.contains(LinearPcmFlags::IS_PACKED);
+        let is_big_endian = flags.contains(LinearPcmFlags::IS_BIG_ENDIAN);
+        if is_big_endian != cfg!(target_endian = "big") {
+            return false;
+        }
         match *self {
             SampleFormat::F32 => is_float && !is_signed_integer && is_packed,
             SampleFormat::I32 | SampleFormat::I16 | SampleFormat::I8 => {
@@ -0,0 +1,100 @@
+//! A wrapper around Apple's **AUSampler** unit, a sample playback instrument.
+//!
+//! Once an instrument has been loaded, the sampler is played via the MIDI methods of its
+//! **AudioUnit**, e.g. [**note_on**](../struct.AudioUnit.html#method.note_on).
+
+use super::{AudioUnit, Element, MusicDeviceType, Scope};
+use crate::error::{AudioError, Error};
+use core_foundation_sys::base::CFRelease;
+use std::os::raw::c_void;
+use std::path::Path;
+use sys;
+
+/// An **AUSampler** unit, playing instruments built from samples.
+///
+/// **Available** in OS X v10.7 and later.
+pub struct Sampler {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Sampler);
+
+impl Sampler {
+    /// Create and initialize a new **Sampler** unit, playing a sine wave until an instrument is
+    /// loaded.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(MusicDeviceType::Sampler)?;
+        Ok(Sampler { audio_unit })
+    }
+
+    /// Load an instrument from the file at the given path via
+    /// `kAUSamplerProperty_LoadInstrument`.
+    ///
+    /// The type of the instrument is determined by the file's extension:
+    ///
+    /// - `.aupreset` files are loaded as sampler presets;
+    /// - `.exs` files are loaded as EXS24 instruments;
+    /// - `.sf2` and `.dls` sound banks are loaded with their first melodic preset, see
+    ///   [**load_sound_bank**](./struct.Sampler.html#method.load_sound_bank) to select another;
+    /// - any other file is loaded as a single audio file, e.g. a `.wav` or `.caf` file.
+    ///
+    /// Returns `AudioError::FileNotFound` if there is no file at the given path.
+    pub fn load_instrument(&mut self, path: &Path) -> Result<(), Error> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let instrument_type = match extension.as_ref().map(|extension| &extension[..]) {
+            Some("aupreset") => sys::kInstrumentType_AUPreset,
+            Some("exs") => sys::kInstrumentType_EXS24,
+            Some("sf2") | Some("dls") => {
+                return self.load_sound_bank(path, sys::kAUSampler_DefaultMelodicBankMSB as u8, 0);
+            }
+            _ => sys::kInstrumentType_Audiofile,
+        };
+        self.load(path, instrument_type as u8, 0, 0, 0)
+    }
+
+    /// Load a preset from the SoundFont (`.sf2`) or DLS sound bank at the given path.
+    ///
+    /// `bank_msb` selects between the melodic (`kAUSampler_DefaultMelodicBankMSB`) and the
+    /// percussion (`kAUSampler_DefaultPercussionBankMSB`) presets of the bank, and `preset` is
+    /// the program number of the preset within it.
+    ///
+    /// Returns `AudioError::FileNotFound` if there is no file at the given path.
+    pub fn load_sound_bank(&mut self, path: &Path, bank_msb: u8, preset: u8) -> Result<(), Error> {
+        let bank_lsb = sys::kAUSampler_DefaultBankLSB as u8;
+        let instrument_type = sys::kInstrumentType_SF2Preset as u8;
+        self.load(path, instrument_type, bank_msb, bank_lsb, preset)
+    }
+
+    fn load(
+        &mut self,
+        path: &Path,
+        instrument_type: u8,
+        bank_msb: u8,
+        bank_lsb: u8,
+        preset_id: u8,
+    ) -> Result<(), Error> {
+        // The sampler reports missing files inconsistently, so check for them up front.
+        if !path.is_file() {
+            return Err(Error::Audio(AudioError::FileNotFound));
+        }
+        let url = super::cf_url_from_path(path)?;
+        unsafe {
+            let instrument = sys::AUSamplerInstrumentData {
+                fileURL: url as sys::CFURLRef,
+                instrumentType: instrument_type,
+                bankMSB: bank_msb,
+                bankLSB: bank_lsb,
+                presetID: preset_id,
+            };
+            let id = sys::kAUSamplerProperty_LoadInstrument;
+            let result =
+                self.audio_unit
+                    .set_property(id, Scope::Global, Element::Output, Some(&instrument));
+            CFRelease(url as *const c_void);
+            result
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Scheduling buffers for sample-accurate playback on a **ScheduledSoundPlayer** unit.
+//!
+//! The unit plays each scheduled slice at its time stamp, relative to the start time set via
+//! [**set_schedule_start_time**](../struct.AudioUnit.html#method.set_schedule_start_time), so
+//! clips may be played without a render callback.
+//!
+//! The **ScheduledSoundPlayer** wrapper creates such a unit and exposes the scheduling methods
+//! directly, e.g. for metronome clicks or stingers that must play gaplessly.
+
+use super::AudioUnit;
+use super::{Element, GeneratorType, Scope};
+use crate::error::Error;
+use std::mem;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use sys;
+
+bitflags! {
+    /// Flags describing how a **ScheduledAudioSlice** is played, which are also updated by the
+    /// unit as the slice is played.
+    pub struct ScheduledAudioSliceFlags: u32 {
+        /// Set by the unit once it is done with the slice.
+        const COMPLETE = sys::kScheduledAudioSliceFlag_Complete;
+        /// Set by the unit once it has begun to play the slice.
+        const BEGAN_TO_RENDER = sys::kScheduledAudioSliceFlag_BeganToRender;
+        /// Set by the unit if the slice began to play after its time stamp.
+        const BEGAN_TO_RENDER_LATE = sys::kScheduledAudioSliceFlag_BeganToRenderLate;
+        /// Loop the slice until it is interrupted.
+        ///
+        /// **Available** in OS X v10.10 and later.
+        const LOOP = sys::kScheduledAudioSliceFlag_Loop;
+        /// Interrupt any slice that is playing when this slice begins.
+        ///
+        /// **Available** in OS X v10.10 and later.
+        const INTERRUPT = sys::kScheduledAudioSliceFlag_Interrupt;
+        /// Interrupt any looping slice at the end of its current loop when this slice begins.
+        ///
+        /// **Available** in OS X v10.10 and later.
+        const INTERRUPT_AT_LOOP = sys::kScheduledAudioSliceFlag_InterruptAtLoop;
+    }
+}
+
+/// The type of closure called once the unit is done with a slice.
+type CompletionFn = dyn FnOnce() + Send;
+
+/// A buffer of non-interleaved `f32` audio to be played at a given sample time.
+///
+/// The number of channels must match the output stream format of the unit.
+pub struct ScheduledAudioSlice {
+    sample_time: f64,
+    flags: ScheduledAudioSliceFlags,
+    channels: Vec<Vec<f32>>,
+    completion: Option<Box<CompletionFn>>,
+}
+
+impl ScheduledAudioSlice {
+    /// A slice playing the given `channels` at `sample_time`, relative to the schedule start
+    /// time.
+    ///
+    /// Returns `Error::BufferLengthMismatch` if the channels differ in length.
+    pub fn new(sample_time: f64, channels: Vec<Vec<f32>>) -> Result<Self, Error> {
+        let num_frames = channels.first().map_or(0, |channel| channel.len());
+        if channels.iter().any(|channel| channel.len() != num_frames) {
+            return Err(Error::BufferLengthMismatch);
+        }
+        Ok(ScheduledAudioSlice {
+            sample_time,
+            flags: ScheduledAudioSliceFlags::empty(),
+            channels,
+            completion: None,
+        })
+    }
+
+    /// Set the flags controlling how the slice is played, e.g. `ScheduledAudioSliceFlags::LOOP`.
+    pub fn flags(mut self, flags: ScheduledAudioSliceFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// A closure to call once the unit is done with the slice.
+    ///
+    /// The closure is called on the render thread, so it must not block.
+    pub fn on_complete<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.completion = Some(Box::new(f));
+        self
+    }
+
+    /// The number of frames in the slice.
+    pub fn num_frames(&self) -> usize {
+        self.channels.first().map_or(0, |channel| channel.len())
+    }
+}
+
+/// A slice that has been handed to the unit, kept alive until the unit is done with it.
+///
+/// The unit holds on to a pointer to the `slice` and updates its flags as it plays, so this must
+/// not move while scheduled.
+pub(crate) struct ScheduledSlice {
+    slice: sys::ScheduledAudioSlice,
+    // The samples of each channel, pointed to by the buffer list. Only kept alive for the unit.
+    _channels: Vec<Vec<f32>>,
+    // Storage for the variable length `AudioBufferList` pointed to by the `slice`.
+    _buffer_list: Vec<u64>,
+    completion: Option<Box<CompletionFn>>,
+    // Set once the completion proc has returned, after which the slice may be freed.
+    done: AtomicBool,
+}
+
+/// A **ScheduledSoundPlayer** unit, playing in-memory buffers at scheduled sample times.
+///
+/// Slices that are still scheduled when the wrapper is consumed via `into_audio_unit` remain
+/// owned by the **AudioUnit**, which frees them once the unit is done with them or when it is
+/// dropped.
+///
+/// **Available** in OS X v10.4 and later.
+pub struct ScheduledSoundPlayer {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(ScheduledSoundPlayer);
+
+impl ScheduledSoundPlayer {
+    /// Create and initialize a new **ScheduledSoundPlayer** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(GeneratorType::ScheduledSoundPlayer)?;
+        Ok(ScheduledSoundPlayer { audio_unit })
+    }
+
+    /// Schedule a slice for playback, calling its completion closure once the unit is done with
+    /// it.
+    ///
+    /// See [**AudioUnit::schedule_audio_slice**](../struct.AudioUnit.html#method.schedule_audio_slice).
+    pub fn schedule_slice(&mut self, slice: ScheduledAudioSlice) -> Result<(), Error> {
+        self.audio_unit.schedule_audio_slice(slice)
+    }
+
+    /// Set the time at which the schedule starts, to which the sample times of the slices are
+    /// relative.
+    ///
+    /// See [**AudioUnit::set_schedule_start_time**](../struct.AudioUnit.html#method.set_schedule_start_time).
+    pub fn set_start_timestamp(&mut self, time_stamp: &sys::AudioTimeStamp) -> Result<(), Error> {
+        self.audio_unit.set_schedule_start_time(time_stamp)
+    }
+}
+
+impl AudioUnit {
+    /// Schedule a slice for playback on a **ScheduledSoundPlayer** unit via
+    /// `kAudioUnitProperty_ScheduleAudioSlice`.
+    ///
+    /// The **AudioUnit** takes ownership of the slice's buffer until the unit is done with it,
+    /// after which it is freed on a later call to this method or when the **AudioUnit** is
+    /// dropped. Slices only play once a start time has been set via
+    /// [**set_schedule_start_time**](./struct.AudioUnit.html#method.set_schedule_start_time).
+    ///
+    /// **Available** in OS X v10.4 and later.
+    pub fn schedule_audio_slice(&mut self, slice: ScheduledAudioSlice) -> Result<(), Error> {
+        self.free_completed_slices();
+
+        let ScheduledAudioSlice {
+            sample_time,
+            flags,
+            mut channels,
+            completion,
+        } = slice;
+        let num_frames = channels.first().map_or(0, |channel| channel.len());
+        let num_buffers = channels.len();
+        let list_bytes = mem::size_of::<sys::AudioBufferList>()
+            + num_buffers.saturating_sub(1) * mem::size_of::<sys::AudioBuffer>();
+        let mut buffer_list = vec![0u64; list_bytes / mem::size_of::<u64>() + 1];
+        unsafe {
+            let list = buffer_list.as_mut_ptr() as *mut sys::AudioBufferList;
+            (*list).mNumberBuffers = num_buffers as u32;
+            let list_buffers = (*list).mBuffers.as_mut_ptr();
+            for (i, channel) in channels.iter_mut().enumerate() {
+                *list_buffers.add(i) = sys::AudioBuffer {
+                    mNumberChannels: 1,
+                    mDataByteSize: (channel.len() * mem::size_of::<f32>()) as u32,
+                    mData: channel.as_mut_ptr() as *mut c_void,
+                };
+            }
+        }
+
+        let mut time_stamp: sys::AudioTimeStamp = unsafe { mem::zeroed() };
+        time_stamp.mSampleTime = sample_time;
+        time_stamp.mFlags = sys::kAudioTimeStampSampleTimeValid;
+        let mut scheduled = Box::new(ScheduledSlice {
+            slice: sys::ScheduledAudioSlice {
+                mTimeStamp: time_stamp,
+                mCompletionProc: Some(slice_completion_proc),
+                mCompletionProcUserData: std::ptr::null_mut(),
+                mFlags: flags.bits(),
+                mReserved: 0,
+                mReserved2: std::ptr::null_mut(),
+                mNumberFrames: num_frames as u32,
+                mBufferList: buffer_list.as_mut_ptr() as *mut sys::AudioBufferList,
+            },
+            _channels: channels,
+            _buffer_list: buffer_list,
+            completion,
+            done: AtomicBool::new(false),
+        });
+        scheduled.slice.mCompletionProcUserData = &mut *scheduled as *mut ScheduledSlice as *mut _;
+
+        let id = sys::kAudioUnitProperty_ScheduleAudioSlice;
+        self.set_property(id, Scope::Global, Element::Output, Some(&scheduled.slice))?;
+        // Relinquish ownership of the slice until the unit is done with it.
+        self.scheduled_slices.push(Box::into_raw(scheduled));
+        Ok(())
+    }
+
+    /// Set the time at which the schedule of a **ScheduledSoundPlayer** unit starts via
+    /// `kAudioUnitProperty_ScheduleStartTimeStamp`.
+    ///
+    /// The sample times of scheduled slices are relative to this time. A time stamp with a
+    /// sample time of `-1.0` starts the schedule on the next render cycle.
+    ///
+    /// **Available** in OS X v10.4 and later.
+    pub fn set_schedule_start_time(
+        &mut self,
+        time_stamp: &sys::AudioTimeStamp,
+    ) -> Result<(), Error> {
+        let id = sys::kAudioUnitProperty_ScheduleStartTimeStamp;
+        self.set_property(id, Scope::Global, Element::Output, Some(time_stamp))
+    }
+
+    /// Free the scheduled slices that the unit is done with.
+    fn free_completed_slices(&mut self) {
+        self.scheduled_slices.retain(|&scheduled| unsafe {
+            if !(*scheduled).done.load(Ordering::Acquire) {
+                return true;
+            }
+            let _: Box<ScheduledSlice> = Box::from_raw(scheduled);
+            false
+        });
+    }
+
+    /// Free all scheduled slices, first resetting the unit so that it is done with any that are
+    /// still playing.
+    pub(crate) fn free_scheduled_slices(&mut self) {
+        self.free_completed_slices();
+        if self.scheduled_slices.is_empty() {
+            return;
+        }
+        unsafe {
+            // We can't do anything useful about a failure here.
+            sys::AudioUnitReset(self.instance, Scope::Global as u32, 0);
+            for scheduled in self.scheduled_slices.drain(..) {
+                let _: Box<ScheduledSlice> = Box::from_raw(scheduled);
+            }
+        }
+    }
+}
+
+/// Called by the unit once it is done with a slice.
+extern "C" fn slice_completion_proc(user_data: *mut c_void, _slice: *mut sys::ScheduledAudioSlice) {
+    let scheduled = unsafe { &mut *(user_data as *mut ScheduledSlice) };
+    if let Some(completion) = scheduled.completion.take() {
+        completion();
+    }
+    scheduled.done.store(true, Ordering::Release);
+}
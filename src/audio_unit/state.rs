@@ -0,0 +1,104 @@
+//! Saving and restoring the state of an **AudioUnit** via its class info property list.
+//!
+//! The class info of an **AudioUnit** captures its parameters and any other state the unit
+//! considers part of a preset. It is stored as a serialized property list so that it may be
+//! written to disk or embedded within a larger document.
+
+use super::{AudioUnit, Element, Scope};
+use crate::error::Error;
+use core_foundation_sys::base::{kCFAllocatorDefault, CFRelease};
+use core_foundation_sys::data::{CFDataCreate, CFDataGetBytePtr, CFDataGetLength};
+use core_foundation_sys::propertylist::{
+    kCFPropertyListBinaryFormat_v1_0, kCFPropertyListImmutable, CFPropertyListCreateData,
+    CFPropertyListCreateWithData, CFPropertyListRef,
+};
+use std::os::raw::c_void;
+use std::ptr;
+use std::slice;
+use sys;
+
+impl AudioUnit {
+    /// Save the state of the **AudioUnit** as a serialized (binary) property list via
+    /// `kAudioUnitProperty_ClassInfo`.
+    ///
+    /// The state may be restored later via
+    /// [**restore_state**](./struct.AudioUnit.html#method.restore_state).
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn save_state(&self) -> Result<Vec<u8>, Error> {
+        let plist: CFPropertyListRef = self.get_property(
+            sys::kAudioUnitProperty_ClassInfo,
+            Scope::Global,
+            Element::Output,
+        )?;
+        if plist.is_null() {
+            return Err(Error::Unspecified);
+        }
+        unsafe {
+            let data = CFPropertyListCreateData(
+                kCFAllocatorDefault,
+                plist,
+                kCFPropertyListBinaryFormat_v1_0,
+                0,
+                ptr::null_mut(),
+            );
+            CFRelease(plist);
+            if data.is_null() {
+                return Err(Error::Unspecified);
+            }
+            let bytes =
+                slice::from_raw_parts(CFDataGetBytePtr(data), CFDataGetLength(data) as usize);
+            let bytes = bytes.to_vec();
+            CFRelease(data as *const c_void);
+            Ok(bytes)
+        }
+    }
+
+    /// Restore state previously saved via
+    /// [**save_state**](./struct.AudioUnit.html#method.save_state) by setting
+    /// `kAudioUnitProperty_ClassInfo`.
+    ///
+    /// **plist** may be a property list in any of the formats understood by Core Foundation.
+    ///
+    /// **Available** in OS X v10.0 and later.
+    pub fn restore_state(&mut self, plist: &[u8]) -> Result<(), Error> {
+        self.set_class_info(sys::kAudioUnitProperty_ClassInfo, plist)
+    }
+
+    /// Restore state that was saved within a larger document, such as a host's session file, by
+    /// setting `kAudioUnitProperty_ClassInfoFromDocument`.
+    ///
+    /// This differs from [**restore_state**](./struct.AudioUnit.html#method.restore_state) in
+    /// that the unit knows the state comes from a document, which may affect how it resolves
+    /// resources referenced by the state, e.g. relative file paths.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn restore_state_from_document(&mut self, plist: &[u8]) -> Result<(), Error> {
+        self.set_class_info(sys::kAudioUnitProperty_ClassInfoFromDocument, plist)
+    }
+
+    /// Deserialize the given property list and set it as the class info property with the
+    /// given id.
+    fn set_class_info(&mut self, id: u32, plist: &[u8]) -> Result<(), Error> {
+        unsafe {
+            let data = CFDataCreate(kCFAllocatorDefault, plist.as_ptr(), plist.len() as _);
+            if data.is_null() {
+                return Err(Error::Unspecified);
+            }
+            let class_info = CFPropertyListCreateWithData(
+                kCFAllocatorDefault,
+                data,
+                kCFPropertyListImmutable,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            CFRelease(data as *const c_void);
+            if class_info.is_null() {
+                return Err(Error::Unspecified);
+            }
+            let result = self.set_property(id, Scope::Global, Element::Output, Some(&class_info));
+            CFRelease(class_info);
+            result
+        }
+    }
+}
@@ -141,4 +141,197 @@ impl StreamFormat {
             mReserved: 0,
         }
     }
+
+    /// The same as [**to_asbd**](./struct.StreamFormat.html#method.to_asbd) but borrowing the
+    /// StreamFormat, e.g. for passing the format to other Core Audio APIs such as audio files or
+    /// converters.
+    ///
+    /// For packed LinearPCM formats, converting the resulting ASBD back via
+    /// [**from_asbd**](./struct.StreamFormat.html#method.from_asbd) yields the same StreamFormat
+    /// (with `LinearPcmFlags::IS_PACKED` set) and converting that once more yields the same ASBD.
+    pub fn as_asbd(&self) -> sys::AudioStreamBasicDescription {
+        self.to_asbd()
+    }
+
+    /// The number of bytes needed to hold `frames` frames of audio in this format, summed over
+    /// all channels, i.e. the size of the single buffer of an interleaved format.
+    ///
+    /// ```
+    /// # use coreaudio::audio_unit::{SampleFormat, StreamFormat};
+    /// # use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+    /// let format = StreamFormat {
+    ///     sample_rate: 44_100.0,
+    ///     sample_format: SampleFormat::F32,
+    ///     flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
+    ///     channels: 2,
+    /// };
+    /// assert_eq!(format.byte_size(512), 4096);
+    /// assert_eq!(format.byte_size(512), 512 * format.to_asbd().mBytesPerFrame as usize);
+    /// ```
+    pub fn byte_size(&self, frames: u32) -> usize {
+        self.bytes_per_channel(frames) * self.channels as usize
+    }
+
+    /// The number of bytes needed to hold `frames` frames of a single channel, i.e. the size of
+    /// each buffer of a non-interleaved format.
+    ///
+    /// ```
+    /// # use coreaudio::audio_unit::{SampleFormat, StreamFormat};
+    /// # use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+    /// let format = StreamFormat {
+    ///     sample_rate: 48_000.0,
+    ///     sample_format: SampleFormat::I16,
+    ///     flags: LinearPcmFlags::IS_SIGNED_INTEGER | LinearPcmFlags::IS_NON_INTERLEAVED,
+    ///     channels: 2,
+    /// };
+    /// assert_eq!(format.bytes_per_channel(512), 1024);
+    /// assert_eq!(format.bytes_per_channel(512), 512 * format.to_asbd().mBytesPerFrame as usize);
+    /// ```
+    pub fn bytes_per_channel(&self, frames: u32) -> usize {
+        frames as usize * self.sample_format.size_in_bytes()
+    }
+
+    /// Describe a compressed format by its format ID, e.g. `kAudioFormatMPEG4AAC`, for use with
+    /// APIs that handle encoded audio such as converters and audio files.
+    ///
+    /// See [**CompressedStreamFormat::new**](./struct.CompressedStreamFormat.html#method.new).
+    pub fn compressed(
+        format_id: u32,
+        sample_rate: f64,
+        channels: u32,
+    ) -> Result<CompressedStreamFormat, Error> {
+        CompressedStreamFormat::new(format_id, sample_rate, channels)
+    }
+}
+
+/// A representation of an AudioStreamBasicDescription describing compressed (encoded) audio.
+///
+/// Unlike the linear PCM **StreamFormat**, the packet layout of a compressed format is specific
+/// to the codec, so the packet and frame sizes are left to be filled in by Core Audio, e.g. via
+/// `kAudioFormatProperty_FormatInfo` or by a converter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CompressedStreamFormat {
+    /// The number of frames of audio data per second of the decoded signal.
+    pub sample_rate: f64,
+    /// The format ID, e.g. `kAudioFormatMPEG4AAC`.
+    pub format_id: u32,
+    /// The format specific flags, e.g. the MPEG-4 object type for AAC, or `0` if none.
+    pub format_flags: u32,
+    /// The number of channels.
+    pub channels: u32,
+}
+
+impl CompressedStreamFormat {
+    /// Describe a compressed format by its format ID, without any format specific flags.
+    ///
+    /// Returns `Error::UnsupportedStreamFormat` if the format ID is that of linear PCM, which is
+    /// described by **StreamFormat** instead, or if the sample rate or channel count is zero.
+    pub fn new(format_id: u32, sample_rate: f64, channels: u32) -> Result<Self, Error> {
+        let linear_pcm = AudioFormat::LinearPCM(LinearPcmFlags::empty()).as_format_and_flag();
+        if format_id == linear_pcm.0 || sample_rate <= 0.0 || channels == 0 {
+            return Err(Error::UnsupportedStreamFormat);
+        }
+        Ok(CompressedStreamFormat {
+            sample_rate,
+            format_id,
+            format_flags: 0,
+            channels,
+        })
+    }
+
+    /// The type-safe representation of the format ID and flags, if known.
+    pub fn audio_format(&self) -> Option<AudioFormat> {
+        // Some formats require flags while for others `0` is not a valid flag.
+        let flag = if self.format_flags == 0 {
+            None
+        } else {
+            Some(self.format_flags)
+        };
+        AudioFormat::from_format_and_flag(self.format_id, flag)
+    }
+
+    /// Convert an AudioStreamBasicDescription into a CompressedStreamFormat.
+    ///
+    /// Returns `Error::UnsupportedStreamFormat` if the ASBD describes linear PCM.
+    pub fn from_asbd(asbd: sys::AudioStreamBasicDescription) -> Result<Self, Error> {
+        let mut format =
+            CompressedStreamFormat::new(asbd.mFormatID, asbd.mSampleRate, asbd.mChannelsPerFrame)?;
+        format.format_flags = asbd.mFormatFlags;
+        Ok(format)
+    }
+
+    /// Convert a CompressedStreamFormat into an AudioStreamBasicDescription.
+    ///
+    /// The packet and frame sizes are left as `0`, meaning that they are variable or unknown.
+    pub fn to_asbd(self) -> sys::AudioStreamBasicDescription {
+        sys::AudioStreamBasicDescription {
+            mSampleRate: self.sample_rate,
+            mFormatID: self.format_id,
+            mFormatFlags: self.format_flags,
+            mBytesPerPacket: 0,
+            mFramesPerPacket: 0,
+            mBytesPerFrame: 0,
+            mChannelsPerFrame: self.channels,
+            mBitsPerChannel: 0,
+            mReserved: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::audio_format::LinearPcmFlags;
+    use super::super::SampleFormat;
+    use super::StreamFormat;
+
+    fn format(sample_format: SampleFormat, flags: LinearPcmFlags, channels: u32) -> StreamFormat {
+        StreamFormat {
+            sample_rate: 44_100.0,
+            sample_format,
+            flags,
+            channels,
+        }
+    }
+
+    #[test]
+    fn byte_size_of_interleaved_stereo_i16() {
+        let format = format(SampleFormat::I16, LinearPcmFlags::IS_SIGNED_INTEGER, 2);
+        let asbd = format.to_asbd();
+        assert_eq!(asbd.mBytesPerFrame, 4);
+        assert_eq!(format.bytes_per_channel(256), 512);
+        assert_eq!(format.byte_size(256), 1024);
+        assert_eq!(format.byte_size(256), asbd.mBytesPerFrame as usize * 256);
+    }
+
+    #[test]
+    fn byte_size_of_non_interleaved_stereo_f32() {
+        let flags = LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_NON_INTERLEAVED;
+        let format = format(SampleFormat::F32, flags, 2);
+        let asbd = format.to_asbd();
+        // Each buffer of a non-interleaved format holds a single channel.
+        assert_eq!(asbd.mBytesPerFrame, 4);
+        assert_eq!(
+            format.bytes_per_channel(256),
+            asbd.mBytesPerFrame as usize * 256
+        );
+        assert_eq!(format.byte_size(256), 2048);
+    }
+
+    #[test]
+    fn byte_size_of_mono() {
+        for &sample_format in &[SampleFormat::I16, SampleFormat::F32] {
+            let format = format(sample_format, LinearPcmFlags::empty(), 1);
+            let asbd = format.to_asbd();
+            assert_eq!(asbd.mBytesPerFrame as usize, sample_format.size_in_bytes());
+            assert_eq!(format.byte_size(100), asbd.mBytesPerFrame as usize * 100);
+            assert_eq!(format.byte_size(100), format.bytes_per_channel(100));
+        }
+    }
+
+    #[test]
+    fn byte_size_of_no_frames() {
+        let format = format(SampleFormat::F32, LinearPcmFlags::IS_FLOAT, 2);
+        assert_eq!(format.byte_size(0), 0);
+        assert_eq!(format.bytes_per_channel(0), 0);
+    }
 }
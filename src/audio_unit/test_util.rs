@@ -0,0 +1,84 @@
+//! Helpers shared by the tests that render audio through units offline, i.e. by pulling their
+//! output via `AudioUnitRender` rather than playing it through a device.
+
+use super::audio_format::LinearPcmFlags;
+use super::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
+use crate::error::Error;
+use std::mem;
+use std::os::raw::c_void;
+use sys;
+
+/// Render `frames` frames from output bus `0` of `unit` in slices of `slice_frames` frames,
+/// returning the samples of its first channel.
+///
+/// The output must be non-interleaved `f32`, the default format of Apple's effect units.
+pub fn render_offline(
+    unit: &AudioUnit,
+    frames: usize,
+    slice_frames: u32,
+) -> Result<Vec<f32>, Error> {
+    let mut channels = render_offline_channels(unit, frames, slice_frames)?;
+    Ok(channels.swap_remove(0))
+}
+
+/// The same as `render_offline`, but returning the samples of every channel.
+pub fn render_offline_channels(
+    unit: &AudioUnit,
+    frames: usize,
+    slice_frames: u32,
+) -> Result<Vec<Vec<f32>>, Error> {
+    let id = sys::kAudioUnitProperty_StreamFormat;
+    let asbd = unit.get_property(id, Scope::Output, Element::Output)?;
+    let format = StreamFormat::from_asbd(asbd)?;
+    assert_eq!(format.sample_format, SampleFormat::F32);
+    assert!(format.flags.contains(LinearPcmFlags::IS_NON_INTERLEAVED));
+
+    let channels = format.channels as usize;
+    let mut buffers = vec![vec![0f32; slice_frames as usize]; channels];
+    // Storage for the variable length `AudioBufferList`, with one buffer per channel.
+    let list_bytes = mem::size_of::<sys::AudioBufferList>()
+        + channels.saturating_sub(1) * mem::size_of::<sys::AudioBuffer>();
+    let mut list_storage = vec![0u64; list_bytes / mem::size_of::<u64>() + 1];
+    let list = list_storage.as_mut_ptr() as *mut sys::AudioBufferList;
+
+    let mut time_stamp: sys::AudioTimeStamp = unsafe { mem::zeroed() };
+    time_stamp.mFlags = sys::kAudioTimeStampSampleTimeValid;
+    let mut rendered = vec![Vec::with_capacity(frames); channels];
+    let mut position = 0;
+    while position < frames {
+        let slice = (frames - position).min(slice_frames as usize);
+        unsafe {
+            (*list).mNumberBuffers = channels as u32;
+            let list_buffers = (*list).mBuffers.as_mut_ptr();
+            for (i, buffer) in buffers.iter_mut().enumerate() {
+                *list_buffers.add(i) = sys::AudioBuffer {
+                    mNumberChannels: 1,
+                    mDataByteSize: (slice * mem::size_of::<f32>()) as u32,
+                    mData: buffer.as_mut_ptr() as *mut c_void,
+                };
+            }
+            let mut flags: sys::AudioUnitRenderActionFlags = 0;
+            Error::from_os_status(sys::AudioUnitRender(
+                unit.as_raw(),
+                &mut flags as *mut _,
+                &time_stamp as *const _,
+                0,
+                slice as u32,
+                list,
+            ))?;
+        }
+        for (channel, buffer) in rendered.iter_mut().zip(&buffers) {
+            channel.extend_from_slice(&buffer[..slice]);
+        }
+        position += slice;
+        time_stamp.mSampleTime += slice as f64;
+    }
+    Ok(rendered)
+}
+
+/// The sample rate of output bus `0` of `unit`.
+pub fn output_sample_rate(unit: &AudioUnit) -> Result<f64, Error> {
+    let id = sys::kAudioUnitProperty_StreamFormat;
+    let asbd = unit.get_property(id, Scope::Output, Element::Output)?;
+    Ok(StreamFormat::from_asbd(asbd)?.sample_rate)
+}
@@ -0,0 +1,141 @@
+//! Wrappers around Apple's units for changing the playback rate and pitch of audio.
+//!
+//! The rate of these units determines how quickly they pull audio from their input, e.g. a rate
+//! of `2.0` consumes two seconds of input per second of output.
+
+use super::{AudioUnit, Element, FormatConverterType, Scope};
+use crate::error::Error;
+use sys;
+
+/// A **Varispeed** unit, changing the playback rate and with it the pitch of the audio, like
+/// changing the speed of a tape.
+///
+/// **Available** in OS X v10.3 and later.
+pub struct Varispeed {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(Varispeed, render_callback);
+
+impl Varispeed {
+    /// Create and initialize a new **Varispeed** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(FormatConverterType::Varispeed)?;
+        Ok(Varispeed { audio_unit })
+    }
+
+    /// The playback rate, where `1.0` is the original speed.
+    pub fn playback_rate(&self) -> Result<f32, Error> {
+        let id = sys::kVarispeedParam_PlaybackRate;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// Set the playback rate, where `1.0` is the original speed.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the rate is outside of the range supported
+    /// by the unit, typically `0.25` to `4.0`.
+    pub fn set_playback_rate(&mut self, rate: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kVarispeedParam_PlaybackRate, rate)
+    }
+
+    /// The playback rate expressed as the resulting change in pitch in cents.
+    pub fn playback_cents(&self) -> Result<f32, Error> {
+        let id = sys::kVarispeedParam_PlaybackCents;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// Set the playback rate by the resulting change in pitch in cents, e.g. `1200.0` to play
+    /// an octave higher at double speed.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the value is outside of the range supported
+    /// by the unit, typically `-2400.0` to `2400.0`.
+    pub fn set_playback_cents(&mut self, cents: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kVarispeedParam_PlaybackCents, cents)
+    }
+}
+
+/// A **NewTimePitch** unit, changing the playback rate and the pitch of the audio independently
+/// of each other, e.g. to speed up speech without raising its pitch.
+///
+/// The unit analyses its input in overlapping windows and so delays the audio passing through
+/// it. Hosts that keep several streams in sync should compensate for the
+/// [**latency**](./struct.NewTimePitch.html#method.latency) it reports.
+///
+/// **Available** in OS X v10.9 and later.
+pub struct NewTimePitch {
+    audio_unit: AudioUnit,
+}
+
+impl_audio_unit_wrapper!(NewTimePitch, render_callback);
+
+impl NewTimePitch {
+    /// Create and initialize a new **NewTimePitch** unit.
+    pub fn new() -> Result<Self, Error> {
+        let audio_unit = AudioUnit::new(FormatConverterType::NewTimePitch)?;
+        Ok(NewTimePitch { audio_unit })
+    }
+
+    /// The playback rate, where `1.0` is the original speed.
+    pub fn rate(&self) -> Result<f32, Error> {
+        let id = sys::kNewTimePitchParam_Rate;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// Set the playback rate without affecting the pitch, where `1.0` is the original speed.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the rate is outside of the range supported
+    /// by the unit, typically `1.0 / 32.0` to `32.0`.
+    pub fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kNewTimePitchParam_Rate, rate)
+    }
+
+    /// The shift in pitch in cents.
+    pub fn pitch_cents(&self) -> Result<f32, Error> {
+        let id = sys::kNewTimePitchParam_Pitch;
+        self.audio_unit
+            .parameter(id, Scope::Global, Element::Output)
+    }
+
+    /// Shift the pitch by the given number of cents without affecting the playback rate.
+    ///
+    /// Returns `Error::ParameterValueOutOfRange` if the value is outside of the range supported
+    /// by the unit, typically `-2400.0` to `2400.0`.
+    pub fn set_pitch_cents(&mut self, cents: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kNewTimePitchParam_Pitch, cents)
+    }
+
+    /// Set the amount of overlap between the analysis windows.
+    ///
+    /// Higher values improve the quality at the cost of CPU time. Returns
+    /// `Error::ParameterValueOutOfRange` if the value is outside of the range supported by the
+    /// unit, typically `3.0` to `32.0`.
+    pub fn set_overlap(&mut self, overlap: f32) -> Result<(), Error> {
+        self.audio_unit
+            .set_global_parameter_checked(sys::kNewTimePitchParam_Overlap, overlap)
+    }
+
+    /// Enable or disable peak locking, which reduces the phasiness of the output at the cost of
+    /// CPU time. Enabled by default.
+    pub fn set_peak_locking_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+        let value = if enabled { 1.0 } else { 0.0 };
+        self.audio_unit
+            .set_global_parameter_checked(sys::kNewTimePitchParam_EnablePeakLocking, value)
+    }
+
+    /// The latency of the unit in seconds, as reported via `kAudioUnitProperty_Latency`.
+    ///
+    /// Audio leaves the unit delayed by this amount, so hosts should offset any streams played
+    /// alongside it accordingly.
+    pub fn latency(&self) -> Result<f64, Error> {
+        let id = sys::kAudioUnitProperty_Latency;
+        self.audio_unit
+            .get_property(id, Scope::Global, Element::Output)
+    }
+}
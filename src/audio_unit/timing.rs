@@ -0,0 +1,184 @@
+//! Measuring the intervals at which the render callback is called, for diagnosing dropouts caused
+//! by late callbacks.
+//!
+//! The time between the start of consecutive render cycles is recorded into a histogram that can
+//! be read from any thread via a **CallbackTiming** handle, as returned by
+//! [**set_render_callback_with_timing**](../struct.AudioUnit.html#method.set_render_callback_with_timing).
+//! Recording neither allocates nor waits, so it is safe on the render thread.
+
+use super::render_callback::{Args, Data};
+use super::AudioUnit;
+use crate::error::Error;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The width of each bin of the histogram in nanoseconds.
+const BIN_WIDTH_NANOS: u64 = 50_000;
+/// The number of bins of the histogram, covering intervals of up to 25.6ms. Longer intervals are
+/// counted in the last bin.
+const BIN_COUNT: usize = 512;
+
+#[repr(C)]
+struct MachTimebaseInfo {
+    numer: u32,
+    denom: u32,
+}
+
+extern "C" {
+    fn mach_absolute_time() -> u64;
+    fn mach_timebase_info(info: *mut MachTimebaseInfo) -> i32;
+}
+
+/// The intervals recorded so far, shared between the render callback and the handles.
+struct TimingState {
+    bins: Box<[AtomicU32]>,
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+    min_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl TimingState {
+    fn new() -> Self {
+        TimingState {
+            bins: (0..BIN_COUNT).map(|_| AtomicU32::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+            min_nanos: AtomicU64::new(u64::MAX),
+            max_nanos: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, nanos: u64) {
+        let bin = ((nanos / BIN_WIDTH_NANOS) as usize).min(BIN_COUNT - 1);
+        self.bins[bin].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.min_nanos.fetch_min(nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// A handle for reading the intervals between render callbacks, as recorded since the callback
+/// was installed or the handle was last reset.
+///
+/// The statistics are read while the render thread may be recording, so they may be off by the
+/// most recent interval.
+#[derive(Clone)]
+pub struct CallbackTiming {
+    state: Arc<TimingState>,
+}
+
+impl CallbackTiming {
+    /// The number of intervals recorded.
+    pub fn count(&self) -> u64 {
+        self.state.count.load(Ordering::Acquire)
+    }
+
+    /// The shortest interval recorded, if any.
+    pub fn min(&self) -> Option<Duration> {
+        match self.state.min_nanos.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// The longest interval recorded, if any.
+    pub fn max(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            _ => Some(Duration::from_nanos(
+                self.state.max_nanos.load(Ordering::Relaxed),
+            )),
+        }
+    }
+
+    /// The mean of the intervals recorded, if any.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => {
+                let sum = self.state.sum_nanos.load(Ordering::Relaxed);
+                Some(Duration::from_nanos(sum / count))
+            }
+        }
+    }
+
+    /// The interval below which the given `percentile`, from `0.0` to `100.0`, of the recorded
+    /// intervals lie, if any were recorded.
+    ///
+    /// The interval is rounded up to the 50µs resolution of the histogram. Intervals longer than
+    /// 25.6ms are reported as the longest interval recorded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let counts: Vec<u64> = self
+            .state
+            .bins
+            .iter()
+            .map(|bin| bin.load(Ordering::Relaxed) as u64)
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let target = ((percentile.clamp(0.0, 100.0) / 100.0) * total as f64).ceil() as u64;
+        let mut seen = 0;
+        for (bin, &count) in counts.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                if bin == BIN_COUNT - 1 {
+                    return self.max();
+                }
+                return Some(Duration::from_nanos((bin as u64 + 1) * BIN_WIDTH_NANOS));
+            }
+        }
+        self.max()
+    }
+
+    /// Clear the recorded intervals, e.g. after the audio device has been reconfigured.
+    pub fn reset(&self) {
+        let state = &self.state;
+        for bin in state.bins.iter() {
+            bin.store(0, Ordering::Relaxed);
+        }
+        state.sum_nanos.store(0, Ordering::Relaxed);
+        state.min_nanos.store(u64::MAX, Ordering::Relaxed);
+        state.max_nanos.store(0, Ordering::Relaxed);
+        state.count.store(0, Ordering::Release);
+    }
+}
+
+impl AudioUnit {
+    /// Pass a render callback, recording the interval between the start of consecutive render
+    /// cycles into a histogram read via the returned **CallbackTiming** handle.
+    ///
+    /// Intervals are measured with `mach_absolute_time` at the top of each callback, before `f`
+    /// is called. An interval well above `num_frames / sample_rate` indicates that a callback
+    /// arrived late, e.g. due to an overloaded render thread.
+    pub fn set_render_callback_with_timing<F, D>(
+        &mut self,
+        mut f: F,
+    ) -> Result<CallbackTiming, Error>
+    where
+        F: FnMut(Args<D>) -> Result<(), ()> + 'static,
+        D: Data,
+    {
+        let mut timebase = MachTimebaseInfo { numer: 0, denom: 0 };
+        unsafe { mach_timebase_info(&mut timebase as *mut _) };
+        let (numer, denom) = (timebase.numer as u128, timebase.denom.max(1) as u128);
+
+        let state = Arc::new(TimingState::new());
+        let callback_state = state.clone();
+        let mut last_ticks: Option<u64> = None;
+        self.set_render_callback(move |args: Args<D>| {
+            let ticks = unsafe { mach_absolute_time() };
+            if let Some(last_ticks) = last_ticks {
+                let elapsed = ticks.saturating_sub(last_ticks) as u128;
+                callback_state.record((elapsed * numer / denom) as u64);
+            }
+            last_ticks = Some(ticks);
+            f(args)
+        })?;
+        Ok(CallbackTiming { state })
+    }
+}
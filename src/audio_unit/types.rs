@@ -1,6 +1,10 @@
 //! Core Audio's various const audio unit types identifiers represented as typesafe enums.
 //!
 //! Oirginal documentation [here](https://developer.apple.com/library/prerelease/mac/documentation/AudioUnit/Reference/AUComponentServicesReference/index.html#//apple_ref/doc/constant_group/Audio_Unit_Types).
+//!
+//! **Breaking change:** the **EffectType**, **MixerType**, **GeneratorType** and
+//! **MusicDeviceType** enums have a `Custom(u32)` variant for subtypes that are not listed, so
+//! their codes can no longer be read via an `as u32` cast. Use their `as_u32` methods instead.
 
 /// Represents the different kinds of Audio Units that are available.
 ///
@@ -111,15 +115,36 @@ impl Type {
         }
     }
 
+    /// Convert a type code and subtype code, e.g. from a component description, into a `Type`.
+    ///
+    /// Returns `None` if the type is unknown, or if the subtype is unknown for types whose
+    /// subtype enum has no `Custom` variant.
+    pub fn from_u32(ty: u32, sub_type: u32) -> Option<Type> {
+        let ty = match ty {
+            1635086197 => Type::IO(IOType::from_u32(sub_type)?),
+            1635085685 => Type::MusicDevice(MusicDeviceType::from_u32(sub_type)),
+            1635085670 => Type::MusicEffect,
+            1635083875 => Type::FormatConverter(FormatConverterType::from_u32(sub_type)?),
+            1635083896 => Type::Effect(EffectType::from_u32(sub_type)),
+            1635085688 => Type::Mixer(MixerType::from_u32(sub_type)),
+            1635086446 => Type::Panner,
+            1635084142 => Type::Generator(GeneratorType::from_u32(sub_type)),
+            1635086188 => Type::OfflineEffect,
+            1635085673 => Type::MidiProcessor,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
     /// Convert the `Type` to the const `u32` that is associated with its subtype.
     pub fn as_subtype_u32(&self) -> Option<u32> {
         match *self {
             Type::IO(ty) => Some(ty as u32),
-            Type::MusicDevice(ty) => Some(ty as u32),
+            Type::MusicDevice(ty) => Some(ty.as_u32()),
             Type::FormatConverter(ty) => Some(ty as u32),
-            Type::Effect(ty) => Some(ty as u32),
-            Type::Mixer(ty) => Some(ty as u32),
-            Type::Generator(ty) => Some(ty as u32),
+            Type::Effect(ty) => Some(ty.as_u32()),
+            Type::Mixer(ty) => Some(ty.as_u32()),
+            Type::Generator(ty) => Some(ty.as_u32()),
             _ => None,
         }
     }
@@ -167,89 +192,146 @@ pub enum EffectType {
     /// An audio unit that enforces an upper dynamic limit on an audio signal.
     ///
     /// **Available** in OS X v10.2 and later.
-    PeakLimiter = 1819112562,
+    PeakLimiter,
     /// An audio unit that provides dynamic compression or expansion.
     ///
     /// **Available** in OS X v10.3 and later.
-    DynamicsProcessor = 1684237680,
+    DynamicsProcessor,
     /// An audio unit that passes frequencies below a specified cutoff frequency and blocks
     /// frequencies above that cutoff frequency.
     ///
     /// **Available** in OS X v10.2 and later.
-    LowPassFilter = 1819304307,
+    LowPassFilter,
     /// An audio unit that passes frequencies above a specified cutoff frequency and blocks
     /// frequencies below that cutoff frequency.
     ///
     /// **Available** in OS X v10.2 and later.
-    HighPassFilter = 1752195443,
+    HighPassFilter,
     /// An audio unit that passes frequencies between specified upper and lower cutoff frequencies,
     /// and blocks frequencies outside that band.
     ///
     /// **Available** in OS X v10.2 and later.
-    BandPassFilter = 1651532147,
+    BandPassFilter,
     /// An audio unit suitable for implementing a treble control in an audio playback or recording
     /// system.
     ///
     /// **Available** in OS X v10.2 and later.
-    HighShelfFilter = 1752393830,
+    HighShelfFilter,
     /// An audio unit suitable for implementing a bass control in an audio playback or recording
     /// system.
     ///
     /// **Available** in OS X v10.2 and later.
-    LowShelfFilter = 1819502694,
+    LowShelfFilter,
     /// An audio unit that provides a filter whose center frequency, boost/cut level, and Q can be
     /// adjusted.
     ///
     /// **Available** in OS X v10.2 and later.
-    ParametricEQ = 1886217585,
+    ParametricEQ,
     /// An audio unit that provides a distortion effect.
     ///
     /// **Available** in OS X v10.5 and later.
-    Distortion = 1684632436,
+    Distortion,
     /// An audio unit that introduces a time delay to a signal.
     ///
     /// **Available** in OS X v10.2 and later.
-    Delay = 1684368505,
+    Delay,
     /// An audio unit that provides a time delay for a specified number of samples.
     ///
     /// **Available** in OS X v10.4 and later.
-    SampleDelay = 1935961209,
+    SampleDelay,
     /// An audio unit that provides a 10- or 31-band graphic equalizer.
     ///
     /// Available in OS X v10.2 and later.
-    GraphicEQ = 1735550321,
+    GraphicEQ,
     /// An audio unit that provides four-bands of dynamic compression or expansion.
     ///
     /// **Available** in OS X v10.3 and later.
-    MultiBandCompressor = 1835232624,
+    MultiBandCompressor,
     /// An audio unit that provides a reverberation effect that can be used to simulate a variety
     /// of acoustic spaces.
     ///
     /// **Available** in OS X v10.2 and later.
-    MatrixReverb = 1836213622,
+    MatrixReverb,
     /// An audio unit for modifying the pitch of a signal.
     ///
     /// **Available** in OS X v10.4 and later.
-    Pitch = 1953329268,
+    Pitch,
     /// An audio unit that provides a combination of five filters: low-frequency, three
     /// mid-frequencies, and high-frequency.
     ///
     /// **Available** in OS X v10.4 and later.
-    AUFilter = 1718185076,
+    AUFilter,
     /// An audio unit for use in conjunction with a kAudioUnitSubType_NetReceive audio unit for
     /// sending audio across a network or from one application to another.
     ///
     /// **Available** in OS X v10.4 and later.
-    NetSend = 1853058660,
+    NetSend,
     /// An audio unit that detects gaps between segments of speech and fills the gaps with a short
     /// tone, simulating the sound of a walkie-talkie communication device.
     ///
     /// **Available** in OS X v10.5 and later.
-    RogerBeep = 1919903602,
+    RogerBeep,
     /// A multi-band equalizer with specifiable filter type for each band.
     ///
     /// **Available** in OS X v10.9 and later.
-    NBandEQ = 1851942257,
+    NBandEQ,
+    /// The subtype of an effect unit not listed above, e.g. one provided by a third party,
+    /// given as its four char code.
+    Custom(u32),
+}
+
+impl EffectType {
+    /// Convert the `EffectType` to its associated `u32` subtype code.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            EffectType::PeakLimiter => 1819112562,
+            EffectType::DynamicsProcessor => 1684237680,
+            EffectType::LowPassFilter => 1819304307,
+            EffectType::HighPassFilter => 1752195443,
+            EffectType::BandPassFilter => 1651532147,
+            EffectType::HighShelfFilter => 1752393830,
+            EffectType::LowShelfFilter => 1819502694,
+            EffectType::ParametricEQ => 1886217585,
+            EffectType::Distortion => 1684632436,
+            EffectType::Delay => 1684368505,
+            EffectType::SampleDelay => 1935961209,
+            EffectType::GraphicEQ => 1735550321,
+            EffectType::MultiBandCompressor => 1835232624,
+            EffectType::MatrixReverb => 1836213622,
+            EffectType::Pitch => 1953329268,
+            EffectType::AUFilter => 1718185076,
+            EffectType::NetSend => 1853058660,
+            EffectType::RogerBeep => 1919903602,
+            EffectType::NBandEQ => 1851942257,
+            EffectType::Custom(code) => code,
+        }
+    }
+
+    /// Convert a `u32` subtype code into the `EffectType`, or `Custom` if it is not listed.
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            1819112562 => EffectType::PeakLimiter,
+            1684237680 => EffectType::DynamicsProcessor,
+            1819304307 => EffectType::LowPassFilter,
+            1752195443 => EffectType::HighPassFilter,
+            1651532147 => EffectType::BandPassFilter,
+            1752393830 => EffectType::HighShelfFilter,
+            1819502694 => EffectType::LowShelfFilter,
+            1886217585 => EffectType::ParametricEQ,
+            1684632436 => EffectType::Distortion,
+            1684368505 => EffectType::Delay,
+            1935961209 => EffectType::SampleDelay,
+            1735550321 => EffectType::GraphicEQ,
+            1835232624 => EffectType::MultiBandCompressor,
+            1836213622 => EffectType::MatrixReverb,
+            1953329268 => EffectType::Pitch,
+            1718185076 => EffectType::AUFilter,
+            1853058660 => EffectType::NetSend,
+            1919903602 => EffectType::RogerBeep,
+            1851942257 => EffectType::NBandEQ,
+            code => EffectType::Custom(code),
+        }
+    }
 }
 
 /// Audio data format converter audio unit subtypes for **AudioUnit**s provided by Apple.
@@ -307,6 +389,23 @@ pub enum FormatConverterType {
     AUiPodTimeOther = 1768977519,
 }
 
+impl FormatConverterType {
+    /// Convert a `u32` subtype code into the `FormatConverterType`, if it is known.
+    pub fn from_u32(code: u32) -> Option<Self> {
+        match code {
+            1668247158 => Some(FormatConverterType::AUConverter),
+            1853191280 => Some(FormatConverterType::NewTimePitch),
+            1953329268 => Some(FormatConverterType::TimePitch),
+            1684366962 => Some(FormatConverterType::DeferredRenderer),
+            1936747636 => Some(FormatConverterType::Splitter),
+            1835364967 => Some(FormatConverterType::Merger),
+            1986097769 => Some(FormatConverterType::Varispeed),
+            1768977519 => Some(FormatConverterType::AUiPodTimeOther),
+            _ => None,
+        }
+    }
+}
+
 /// Audio mixing **AudioUnit** subtypes for **AudioUnit**s provided by Apple.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MixerType {
@@ -318,12 +417,12 @@ pub enum MixerType {
     /// In iPhone OS, the output bus always has two channels.
     ///
     /// **Available** in OS X v10.5 and later.
-    MultiChannelMixer = 1835232632,
+    MultiChannelMixer,
     /// An audio unit that can have any number of input buses, each of which is mono or stereo, and
     /// one stereo output bus.
     ///
     /// **Available** in OS X v10.2 and later.
-    StereoMixer = 1936554098,
+    StereoMixer,
     /// An audio unit that can have any number of input buses and one output bus.
     ///
     /// Each input bus can be mono, in which case it can be panned using 3D coordinates and
@@ -338,7 +437,7 @@ pub enum MixerType {
     /// **Available** in OS X v10.3 and later.
     ///
     /// **Deprecated** in OS X v10.10.
-    Mixer3D = 862219640,
+    Mixer3D,
     /// An audio unit that can have any number of input and output buses with any number of
     /// channels on each bus.
     ///
@@ -352,7 +451,44 @@ pub enum MixerType {
     /// Finally, the audio unit provides a global level control for the matrix as a whole.
     ///
     /// **Available** in OS X v10.3 and later.
-    MatrixMixer = 1836608888,
+    MatrixMixer,
+    /// An audio unit that can have any number of input buses and one output bus, spatializing
+    /// each input in 3D space.
+    ///
+    /// This replaces the deprecated **Mixer3D** and supports head-related transfer function based
+    /// rendering for headphones.
+    ///
+    /// **Available** in OS X v10.10 and later.
+    SpatialMixer,
+    /// The subtype of a mixer unit not listed above, e.g. one provided by a third party,
+    /// given as its four char code.
+    Custom(u32),
+}
+
+impl MixerType {
+    /// Convert the `MixerType` to its associated `u32` subtype code.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            MixerType::MultiChannelMixer => 1835232632,
+            MixerType::StereoMixer => 1936554098,
+            MixerType::Mixer3D => 862219640,
+            MixerType::MatrixMixer => 1836608888,
+            MixerType::SpatialMixer => 862217581,
+            MixerType::Custom(code) => code,
+        }
+    }
+
+    /// Convert a `u32` subtype code into the `MixerType`, or `Custom` if it is not listed.
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            1835232632 => MixerType::MultiChannelMixer,
+            1936554098 => MixerType::StereoMixer,
+            862219640 => MixerType::Mixer3D,
+            1836608888 => MixerType::MatrixMixer,
+            862217581 => MixerType::SpatialMixer,
+            code => MixerType::Custom(code),
+        }
+    }
 }
 
 /// Audio units that serve as sound sources.
@@ -365,12 +501,35 @@ pub enum GeneratorType {
     /// from any thread.
     ///
     /// **Available** in OS X v10.4 and later.
-    ScheduledSoundPlayer = 1936945260,
+    ScheduledSoundPlayer,
     /// A generator unit that is used to play a file. In OS X it presents a custom UI so can be
     /// used in a UI context as well as in a programmatic context.
     ///
     /// **Available** in OS X v10.4 and later.
-    AudioFilePlayer = 1634103404,
+    AudioFilePlayer,
+    /// The subtype of a generator unit not listed above, e.g. one provided by a third party,
+    /// given as its four char code.
+    Custom(u32),
+}
+
+impl GeneratorType {
+    /// Convert the `GeneratorType` to its associated `u32` subtype code.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            GeneratorType::ScheduledSoundPlayer => 1936945260,
+            GeneratorType::AudioFilePlayer => 1634103404,
+            GeneratorType::Custom(code) => code,
+        }
+    }
+
+    /// Convert a `u32` subtype code into the `GeneratorType`, or `Custom` if it is not listed.
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            1936945260 => GeneratorType::ScheduledSoundPlayer,
+            1634103404 => GeneratorType::AudioFilePlayer,
+            code => GeneratorType::Custom(code),
+        }
+    }
 }
 
 /// Audio units that can be played as musical instruments via MIDI control.
@@ -382,12 +541,42 @@ pub enum MusicDeviceType {
     /// It fully supports GM-MIDI and the basic extensions of GS-MIDI
     ///
     /// **Available** in OS X v10.2 and later.
-    DLSSynth = 1684828960,
+    DLSSynth,
     /// A monotimbral instrument unit that functions a a sampler-synthesizer and supports full
     /// interactive editing of its state.
     ///
     /// **Available** in OS X v10.7 and later.
-    Sampler = 1935764848,
+    Sampler,
+    /// A fully GM-compatible multitimbral instrument unit that can load instruments from
+    /// SoundFont or DLS banks.
+    ///
+    /// **Available** in OS X v10.8 and later.
+    MIDISynth,
+    /// The subtype of a music device unit not listed above, e.g. one provided by a third party,
+    /// given as its four char code.
+    Custom(u32),
+}
+
+impl MusicDeviceType {
+    /// Convert the `MusicDeviceType` to its associated `u32` subtype code.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            MusicDeviceType::DLSSynth => 1684828960,
+            MusicDeviceType::Sampler => 1935764848,
+            MusicDeviceType::MIDISynth => 1836284270,
+            MusicDeviceType::Custom(code) => code,
+        }
+    }
+
+    /// Convert a `u32` subtype code into the `MusicDeviceType`, or `Custom` if it is not listed.
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            1684828960 => MusicDeviceType::DLSSynth,
+            1935764848 => MusicDeviceType::Sampler,
+            1836284270 => MusicDeviceType::MIDISynth,
+            code => MusicDeviceType::Custom(code),
+        }
+    }
 }
 
 /// Input/output **AudioUnit** subtypes for **AudioUnit**s provided by Apple.
@@ -433,6 +622,11 @@ pub enum IOType {
     /// Properties](https://developer.apple.com/library/prerelease/mac/documentation/AudioUnit/Reference/AudioUnitPropertiesReference/index.html#//apple_ref/doc/constant_group/Voice_Processing_I_O_Audio_Unit_Properties)
     /// enumeration for the identifiers for this audio unit’s properties.
     ///
+    /// Unlike the other I/O units, input is enabled by default as the echo cancellation requires
+    /// both the microphone input and the output being played. The unit also only supports a
+    /// limited set of formats on its hardware side (mono, at the device's sample rate), converting
+    /// to and from the formats set on the application side.
+    ///
     /// **Available** in OS X v10.7 and later.
     VoiceProcessingIO = 1987078511,
     /// Connects to device hardware for input, output, or simultaneous input and output.
@@ -441,5 +635,22 @@ pub enum IOType {
     ///
     /// See https://developer.apple.com/library/content/documentation/MusicAudio/Conceptual/AudioUnitHostingGuide_iOS/UsingSpecificAudioUnits/UsingSpecificAudioUnits.html
     /// **Available** in iOS.
+    #[cfg(target_os = "ios")]
     RemoteIO = 1919512419,
 }
+
+impl IOType {
+    /// Convert a `u32` subtype code into the `IOType`, if it is known.
+    pub fn from_u32(code: u32) -> Option<Self> {
+        match code {
+            1734700658 => Some(IOType::GenericOutput),
+            1634230636 => Some(IOType::HalOutput),
+            1684366880 => Some(IOType::DefaultOutput),
+            1937339168 => Some(IOType::SystemOutput),
+            1987078511 => Some(IOType::VoiceProcessingIO),
+            #[cfg(target_os = "ios")]
+            1919512419 => Some(IOType::RemoteIO),
+            _ => None,
+        }
+    }
+}
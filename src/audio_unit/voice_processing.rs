@@ -0,0 +1,82 @@
+//! Methods specific to the **VoiceProcessingIO** unit, an I/O unit with built-in echo
+//! cancellation and automatic gain control for voice chat.
+//!
+//! The unit works like the **HalOutput** unit (or **RemoteIO** on iOS), with its input on
+//! element `1` and its output on element `0`. It removes the audio played through its output from
+//! the audio captured by its input, so both the near-end microphone and the far-end audio must
+//! go through the same unit.
+
+use super::{AudioUnit, Element, IOType, Scope};
+use crate::error::Error;
+use sys;
+
+impl AudioUnit {
+    /// Create and initialize a new **VoiceProcessingIO** unit.
+    ///
+    /// Unlike other I/O units, input is enabled by default.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn voice_processing_io() -> Result<AudioUnit, Error> {
+        AudioUnit::new(IOType::VoiceProcessingIO)
+    }
+
+    /// Bypass the echo cancellation and gain control of a **VoiceProcessingIO** unit via
+    /// `kAUVoiceIOProperty_BypassVoiceProcessing`, passing the input through unprocessed.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn set_voice_processing_bypassed(&mut self, bypass: bool) -> Result<(), Error> {
+        let id = sys::kAUVoiceIOProperty_BypassVoiceProcessing;
+        let bypass = bypass as u32;
+        self.set_property(id, Scope::Global, Element::Input, Some(&bypass))
+    }
+
+    /// Whether the echo cancellation and gain control of a **VoiceProcessingIO** unit are
+    /// bypassed.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn is_voice_processing_bypassed(&self) -> Result<bool, Error> {
+        let id = sys::kAUVoiceIOProperty_BypassVoiceProcessing;
+        let bypass: u32 = self.get_property(id, Scope::Global, Element::Input)?;
+        Ok(bypass != 0)
+    }
+
+    /// Enable or disable the automatic gain control of a **VoiceProcessingIO** unit via
+    /// `kAUVoiceIOProperty_VoiceProcessingEnableAGC`. Enabled by default.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn set_agc_enabled(&mut self, enable: bool) -> Result<(), Error> {
+        let id = sys::kAUVoiceIOProperty_VoiceProcessingEnableAGC;
+        let enable = enable as u32;
+        self.set_property(id, Scope::Global, Element::Input, Some(&enable))
+    }
+
+    /// Whether the automatic gain control of a **VoiceProcessingIO** unit is enabled.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn is_agc_enabled(&self) -> Result<bool, Error> {
+        let id = sys::kAUVoiceIOProperty_VoiceProcessingEnableAGC;
+        let enable: u32 = self.get_property(id, Scope::Global, Element::Input)?;
+        Ok(enable != 0)
+    }
+
+    /// Mute the output of a **VoiceProcessingIO** unit via `kAUVoiceIOProperty_MuteOutput`.
+    ///
+    /// Prefer this over writing silence in the render callback, as the echo canceller keeps
+    /// adapting to the far-end audio while the output is muted.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn set_muted(&mut self, mute: bool) -> Result<(), Error> {
+        let id = sys::kAUVoiceIOProperty_MuteOutput;
+        let mute = mute as u32;
+        self.set_property(id, Scope::Global, Element::Output, Some(&mute))
+    }
+
+    /// Whether the output of a **VoiceProcessingIO** unit is muted.
+    ///
+    /// **Available** in OS X v10.7 and later.
+    pub fn is_muted(&self) -> Result<bool, Error> {
+        let id = sys::kAUVoiceIOProperty_MuteOutput;
+        let mute: u32 = self.get_property(id, Scope::Global, Element::Output)?;
+        Ok(mute != 0)
+    }
+}
@@ -1,5 +1,6 @@
 //! This module is an attempt at rustifying the OSStatus result.
 
+pub use self::au_graph::Error as AUGraphError;
 pub use self::audio::Error as AudioError;
 pub use self::audio_codec::Error as AudioCodecError;
 pub use self::audio_format::Error as AudioFormatError;
@@ -236,6 +237,49 @@ pub mod audio_unit {
     }
 }
 
+pub mod au_graph {
+    use sys::OSStatus;
+
+    #[derive(Copy, Clone, Debug)]
+    pub enum Error {
+        NodeNotFound = -10860,
+        InvalidConnection = -10861,
+        OutputNodeError = -10862,
+        InvalidAudioUnit = -10864,
+        Unknown,
+    }
+
+    impl Error {
+        pub fn from_os_status(os_status: OSStatus) -> Result<(), Error> {
+            match os_status {
+                0 => Ok(()),
+                -10860 => Err(Error::NodeNotFound),
+                -10861 => Err(Error::InvalidConnection),
+                -10862 => Err(Error::OutputNodeError),
+                -10864 => Err(Error::InvalidAudioUnit),
+                _ => Err(Error::Unknown),
+            }
+        }
+
+        pub fn as_os_status(&self) -> OSStatus {
+            *self as OSStatus
+        }
+    }
+
+    impl ::std::fmt::Display for Error {
+        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+            let description = match *self {
+                Error::NodeNotFound => "The node was not found in the graph",
+                Error::InvalidConnection => "Invalid connection between graph nodes",
+                Error::OutputNodeError => "The graph may only contain a single output node",
+                Error::InvalidAudioUnit => "The node's audio unit is invalid",
+                Error::Unknown => "Unknown error occurred",
+            };
+            write!(f, "{}", description)
+        }
+    }
+}
+
 /// A wrapper around all possible Core Audio errors.
 #[derive(Copy, Clone, Debug)]
 pub enum Error {
@@ -247,10 +291,25 @@ pub enum Error {
     NonInterleavedInputOnlySupportsMono,
     UnsupportedSampleRate,
     UnsupportedStreamFormat,
+    BufferOffsetOutOfRange,
+    NotAMusicDevice,
+    StreamFormatChangedSinceCallbackInstalled,
+    HogModeOwnedByOtherProcess(i32),
+    BufferLengthMismatch,
+    InvalidFourCharCode,
+    RenderBuffersUnavailable,
+    ParameterValueOutOfRange,
+    SampleRateChangeTimedOut,
+    NoDefaultDevice,
+    TooManyFramesToProcess,
+    InputCallbackAlreadySet,
+    CaptureTimedOut,
+    MaximumFramesPerSliceUnknown,
     Audio(AudioError),
     AudioCodec(AudioCodecError),
     AudioFormat(AudioFormatError),
     AudioUnit(AudioUnitError),
+    AUGraph(AUGraphError),
     Unknown(OSStatus),
 }
 
@@ -261,6 +320,8 @@ impl Error {
             0 => Ok(()),
             -1500 => Err(Error::Unspecified),
             -1501 => Err(Error::SystemSoundClientMessageTimedOut),
+            // Checked before the generic audio unit errors, as it deserves an actionable message.
+            -10874 => Err(Error::TooManyFramesToProcess),
             _ => {
                 match AudioError::from_os_status(os_status) {
                     Ok(()) => return Ok(()),
@@ -282,6 +343,11 @@ impl Error {
                     Err(AudioUnitError::Unknown) => (),
                     Err(err) => return Err(Error::AudioUnit(err)),
                 }
+                match AUGraphError::from_os_status(os_status) {
+                    Ok(()) => return Ok(()),
+                    Err(AUGraphError::Unknown) => (),
+                    Err(err) => return Err(Error::AUGraph(err)),
+                }
                 Err(Error::Unknown(os_status))
             }
         }
@@ -294,9 +360,11 @@ impl Error {
             Error::NoMatchingDefaultAudioUnitFound => -1500,
             Error::RenderCallbackBufferFormatDoesNotMatchAudioUnitStreamFormat => -1500,
             Error::SystemSoundClientMessageTimedOut => -1501,
+            Error::TooManyFramesToProcess => AudioUnitError::TooManyFramesToProcess as OSStatus,
             Error::Audio(err) => err as OSStatus,
             Error::AudioCodec(err) => err as OSStatus,
             Error::AudioUnit(err) => err as OSStatus,
+            Error::AUGraph(err) => err as OSStatus,
             _ => -1500,
         }
     }
@@ -314,10 +382,27 @@ impl ::std::fmt::Display for Error {
             Error::NonInterleavedInputOnlySupportsMono => write!(f, "In non-interleaved mode input only supports one channel"),
             Error::UnsupportedSampleRate => write!(f, "The requested sample rate is not available"),
             Error::UnsupportedStreamFormat => write!(f, "The requested stream format is not available"),
+            Error::BufferOffsetOutOfRange => write!(f, "The buffer offset exceeds the maximum frames per slice"),
+            Error::NotAMusicDevice => write!(f, "The audio unit is not a music device or music effect"),
+            Error::StreamFormatChangedSinceCallbackInstalled =>
+                write!(f, "The `AudioUnit` `StreamFormat` changed since the callback was installed and no longer matches its buffer format"),
+            Error::HogModeOwnedByOtherProcess(pid) => write!(f, "Exclusive access to the device is owned by another process (pid {})", pid),
+            Error::BufferLengthMismatch => write!(f, "The lengths of the given sample buffers do not match"),
+            Error::InvalidFourCharCode => write!(f, "A four character code must consist of exactly four ASCII characters"),
+            Error::RenderBuffersUnavailable => write!(f, "The render callback was called without valid buffers, e.g. because the device was disconnected"),
+            Error::ParameterValueOutOfRange => write!(f, "The value is outside of the range of the parameter"),
+            Error::SampleRateChangeTimedOut => write!(f, "Timed out waiting for the device to change its sample rate"),
+            Error::NoDefaultDevice => write!(f, "There is no default audio device"),
+            Error::TooManyFramesToProcess =>
+                write!(f, "More frames were requested than the audio unit's MaximumFramesPerSlice; raise it via `set_maximum_frames_per_slice` before initializing the unit"),
+            Error::InputCallbackAlreadySet => write!(f, "An input callback is already installed on the audio unit"),
+            Error::CaptureTimedOut => write!(f, "Timed out waiting for the audio unit to deliver input"),
+            Error::MaximumFramesPerSliceUnknown => write!(f, "The maximum frames per slice of the audio unit could not be read"),
             Error::Audio(ref err) => write!(f, "{}", err),
             Error::AudioCodec(ref err) => write!(f, "{}", err),
             Error::AudioFormat(ref err) => write!(f, "{}", err),
             Error::AudioUnit(ref err) => write!(f, "{}", err),
+            Error::AUGraph(ref err) => write!(f, "{}", err),
             Error::Unknown(_) => write!(f, "An unknown error unknown to the coreaudio-rs API occurred"),
         }
     }
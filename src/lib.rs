@@ -18,3 +18,4 @@ pub use error::Error;
 #[cfg(feature = "audio_unit")]
 pub mod audio_unit;
 pub mod error;
+pub mod util;
@@ -0,0 +1,121 @@
+//! Helpers for converting sample data between interleaved and non-interleaved (planar) layouts.
+//!
+//! These complement the `Interleaved` and `NonInterleaved` render callback buffer types of the
+//! `audio_unit` module, e.g. when bridging interleaved file I/O with planar processing. They only
+//! depend on the sample data, so they are available regardless of the enabled features.
+
+use crate::error::Error;
+
+/// Interleave the given channel `planes` into `out`.
+///
+/// All planes must have the same number of frames, and `out` must hold exactly one sample per
+/// channel per frame. Otherwise `Error::BufferLengthMismatch` is returned and `out` is left
+/// untouched.
+///
+/// ```
+/// let left = [0, 2, 4];
+/// let right = [1, 3, 5];
+/// let mut out = [0; 6];
+/// coreaudio::util::interleave(&[&left, &right], &mut out).unwrap();
+/// assert_eq!(out, [0, 1, 2, 3, 4, 5]);
+/// ```
+pub fn interleave<S: Copy>(planes: &[&[S]], out: &mut [S]) -> Result<(), Error> {
+    let channels = planes.len();
+    let frames = planes.first().map_or(0, |plane| plane.len());
+    if planes.iter().any(|plane| plane.len() != frames) || out.len() != frames * channels {
+        return Err(Error::BufferLengthMismatch);
+    }
+    for (channel, plane) in planes.iter().enumerate() {
+        for (frame, &sample) in plane.iter().enumerate() {
+            out[frame * channels + channel] = sample;
+        }
+    }
+    Ok(())
+}
+
+/// Deinterleave the samples in `src` into the given channel `planes`.
+///
+/// All planes must have the same number of frames, and `src` must hold exactly one sample per
+/// channel per frame. Otherwise `Error::BufferLengthMismatch` is returned and the planes are left
+/// untouched.
+///
+/// ```
+/// let src = [0, 1, 2, 3, 4, 5];
+/// let mut left = [0; 3];
+/// let mut right = [0; 3];
+/// coreaudio::util::deinterleave(&src, &mut [&mut left, &mut right]).unwrap();
+/// assert_eq!(left, [0, 2, 4]);
+/// assert_eq!(right, [1, 3, 5]);
+/// ```
+pub fn deinterleave<S: Copy>(src: &[S], planes: &mut [&mut [S]]) -> Result<(), Error> {
+    let channels = planes.len();
+    let frames = planes.first().map_or(0, |plane| plane.len());
+    if planes.iter().any(|plane| plane.len() != frames) || src.len() != frames * channels {
+        return Err(Error::BufferLengthMismatch);
+    }
+    for (channel, plane) in planes.iter_mut().enumerate() {
+        for (frame, sample) in plane.iter_mut().enumerate() {
+            *sample = src[frame * channels + channel];
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deinterleave, interleave};
+    use crate::error::Error;
+
+    #[test]
+    fn interleave_rejects_planes_of_different_lengths() {
+        let left = [0, 2, 4];
+        let right = [1, 3];
+        let mut out = [9; 5];
+        let result = interleave(&[&left, &right], &mut out);
+        assert!(matches!(result, Err(Error::BufferLengthMismatch)));
+        assert_eq!(out, [9; 5]);
+    }
+
+    #[test]
+    fn interleave_rejects_output_of_wrong_length() {
+        let left = [0, 2, 4];
+        let right = [1, 3, 5];
+        let mut out = [9; 7];
+        let result = interleave(&[&left, &right], &mut out);
+        assert!(matches!(result, Err(Error::BufferLengthMismatch)));
+        assert_eq!(out, [9; 7]);
+    }
+
+    #[test]
+    fn deinterleave_rejects_planes_of_different_lengths() {
+        let src = [0, 1, 2, 3, 4, 5];
+        let mut left = [9; 3];
+        let mut right = [9; 2];
+        let result = deinterleave(&src, &mut [&mut left, &mut right]);
+        assert!(matches!(result, Err(Error::BufferLengthMismatch)));
+        assert_eq!(left, [9; 3]);
+        assert_eq!(right, [9; 2]);
+    }
+
+    #[test]
+    fn deinterleave_rejects_source_of_wrong_length() {
+        let src = [0, 1, 2, 3, 4];
+        let mut left = [9; 3];
+        let mut right = [9; 3];
+        let result = deinterleave(&src, &mut [&mut left, &mut right]);
+        assert!(matches!(result, Err(Error::BufferLengthMismatch)));
+        assert_eq!(left, [9; 3]);
+        assert_eq!(right, [9; 3]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let src = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut left = [0.0; 3];
+        let mut right = [0.0; 3];
+        deinterleave(&src, &mut [&mut left, &mut right]).unwrap();
+        let mut out = [0.0; 6];
+        interleave(&[&left, &right], &mut out).unwrap();
+        assert_eq!(out, src);
+    }
+}